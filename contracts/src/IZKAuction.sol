@@ -5,6 +5,27 @@ pragma solidity ^0.8.20;
 contract IZKAuction {
     error ZKAuction__InsufficientCollateral();
     error ZKAuction__InsufficientPurchaseTokens();
+    error ZKAuction__TooManyBids();
+    error ZKAuction__TooManyOffers();
+
+    /// @dev Emitted once an auction proof is verified, with the results it committed to.
+    event AuctionCleared(uint256 clearingPrice, uint256 clearedVolume);
+
+    /// @dev The convention the interest accrued over `dayCount` days is computed with.
+    enum DayCountConvention {
+        // Every year is taken to have 360 days
+        Thirty360,
+        // Every year is taken to have 365 days
+        Actual365
+    }
+
+    /// @dev How the clearing price is computed once the market intersects.
+    enum AuctionMode {
+        // The average of the marginal bid and offer prices
+        SecondPriceMidpoint,
+        // The average price of the orders crossing at the midpoint, weighted by their amounts
+        VolumeWeighted
+    }
 
     /// @dev The `AuctionParameters` struct is used to store the parameters of an auction.
     struct AuctionParameters {
@@ -18,6 +39,10 @@ contract IZKAuction {
         uint256 collateralPrice;
         // Number of days between auction and maturity dates, used to compute servicing fees and repurchase prices
         uint256 dayCount;
+        /// The convention the repurchase prices are computed with
+        DayCountConvention dayCountConvention;
+        /// How the clearing price is computed
+        AuctionMode auctionMode;
     }
 
     /// @dev The public values encoded as a struct that can be easily deserialized inside Solidity.
@@ -30,7 +55,13 @@ contract IZKAuction {
         bytes32 accOffersHash;
         /// Hashed together information on the tokens involved
         bytes32 auctionParametersHash;
+        /// Hashed together constants that govern how the auction is run
+        bytes32 constantsCommitment;
         /// The root of the auction results tree
         bytes32 auctionResultRoot;
+        /// The clearing price as a percentage with 9 decimal places, or zero if the market did not clear
+        uint256 clearingPrice;
+        /// The total amount that was lent, or zero if the market did not clear
+        uint256 clearedVolume;
     }
 }
@@ -15,14 +15,22 @@ contract ZKAuction is IZKAuction{
     ///      https://github.com/succinctlabs/sp1-contracts/tree/main/contracts/deployments
     address public verifier;
 
+    /// @notice The maximum number of bids, and of offers, the zkAuction program places at once.
+    /// @dev Must match `MAX_ORDERS` in the zkAuction program, which cannot prove auctions with more orders.
+    uint256 public constant MAX_ORDERS = 10_000;
+
     /// @notice The accumulated bids hash
     bytes32 public accBidsHash = bytes32(0x0000000000000000000000000000000000000000000000000000000000000001);
     /// @notice Mapping of bid IDs to the amount of collateral locked, indexed by `abi.encodePacked(bidder, orderId)`
     mapping(bytes32 => uint256) lockedBids;
+    /// @notice The number of bids with collateral locked
+    uint256 public placedBids;
     /// @notice The accumulated offers hash
     bytes32 public accOffersHash = bytes32(0x0000000000000000000000000000000000000000000000000000000000000001);
     /// @notice Mapping of offer IDs to the amount of purchase tokens locked, indexed by `abi.encodePacked(offeror, orderId)`
     mapping(bytes32 => uint256) lockedOffers;
+    /// @notice The number of offers with purchase tokens locked
+    uint256 public placedOffers;
 
     /// @notice The purchase token address
     address public purchaseToken;
@@ -30,19 +38,33 @@ contract ZKAuction is IZKAuction{
     address public collateralToken;
     /// @notice The number of days between auction and maturity dates
     uint256 public dayCount;
+    /// @notice The convention the repurchase prices are computed with
+    DayCountConvention public dayCountConvention;
+    /// @notice How the clearing price is computed
+    AuctionMode public auctionMode;
 
     /// @notice The root of the auction results tree
     bytes32 public auctionResultRoot;
+    /// @notice The clearing price of the verified auction, zero if the market did not clear
+    uint256 public clearingPrice;
+    /// @notice The total amount lent in the verified auction, zero if the market did not clear
+    uint256 public clearedVolume;
 
     /// @notice The verification key for the zkAuction program.
     bytes32 public zkAuctionProgramVKey;
 
-    constructor(address _purchaseToken, address _collateralToken, uint256 _dayCount, address _verifier, bytes32 _zkAuctionProgramVKey) {
+    /// @notice The commitment to the constants the zkAuction program is expected to run the auction with.
+    bytes32 public constantsCommitment;
+
+    constructor(address _purchaseToken, address _collateralToken, uint256 _dayCount, DayCountConvention _dayCountConvention, AuctionMode _auctionMode, address _verifier, bytes32 _zkAuctionProgramVKey, bytes32 _constantsCommitment) {
         verifier = _verifier;
         zkAuctionProgramVKey = _zkAuctionProgramVKey;
+        constantsCommitment = _constantsCommitment;
         purchaseToken = _purchaseToken;
         collateralToken = _collateralToken;
         dayCount = _dayCount;
+        dayCountConvention = _dayCountConvention;
+        auctionMode = _auctionMode;
     }
 
     /// @notice Submits a bid to borrow an amount of money for a specific interest rate by locking the collateral amount.
@@ -50,11 +72,25 @@ contract ZKAuction is IZKAuction{
     /// @param _bidPriceHash The hash of the bid price.
     /// @param _amount The amount of purchase tokens to borrow.
     /// @param _collateralAmount The amount of collateral to lock.
-    function lockBid(uint96 _id, bytes32 _bidPriceHash, uint256 _amount, uint256 _collateralAmount) public {
+    /// @param _isRollover Whether the bid rolls over an existing loan.
+    /// @param _rolloverPairOffTermRepoServicer The term repo servicer of the loan being rolled over, zero if not a rollover.
+    function lockBid(
+        uint96 _id,
+        bytes32 _bidPriceHash,
+        uint256 _amount,
+        uint256 _collateralAmount,
+        bool _isRollover,
+        address _rolloverPairOffTermRepoServicer
+    ) public {
         // Lock the collateral amount
         uint256 lockedAmount = lockedBids[bytes32(abi.encodePacked(msg.sender, _id))];
         uint256 newLockedAmount = lockedAmount + _collateralAmount;
         lockedBids[bytes32(abi.encodePacked(msg.sender, _id))] = newLockedAmount;
+        // A bid is placed while it has collateral locked
+        if (lockedAmount == 0 && newLockedAmount != 0) {
+            if (placedBids == MAX_ORDERS) revert ZKAuction__TooManyBids();
+            placedBids++;
+        }
         // Update the hash chain
         accBidsHash = keccak256(
             abi.encodePacked(
@@ -63,7 +99,9 @@ contract ZKAuction is IZKAuction{
                 _id,
                 _bidPriceHash,
                 _amount,
-                newLockedAmount
+                newLockedAmount,
+                _isRollover,
+                _rolloverPairOffTermRepoServicer
             )
         );
     }
@@ -73,12 +111,23 @@ contract ZKAuction is IZKAuction{
     /// @param _bidPriceHash The hash of the bid price.
     /// @param _amount The amount of purchase tokens to borrow.
     /// @param _unlockCollateralAmount The amount of collateral to unlock.
-    function unlockBid(uint96 _id, bytes32 _bidPriceHash, uint256 _amount, uint256 _unlockCollateralAmount) public {
+    /// @param _isRollover Whether the bid rolls over an existing loan.
+    /// @param _rolloverPairOffTermRepoServicer The term repo servicer of the loan being rolled over, zero if not a rollover.
+    function unlockBid(
+        uint96 _id,
+        bytes32 _bidPriceHash,
+        uint256 _amount,
+        uint256 _unlockCollateralAmount,
+        bool _isRollover,
+        address _rolloverPairOffTermRepoServicer
+    ) public {
         // Unlock the collateral amount
         uint256 lockedAmount = lockedBids[bytes32(abi.encodePacked(msg.sender, _id))];
         if (_unlockCollateralAmount > lockedAmount) revert ZKAuction__InsufficientCollateral();
         uint256 newLockedAmount = lockedAmount - _unlockCollateralAmount;
         lockedBids[bytes32(abi.encodePacked(msg.sender, _id))] = newLockedAmount;
+        // Unlocking all the collateral cancels the bid
+        if (lockedAmount != 0 && newLockedAmount == 0) placedBids--;
         // Update the hash chain
         accBidsHash = keccak256(
             abi.encodePacked(
@@ -87,7 +136,9 @@ contract ZKAuction is IZKAuction{
                 _id,
                 _bidPriceHash,
                 _amount,
-                newLockedAmount
+                newLockedAmount,
+                _isRollover,
+                _rolloverPairOffTermRepoServicer
             )
         );
     }
@@ -118,6 +169,11 @@ contract ZKAuction is IZKAuction{
         uint256 lockedAmount = lockedOffers[bytes32(abi.encodePacked(msg.sender, _id))];
         uint256 newLockedAmount = lockedAmount + _amount;
         lockedOffers[bytes32(abi.encodePacked(msg.sender, _id))] = newLockedAmount;
+        // An offer is placed while it has purchase tokens locked
+        if (lockedAmount == 0 && newLockedAmount != 0) {
+            if (placedOffers == MAX_ORDERS) revert ZKAuction__TooManyOffers();
+            placedOffers++;
+        }
         // Update the hash chain
         accOffersHash = keccak256(
             abi.encodePacked(
@@ -140,6 +196,8 @@ contract ZKAuction is IZKAuction{
         if (_unlockPurchaseAmount > lockedAmount) revert ZKAuction__InsufficientPurchaseTokens();
         uint256 newLockedAmount = lockedAmount - _unlockPurchaseAmount;
         lockedOffers[bytes32(abi.encodePacked(msg.sender, _id))] = newLockedAmount;
+        // Unlocking all the purchase tokens cancels the offer
+        if (lockedAmount != 0 && newLockedAmount == 0) placedOffers--;
         // Update the hash chain
         accOffersHash = keccak256(
             abi.encodePacked(
@@ -170,20 +228,27 @@ contract ZKAuction is IZKAuction{
     }
 
     /// @notice The entrypoint for verifying the proof for an auction.
+    /// @param _clearingPrice The clearing price committed by the proof, zero if the market did not clear.
+    /// @param _clearedVolume The total amount lent committed by the proof, zero if the market did not clear.
     /// @param _proofBytes The encoded proof.
-    function verifyAuctionProof(bytes calldata _proofBytes)
-        public
-        view
-    {
+    function verifyAuctionProof(uint256 _clearingPrice, uint256 _clearedVolume, bytes calldata _proofBytes) public {
         PublicValuesStruct memory publicValues = PublicValuesStruct(
             msg.sender,
             accBidsHash,
             accOffersHash,
             _getAuctionParametersHash(),
-            auctionResultRoot
+            constantsCommitment,
+            auctionResultRoot,
+            _clearingPrice,
+            _clearedVolume
         );
 
         ISP1Verifier(verifier).verifyProof(zkAuctionProgramVKey, abi.encode(publicValues), _proofBytes);
+
+        // Store the results the proof committed to
+        clearingPrice = _clearingPrice;
+        clearedVolume = _clearedVolume;
+        emit AuctionCleared(_clearingPrice, _clearedVolume);
     }
 
     function _getAuctionParametersHash() private view returns (bytes32) {
@@ -192,7 +257,9 @@ contract ZKAuction is IZKAuction{
             _getPurchaseTokenPrice(),
             collateralToken,
             _getCollateralTokenPrice(),
-            dayCount
+            dayCount,
+            dayCountConvention,
+            auctionMode
         )));
     }
 
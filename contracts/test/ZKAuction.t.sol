@@ -4,6 +4,7 @@ pragma solidity ^0.8.20;
 import {Test, console} from "forge-std/Test.sol";
 import {stdJson} from "forge-std/StdJson.sol";
 import {ZKAuction} from "../src/ZKAuction.sol";
+import {IZKAuction} from "../src/IZKAuction.sol";
 import {SP1VerifierGateway} from "@sp1-contracts/SP1VerifierGateway.sol";
 
 struct SP1ProofFixtureJson {
@@ -32,7 +33,7 @@ contract ZKAuctionTest is Test {
     function setUp() public {
         //SP1ProofFixtureJson memory fixture = loadFixture();
         verifier = address(new SP1VerifierGateway(address(1)));
-        zkAuction = new ZKAuction(purchaseToken, collateralToken, dayCount, verifier, bytes32(0));
+        zkAuction = new ZKAuction(purchaseToken, collateralToken, dayCount, ZKAuction.DayCountConvention.Thirty360, ZKAuction.AuctionMode.SecondPriceMidpoint, verifier, bytes32(0), bytes32(0));
     }
 
     /* function test_ValidAuctionProof() public {
@@ -40,7 +41,7 @@ contract ZKAuctionTest is Test {
 
         vm.mockCall(verifier, abi.encodeWithSelector(SP1VerifierGateway.verifyProof.selector), abi.encode(true));
 
-        zkAuction.verifyAuctionProof(fixture.proof);
+        zkAuction.verifyAuctionProof(0, 0, fixture.proof);
     }
 
     function testFail_InvalidAuctionProof() public view {
@@ -49,21 +50,69 @@ contract ZKAuctionTest is Test {
         // Create a fake proof.
         bytes memory fakeProof = new bytes(fixture.proof.length);
 
-        zkAuction.verifyAuctionProof(fakeProof);
+        zkAuction.verifyAuctionProof(0, 0, fakeProof);
     } */
 
+    function test_VerifyAuctionProofStoresResults() public {
+        vm.mockCall(verifier, abi.encodeWithSelector(SP1VerifierGateway.verifyProof.selector), abi.encode());
+
+        vm.expectEmit(address(zkAuction));
+        emit IZKAuction.AuctionCleared(1_050_000_000, 1_000);
+        zkAuction.verifyAuctionProof(1_050_000_000, 1_000, "");
+
+        assertEq(zkAuction.clearingPrice(), 1_050_000_000);
+        assertEq(zkAuction.clearedVolume(), 1_000);
+    }
+
     function test_LockBid() public {
-        zkAuction.lockBid(1, bytes32(0), 100, 100);
-        zkAuction.lockBid(1, bytes32(0), 100, 100);
+        zkAuction.lockBid(1, bytes32(0), 100, 100, false, address(0));
+        zkAuction.lockBid(1, bytes32(0), 100, 100, false, address(0));
+    }
+
+    function test_LockRolloverBid() public {
+        address termRepoServicer = address(0x450);
+        bytes32 accBidsHash = zkAuction.accBidsHash();
+        zkAuction.lockBid(1, bytes32(0), 100, 100, true, termRepoServicer);
+
+        // The rollover fields are part of the bid hash chain
+        assertEq(
+            zkAuction.accBidsHash(),
+            keccak256(
+                abi.encodePacked(
+                    accBidsHash, address(this), uint96(1), bytes32(0), uint256(100), uint256(100), true, termRepoServicer
+                )
+            )
+        );
+    }
+
+    function test_LockBidAboveMaxOrders() public {
+        for (uint256 i = 0; i < zkAuction.MAX_ORDERS(); i++) {
+            vm.prank(address(uint160(i + 1)));
+            zkAuction.lockBid(1, bytes32(0), 100, 100, false, address(0));
+        }
+        assertEq(zkAuction.placedBids(), zkAuction.MAX_ORDERS());
+
+        // Updating a placed bid does not count towards the limit
+        vm.prank(address(1));
+        zkAuction.lockBid(1, bytes32(0), 100, 100, false, address(0));
+
+        vm.expectRevert(IZKAuction.ZKAuction__TooManyBids.selector);
+        zkAuction.lockBid(2, bytes32(0), 100, 100, false, address(0));
+
+        // Cancelling a bid frees its place
+        vm.prank(address(1));
+        zkAuction.unlockBid(1, bytes32(0), 100, 200, false, address(0));
+        zkAuction.lockBid(2, bytes32(0), 100, 100, false, address(0));
+        assertEq(zkAuction.placedBids(), zkAuction.MAX_ORDERS());
     }
 
     function test_UnlockBid() public {
-        zkAuction.lockBid(1, bytes32(0), 100, 100);
-        zkAuction.unlockBid(1, bytes32(0), 100, 100);
+        zkAuction.lockBid(1, bytes32(0), 100, 100, false, address(0));
+        zkAuction.unlockBid(1, bytes32(0), 100, 100, false, address(0));
     }
 
     function test_RevealBid() public {
-        zkAuction.lockBid(1, bytes32(0), 100, 100);
+        zkAuction.lockBid(1, bytes32(0), 100, 100, false, address(0));
         zkAuction.revealBid(1, 100, 100);
     }
 
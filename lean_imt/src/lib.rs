@@ -1,15 +1,27 @@
 //! This module contains a lean incremental Merkle tree implementation which follows
 //! [Semaphore's implementation](https://hackmd.io/@vplasencia/S1whLBN16)
-use alloy_primitives::{keccak256, B256};
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+use alloc::{collections::BTreeMap, vec, vec::Vec};
+use alloy_primitives::{keccak256, B256, U256};
+use alloy_sol_types::{sol, SolValue};
+use core::{error, fmt};
+use subtle::{Choice, ConstantTimeEq};
 
 /// A lean incremental Merkle tree is an append-only merkle which minimizes the number of hash calculations
 ///
 /// This structure represents an append-only Merkle tree that minimizes the number of hash calculations.
 /// It stores nodes at each level of the tree, allowing for efficient updates and proof generation.
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub struct LeanIncrementalMerkleTree {
     /// Stores the nodes of the tree. Each inner `Vec` represents a level in the tree.
     /// The first `Vec` (index 0) contains the leaves, and the last `Vec` contains the root.
     nodes: Vec<Vec<B256>>,
+    /// The size of the tree and its root after every batch of insertions, in insertion order.
+    #[cfg(feature = "history")]
+    roots: Vec<(usize, B256)>,
 }
 
 impl Default for LeanIncrementalMerkleTree {
@@ -23,6 +35,7 @@ impl Default for LeanIncrementalMerkleTree {
 ///
 /// This struct contains all the necessary information to verify the inclusion
 /// of a specific `leaf` in the Merkle tree defined by the `root`.
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub struct LeanIMTMerkleProof {
     /// The root hash of the Merkle tree.
     pub root: B256,
@@ -34,6 +47,108 @@ pub struct LeanIMTMerkleProof {
     pub siblings: Vec<B256>,
 }
 
+sol! {
+    /// The ABI representation of a `LeanIMTMerkleProof`, as consumed by a Solidity verifier.
+    struct SolLeanIMTMerkleProof {
+        /// The root hash of the Merkle tree.
+        bytes32 root;
+        /// The leaf hash for which the proof is generated.
+        bytes32 leaf;
+        /// The index of the leaf in the tree.
+        uint256 index;
+        /// The sibling hashes needed to reconstruct the path to the root.
+        bytes32[] siblings;
+    }
+}
+
+impl LeanIMTMerkleProof {
+    /// ABI-encodes the proof as a `SolLeanIMTMerkleProof`, so it can be verified onchain.
+    pub fn abi_encode(&self) -> Vec<u8> {
+        SolLeanIMTMerkleProof::from(self).abi_encode()
+    }
+}
+
+impl fmt::Display for LeanIMTMerkleProof {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "root: {}", self.root)?;
+        writeln!(f, "leaf: {}", self.leaf)?;
+        writeln!(f, "index: {}", self.index)?;
+        write!(f, "siblings: {}", self.siblings.len())?;
+        for (level, sibling) in self.siblings.iter().enumerate() {
+            write!(f, "\n  [{}] {}", level, sibling)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<&LeanIMTMerkleProof> for SolLeanIMTMerkleProof {
+    fn from(proof: &LeanIMTMerkleProof) -> Self {
+        Self {
+            root: proof.root,
+            leaf: proof.leaf,
+            index: U256::from(proof.index),
+            siblings: proof.siblings.clone(),
+        }
+    }
+}
+
+/// Errors that can occur when operating on a `LeanIncrementalMerkleTree`.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum LeanImtError {
+    /// The requested leaf index is not below the size of the tree.
+    IndexOutOfBounds { index: usize, size: usize },
+    /// The operation requires at least one leaf, but the tree is empty.
+    EmptyTree,
+    /// The number of levels, or of nodes at some level, does not match the number of leaves.
+    InvalidShape,
+    /// The node at the given level and index is not the hash of its children.
+    InconsistentNode { level: usize, index: usize },
+    /// The leaf at the given index is zero, which `TreeBuildOptions::require_nonzero` rejects.
+    ZeroLeaf { index: usize },
+    /// The leaf at `index` repeats the one at `first_index`, which `TreeBuildOptions::require_unique` rejects.
+    DuplicateLeaf { first_index: usize, index: usize },
+}
+
+impl fmt::Display for LeanImtError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LeanImtError::IndexOutOfBounds { index, size } => write!(
+                f,
+                "the leaf at index '{}' does not exist in this tree of size {}",
+                index, size
+            ),
+            LeanImtError::EmptyTree => write!(f, "the tree has no leaves"),
+            LeanImtError::InvalidShape => {
+                write!(f, "the tree levels do not match its number of leaves")
+            }
+            LeanImtError::InconsistentNode { level, index } => write!(
+                f,
+                "the node at level {} and index {} does not match its children",
+                level, index
+            ),
+            LeanImtError::ZeroLeaf { index } => {
+                write!(f, "the leaf at index '{}' is zero", index)
+            }
+            LeanImtError::DuplicateLeaf { first_index, index } => write!(
+                f,
+                "the leaves at indices '{}' and '{}' are the same",
+                first_index, index
+            ),
+        }
+    }
+}
+
+impl error::Error for LeanImtError {}
+
+/// The checks `LeanIncrementalMerkleTree::try_new` runs on the leaves before building the tree.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct TreeBuildOptions {
+    /// Rejects zero leaves.
+    pub require_nonzero: bool,
+    /// Rejects leaves that appear more than once.
+    pub require_unique: bool,
+}
+
 impl LeanIncrementalMerkleTree {
     /// Create a new lean incremental Merkle tree containing the provided `leaves`
     ///
@@ -47,6 +162,8 @@ impl LeanIncrementalMerkleTree {
     pub fn new(leaves: &[B256]) -> Self {
         let mut tree: LeanIncrementalMerkleTree = Self {
             nodes: vec![vec![]],
+            #[cfg(feature = "history")]
+            roots: vec![],
         };
         if !leaves.is_empty() {
             tree.insert_many(leaves);
@@ -54,6 +171,90 @@ impl LeanIncrementalMerkleTree {
         tree
     }
 
+    /// Create a new lean incremental Merkle tree containing the provided `leaves`, after checking them as
+    /// requested by `options`.
+    ///
+    /// For applications where a zero or repeated leaf is a bug. `new` remains the unchecked fast path.
+    ///
+    /// # Arguments
+    ///
+    /// * `leaves` - A vector of leaf hashes to initialize the tree with.
+    /// * `options` - The checks to run on the leaves.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing either the new `LeanIncrementalMerkleTree`, or the `LeanImtError` identifying
+    /// the first offending leaf.
+    pub fn try_new(leaves: &[B256], options: TreeBuildOptions) -> Result<Self, LeanImtError> {
+        let mut seen: BTreeMap<B256, usize> = BTreeMap::new();
+        for (index, leaf) in leaves.iter().enumerate() {
+            if options.require_nonzero && leaf.is_zero() {
+                return Err(LeanImtError::ZeroLeaf { index });
+            }
+
+            if options.require_unique {
+                if let Some(first_index) = seen.insert(*leaf, index) {
+                    return Err(LeanImtError::DuplicateLeaf { first_index, index });
+                }
+            }
+        }
+
+        Ok(Self::new(leaves))
+    }
+
+    /// Rebuilds a lean incremental Merkle tree from the `nodes` of every level, as exported by `nodes`.
+    ///
+    /// The nodes are only accepted if they are internally consistent: every level holds half the nodes of
+    /// the one below rounded up, the last level holds the root alone, and every parent is the hash of its
+    /// children, or the left child itself when it has no sibling.
+    ///
+    /// # Arguments
+    ///
+    /// * `nodes` - The nodes of the tree, starting with the leaves and ending with the root.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing either the rebuilt `LeanIncrementalMerkleTree`, or the `LeanImtError` describing
+    /// the first inconsistency found.
+    pub fn from_nodes(nodes: Vec<Vec<B256>>) -> Result<Self, LeanImtError> {
+        check_nodes(&nodes, &|x: &[u8]| keccak256(x))?;
+
+        // The roots the tree had before being exported are lost, only the current one is known
+        #[cfg(feature = "history")]
+        let roots: Vec<(usize, B256)> = match nodes.last().and_then(|level| level.first()) {
+            Some(root) => vec![(nodes[0].len(), *root)],
+            None => vec![],
+        };
+
+        Ok(Self {
+            nodes,
+            #[cfg(feature = "history")]
+            roots,
+        })
+    }
+
+    /// Recomputes every internal node from the leaves and checks that it matches the stored one.
+    ///
+    /// Complements the validation of `from_nodes` for trees that are already built, to detect corrupted nodes when
+    /// auditing persisted trees. The tree hashes its nodes with Keccak-256, so `hash_function` must compute it too,
+    /// possibly through a zkVM precompile.
+    ///
+    /// # Arguments
+    ///
+    /// * `hash_function` - A function that computes the Keccak-256 hash of a byte slice.
+    ///
+    /// # Returns
+    ///
+    /// `true` if every stored node matches its recomputed value, `false` otherwise.
+    pub fn verify_integrity<F: Fn(&[u8]) -> B256>(&self, hash_function: &F) -> bool {
+        check_nodes(&self.nodes, hash_function).is_ok()
+    }
+
+    /// Returns the nodes of every level of the Merkle tree, starting with the leaves and ending with the root.
+    pub fn nodes(&self) -> Vec<Vec<B256>> {
+        self.nodes.clone()
+    }
+
     /// Returns the root hash of the Merkle tree.
     ///
     /// If the tree is empty, returns a zero `B256` value.
@@ -61,6 +262,30 @@ impl LeanIncrementalMerkleTree {
         *self.nodes[self.depth()].first().unwrap_or(&B256::ZERO)
     }
 
+    /// Returns the root the Merkle tree had when it held `size` leaves.
+    ///
+    /// Only the roots right after each batch of insertions are kept, that is after every call to `new` or `insert`,
+    /// so a `size` reached halfway through a batch has no root. An empty tree always has a zero root.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - The number of leaves the tree held.
+    ///
+    /// # Returns
+    ///
+    /// The root at that size, or `None` if the tree never stopped at that size or it was not recorded.
+    #[cfg(feature = "history")]
+    pub fn root_at(&self, size: usize) -> Option<B256> {
+        if size == 0 {
+            return Some(B256::ZERO);
+        }
+
+        self.roots
+            .binary_search_by_key(&size, |(root_size, _)| *root_size)
+            .ok()
+            .map(|index| self.roots[index].1)
+    }
+
     /// Returns the depth of the Merkle tree.
     ///
     /// The depth is the number of levels in the tree minus one (this definition excludes the leaf level).
@@ -91,6 +316,39 @@ impl LeanIncrementalMerkleTree {
         self.nodes[0].iter().position(|&x| x == *leaf)
     }
 
+    /// Finds the index of the first leaf for which `predicate` returns `true`.
+    ///
+    /// # Arguments
+    ///
+    /// * `predicate` - The condition the leaf hash must satisfy, such as being in a set of precomputed hashes.
+    ///
+    /// # Returns
+    ///
+    /// An `Option<usize>` containing the index of the first matching leaf, or `None` if no leaf matches.
+    pub fn position_by<P: Fn(&B256) -> bool>(&self, predicate: P) -> Option<usize> {
+        self.nodes[0].iter().position(predicate)
+    }
+
+    /// Finds the indices of every occurrence of a given `leaf` in the Merkle tree.
+    ///
+    /// The same leaf can be inserted more than once, for instance when two allocations hash to the same value.
+    ///
+    /// # Arguments
+    ///
+    /// * `leaf` - The leaf hash to search for.
+    ///
+    /// # Returns
+    ///
+    /// The indices of the matching leaves in ascending order, empty if the leaf is not present.
+    pub fn indices_of(&self, leaf: &B256) -> Vec<usize> {
+        self.nodes[0]
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| *x == leaf)
+            .map(|(index, _)| index)
+            .collect()
+    }
+
     /// Checks if the Merkle tree contains a specific `leaf`.
     ///
     /// # Arguments
@@ -104,6 +362,95 @@ impl LeanIncrementalMerkleTree {
         self.nodes[0].contains(leaf)
     }
 
+    /// Checks if the Merkle tree contains a specific `leaf`, in time that does not depend on where, or whether, the
+    /// leaf is found.
+    ///
+    /// Every leaf is compared in constant time and none of the comparisons short-circuit, so this is slower than
+    /// `has`, which should be preferred whenever leaf presence is not sensitive.
+    ///
+    /// # Arguments
+    ///
+    /// * `leaf` - The leaf hash to check for.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the leaf is present in the tree, `false` otherwise.
+    pub fn has_ct(&self, leaf: &B256) -> bool {
+        self.nodes[0]
+            .iter()
+            .fold(Choice::from(0), |found, x| {
+                found | x.as_slice().ct_eq(leaf.as_slice())
+            })
+            .into()
+    }
+
+    /// Inserts a single `leaf` into the Merkle tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `leaf` - The leaf hash to insert into the tree.
+    pub fn insert(&mut self, leaf: B256) {
+        self.insert_many(&[leaf]);
+    }
+
+    /// Inserts a single `leaf` into the Merkle tree and returns its inclusion proof.
+    ///
+    /// The proof is generated against the root right after the insertion: any later insertion changes
+    /// the root, so earlier proofs go stale and must be generated again to verify against the new root.
+    ///
+    /// # Arguments
+    ///
+    /// * `leaf` - The leaf hash to insert into the tree.
+    ///
+    /// # Returns
+    ///
+    /// The `LeanIMTMerkleProof` for the inserted leaf.
+    pub fn insert_and_prove(&mut self, leaf: B256) -> LeanIMTMerkleProof {
+        self.insert(leaf);
+        self.generate_proof(self.size() - 1)
+            .expect("The leaf was just inserted")
+    }
+
+    /// Removes the last leaf inserted into the Merkle tree, undoing its insertion.
+    ///
+    /// Levels left without nodes are dropped and the path of the removed leaf is recomputed, so the tree ends up
+    /// identical to one built from the remaining leaves.
+    ///
+    /// # Returns
+    ///
+    /// The removed leaf, or `None` if the tree is empty.
+    pub fn pop(&mut self) -> Option<B256> {
+        let leaf: B256 = self.nodes[0].pop()?;
+
+        let depth: usize = depth_for_size(self.size());
+        self.nodes.truncate(depth + 1);
+
+        // Only the last node of each level can be an ancestor of the removed leaf
+        for level in 0..depth {
+            let num_nodes: usize = (self.nodes[level].len() + 1) / 2;
+            self.nodes[level + 1].truncate(num_nodes);
+
+            let index: usize = num_nodes - 1;
+            let left_node: B256 = self.nodes[level][index * 2];
+            self.nodes[level + 1][index] = match self.nodes[level].get(index * 2 + 1) {
+                Some(right_node) => keccak256([&left_node, right_node].concat()),
+                None => left_node,
+            };
+        }
+
+        // Roots of the sizes past the removed leaf no longer belong to the tree
+        #[cfg(feature = "history")]
+        {
+            let size: usize = self.size();
+            self.roots.retain(|(root_size, _)| *root_size <= size);
+            if size > 0 && self.roots.last().map(|(root_size, _)| *root_size) != Some(size) {
+                self.roots.push((size, self.root()));
+            }
+        }
+
+        Some(leaf)
+    }
+
     /// Inserts multiple leaves into the Merkle tree.
     ///
     /// This method updates the tree structure efficiently by only recalculating
@@ -116,8 +463,7 @@ impl LeanIncrementalMerkleTree {
         let mut start_index: usize = self.size() >> 1;
         self.nodes[0].extend_from_slice(leaves);
 
-        let new_levels: usize =
-            ((self.size() as f64).log2().ceil() as usize).saturating_sub(self.depth());
+        let new_levels: usize = depth_for_size(self.size()).saturating_sub(self.depth());
         self.nodes.extend((0..new_levels).map(|_| vec![]));
 
         for level in 0..self.depth() {
@@ -141,6 +487,9 @@ impl LeanIncrementalMerkleTree {
 
             start_index >>= 1;
         }
+
+        #[cfg(feature = "history")]
+        self.roots.push((self.size(), self.root()));
     }
 
     /// Generates a `LeanIMTMerkleProof` Merkle proof for a leaf at the given `index`.
@@ -151,14 +500,17 @@ impl LeanIncrementalMerkleTree {
     ///
     /// # Returns
     ///
-    /// A `Result` containing either the corresponding `LeanIMTMerkleProof` or an error message
-    /// if the index is out of bounds.
-    pub fn generate_proof(&self, index: usize) -> Result<LeanIMTMerkleProof, String> {
+    /// A `Result` containing either the corresponding `LeanIMTMerkleProof`, `LeanImtError::EmptyTree`
+    /// if the tree has no leaves, or `LeanImtError::IndexOutOfBounds` if the index is out of bounds.
+    pub fn generate_proof(&self, index: usize) -> Result<LeanIMTMerkleProof, LeanImtError> {
+        if self.size() == 0 {
+            return Err(LeanImtError::EmptyTree);
+        }
         if index >= self.size() {
-            return Err(format!(
-                "The leaf at index '{}' does not exist in this tree",
-                index
-            ));
+            return Err(LeanImtError::IndexOutOfBounds {
+                index,
+                size: self.size(),
+            });
         }
 
         let leaf: B256 = self.nodes[0][index];
@@ -207,24 +559,105 @@ impl LeanIncrementalMerkleTree {
     ///
     /// `true` if the proof is valid, `false` otherwise.
     pub fn verify_proof(&self, proof: &LeanIMTMerkleProof) -> bool {
-        let mut node: B256 = proof.leaf;
+        proof.root == compute_proof_root(proof)
+    }
 
-        for (i, &sibling) in proof.siblings.iter().enumerate() {
-            if (proof.index >> i) & 1 == 1 {
-                node = keccak256([&sibling, &node].concat());
-            } else {
-                node = keccak256([&node, &sibling].concat());
-            }
+    /// Verifies a `LeanIMTMerkleProof` Merkle proof against an expected root.
+    ///
+    /// Unlike `verify_proof`, the root embedded in the proof is ignored: the root is recomputed from the `leaf`,
+    /// `index` and `siblings` of the proof and compared to `expected_root`, so a proof whose embedded root was
+    /// fabricated alongside its path is rejected.
+    ///
+    /// # Arguments
+    ///
+    /// * `proof` - The `LeanIMTMerkleProof` to verify.
+    /// * `expected_root` - The root the proof must lead to.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the proof leads to `expected_root`, `false` otherwise.
+    pub fn verify_proof_with_root(&self, proof: &LeanIMTMerkleProof, expected_root: &B256) -> bool {
+        *expected_root == compute_proof_root(proof)
+    }
+}
+
+/// Recomputes the root a `LeanIMTMerkleProof` leads to from its `leaf`, `index` and `siblings`.
+///
+/// # Arguments
+///
+/// * `proof` - The `LeanIMTMerkleProof` whose path is hashed up to the root.
+fn compute_proof_root(proof: &LeanIMTMerkleProof) -> B256 {
+    let mut node: B256 = proof.leaf;
+
+    for (i, &sibling) in proof.siblings.iter().enumerate() {
+        if (proof.index >> i) & 1 == 1 {
+            node = keccak256([&sibling, &node].concat());
+        } else {
+            node = keccak256([&node, &sibling].concat());
         }
+    }
+
+    node
+}
 
-        proof.root == node
+/// Returns the depth of a tree holding `size` leaves, `ceil(log2(size))`, or zero when there are none.
+///
+/// Computed on integers, as floating point logarithms are not available without `std`.
+fn depth_for_size(size: usize) -> usize {
+    size.next_power_of_two().trailing_zeros() as usize
+}
+
+/// Checks that the `nodes` of every level are internally consistent: every level holds half the nodes of the one below
+/// rounded up, the last level holds the root alone, and every parent is the hash of its children, or the left child
+/// itself when it has no sibling.
+///
+/// # Arguments
+///
+/// * `nodes` - The nodes of the tree, starting with the leaves and ending with the root.
+/// * `hash_function` - A function that computes the hash of two concatenated children.
+fn check_nodes<F: Fn(&[u8]) -> B256>(
+    nodes: &[Vec<B256>],
+    hash_function: &F,
+) -> Result<(), LeanImtError> {
+    let size: usize = nodes.first().map_or(0, Vec::len);
+    let depth: usize = depth_for_size(size);
+    if nodes.len() != depth + 1 {
+        return Err(LeanImtError::InvalidShape);
     }
+
+    for level in 0..depth {
+        if nodes[level + 1].len() != (nodes[level].len() + 1) / 2 {
+            return Err(LeanImtError::InvalidShape);
+        }
+
+        for (index, parent_node) in nodes[level + 1].iter().enumerate() {
+            let left_node: B256 = nodes[level][index * 2];
+            let expected_node: B256 = match nodes[level].get(index * 2 + 1) {
+                Some(right_node) => hash_function(&[&left_node[..], &right_node[..]].concat()),
+                None => left_node,
+            };
+
+            if *parent_node != expected_node {
+                return Err(LeanImtError::InconsistentNode {
+                    level: level + 1,
+                    index,
+                });
+            }
+        }
+    }
+
+    Ok(())
 }
 
 #[cfg(test)]
 mod test {
-    use super::LeanIncrementalMerkleTree;
-    use alloy_primitives::{keccak256, B256};
+    use super::{
+        compute_proof_root, LeanIMTMerkleProof, LeanImtError, LeanIncrementalMerkleTree,
+        SolLeanIMTMerkleProof, TreeBuildOptions,
+    };
+    use alloy_primitives::{keccak256, B256, U256};
+    use alloy_sol_types::SolValue;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
 
     #[test]
     fn test_initializes_empty_tree() {
@@ -251,6 +684,60 @@ mod test {
         assert_eq!(tree.root(), manual_root);
     }
 
+    #[test]
+    fn test_try_new() {
+        let mut leaves: Vec<B256> = (0..8).map(|_| B256::random()).collect();
+        let all_checks: TreeBuildOptions = TreeBuildOptions {
+            require_nonzero: true,
+            require_unique: true,
+        };
+
+        // Valid leaves build the same tree as `new`
+        assert_eq!(
+            LeanIncrementalMerkleTree::try_new(&leaves, all_checks),
+            Ok(LeanIncrementalMerkleTree::new(&leaves))
+        );
+
+        // A zero leaf is only rejected when required
+        leaves[3] = B256::ZERO;
+        assert_eq!(
+            LeanIncrementalMerkleTree::try_new(
+                &leaves,
+                TreeBuildOptions {
+                    require_nonzero: true,
+                    ..Default::default()
+                }
+            ),
+            Err(LeanImtError::ZeroLeaf { index: 3 })
+        );
+        assert!(LeanIncrementalMerkleTree::try_new(&leaves, TreeBuildOptions::default()).is_ok());
+
+        // A duplicate leaf is only rejected when required
+        leaves[3] = leaves[1];
+        assert_eq!(
+            LeanIncrementalMerkleTree::try_new(
+                &leaves,
+                TreeBuildOptions {
+                    require_unique: true,
+                    ..Default::default()
+                }
+            ),
+            Err(LeanImtError::DuplicateLeaf {
+                first_index: 1,
+                index: 3
+            })
+        );
+        assert_eq!(
+            LeanImtError::DuplicateLeaf {
+                first_index: 1,
+                index: 3
+            }
+            .to_string(),
+            "the leaves at indices '1' and '3' are the same"
+        );
+        assert!(LeanIncrementalMerkleTree::try_new(&leaves, TreeBuildOptions::default()).is_ok());
+    }
+
     #[test]
     fn test_leaves() {
         let leaves: Vec<B256> = (0..rand::random::<u16>()).map(|_| B256::random()).collect();
@@ -270,6 +757,35 @@ mod test {
         assert_eq!(tree.index_of(&element).unwrap(), insert_index);
     }
 
+    #[test]
+    fn test_position_by() {
+        let leaves: Vec<B256> = (0..10).map(|_| B256::random()).collect();
+        let tree: LeanIncrementalMerkleTree = LeanIncrementalMerkleTree::new(&leaves);
+
+        let wanted_leaves: [B256; 2] = [leaves[7], leaves[3]];
+        assert_eq!(
+            tree.position_by(|leaf| wanted_leaves.contains(leaf)),
+            Some(3)
+        );
+        assert_eq!(tree.position_by(|leaf| *leaf == B256::ZERO), None);
+    }
+
+    #[test]
+    fn test_indices_of() {
+        let element: B256 = B256::random();
+        let mut leaves: Vec<B256> = (0..10).map(|_| B256::random()).collect();
+        leaves[2] = element;
+        leaves[5] = element;
+        leaves[9] = element;
+        let tree: LeanIncrementalMerkleTree = LeanIncrementalMerkleTree::new(&leaves);
+
+        // Every duplicate is returned, while `index_of` only finds the first one
+        assert_eq!(tree.indices_of(&element), vec![2, 5, 9]);
+        assert_eq!(tree.index_of(&element), Some(2));
+        assert_eq!(tree.indices_of(&leaves[0]), vec![0]);
+        assert!(tree.indices_of(&B256::random()).is_empty());
+    }
+
     #[test]
     fn test_has() {
         let element: B256 = B256::random();
@@ -282,6 +798,17 @@ mod test {
         assert!(!tree.has(&B256::random()));
     }
 
+    #[test]
+    fn test_has_ct() {
+        let leaves: Vec<B256> = (0..rand::random::<u8>()).map(|_| B256::random()).collect();
+        let tree: LeanIncrementalMerkleTree = LeanIncrementalMerkleTree::new(&leaves);
+
+        for leaf in leaves.iter().chain([B256::random(), B256::ZERO].iter()) {
+            assert_eq!(tree.has_ct(leaf), tree.has(leaf));
+        }
+        assert!(leaves.iter().all(|leaf| tree.has_ct(leaf)));
+    }
+
     #[test]
     fn test_generate_verify_proof() {
         let size: u16 = rand::random::<u16>();
@@ -294,4 +821,288 @@ mod test {
 
         assert!(tree.verify_proof(&proof));
     }
+
+    #[test]
+    fn test_verify_proof_with_root() {
+        let leaves: Vec<B256> = (0..42).map(|_| B256::random()).collect();
+        let tree: LeanIncrementalMerkleTree = LeanIncrementalMerkleTree::new(&leaves);
+        let mut proof: LeanIMTMerkleProof =
+            tree.generate_proof(rand::random::<usize>() % 42).unwrap();
+
+        // The embedded root is ignored
+        proof.root = B256::random();
+        assert!(!tree.verify_proof(&proof));
+        assert!(tree.verify_proof_with_root(&proof, &tree.root()));
+
+        // A fabricated leaf whose embedded root matches its own path is rejected
+        let mut fabricated_proof: LeanIMTMerkleProof = proof.clone();
+        fabricated_proof.leaf = B256::random();
+        fabricated_proof.root = compute_proof_root(&fabricated_proof);
+        assert!(tree.verify_proof(&fabricated_proof));
+        assert!(!tree.verify_proof_with_root(&fabricated_proof, &tree.root()));
+    }
+
+    #[test]
+    fn test_generate_proof_errors() {
+        let empty_tree: LeanIncrementalMerkleTree = LeanIncrementalMerkleTree::default();
+        assert_eq!(
+            empty_tree.generate_proof(0).err(),
+            Some(LeanImtError::EmptyTree)
+        );
+
+        let leaves: Vec<B256> = (0..5).map(|_| B256::random()).collect();
+        let tree: LeanIncrementalMerkleTree = LeanIncrementalMerkleTree::new(&leaves);
+        let error: LeanImtError = tree.generate_proof(5).err().unwrap();
+        assert_eq!(error, LeanImtError::IndexOutOfBounds { index: 5, size: 5 });
+        assert_eq!(
+            error.to_string(),
+            "the leaf at index '5' does not exist in this tree of size 5"
+        );
+    }
+
+    #[test]
+    fn test_proofs_for_every_index() {
+        // Seeded, so that a failing tree can be rebuilt
+        const SEED: u64 = 0x1ea7_1317;
+        let mut rng: StdRng = StdRng::seed_from_u64(SEED);
+
+        // Edge sizes around powers of two, where the tree is the most unbalanced, and then random ones
+        let sizes: Vec<usize> = [1, 2, 3, 4, 5, 7, 8, 9, 255, 256, 257, 1_023, 1_024, 1_025]
+            .into_iter()
+            .chain((0..8).map(|_| rng.gen_range(1..=3_000)))
+            .collect();
+
+        for size in sizes {
+            let leaves: Vec<B256> = (0..size)
+                .map(|_| B256::from(rng.gen::<[u8; 32]>()))
+                .collect();
+
+            // Inserting the leaves one by one builds the same tree as inserting them all at once
+            let split: usize = rng.gen_range(0..=size);
+            let mut tree: LeanIncrementalMerkleTree =
+                LeanIncrementalMerkleTree::new(&leaves[..split]);
+            for leaf in leaves[split..].iter() {
+                tree.insert(*leaf);
+            }
+            assert_eq!(
+                tree.root(),
+                LeanIncrementalMerkleTree::new(&leaves).root(),
+                "seed {SEED}, size {size}"
+            );
+
+            for (index, leaf) in leaves.iter().enumerate() {
+                let proof: LeanIMTMerkleProof = tree.generate_proof(index).unwrap();
+                assert_eq!(proof.leaf, *leaf, "seed {SEED}, size {size}, index {index}");
+                assert_eq!(
+                    proof.root,
+                    tree.root(),
+                    "seed {SEED}, size {size}, index {index}"
+                );
+                assert!(
+                    tree.verify_proof(&proof),
+                    "seed {SEED}, size {size}, index {index}"
+                );
+            }
+
+            assert_eq!(
+                tree.generate_proof(size).err(),
+                Some(LeanImtError::IndexOutOfBounds { index: size, size }),
+                "seed {SEED}, size {size}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_insert() {
+        let leaves: Vec<B256> = (0..rand::random::<u8>()).map(|_| B256::random()).collect();
+        let mut tree: LeanIncrementalMerkleTree = LeanIncrementalMerkleTree::default();
+        for leaf in leaves.iter() {
+            tree.insert(*leaf);
+        }
+
+        assert_eq!(tree.root(), LeanIncrementalMerkleTree::new(&leaves).root());
+    }
+
+    #[test]
+    #[cfg(feature = "history")]
+    fn test_root_at() {
+        let leaves: Vec<B256> = (0..20).map(|_| B256::random()).collect();
+        let mut tree: LeanIncrementalMerkleTree = LeanIncrementalMerkleTree::new(&leaves[..3]);
+        for leaf in leaves[3..9].iter() {
+            tree.insert(*leaf);
+        }
+        tree.insert_many(&leaves[9..]);
+
+        // Every size at the end of a batch matches a tree built from the leaves up to then
+        for size in [0, 3, 4, 5, 6, 7, 8, 9, 20] {
+            assert_eq!(
+                tree.root_at(size),
+                Some(LeanIncrementalMerkleTree::new(&leaves[..size]).root())
+            );
+        }
+        assert_eq!(tree.root_at(20), Some(tree.root()));
+
+        // Sizes halfway through a batch, or never reached, have no root
+        assert_eq!(tree.root_at(1), None);
+        assert_eq!(tree.root_at(10), None);
+        assert_eq!(tree.root_at(21), None);
+
+        // Importing the nodes only keeps the current root
+        let imported_tree: LeanIncrementalMerkleTree =
+            LeanIncrementalMerkleTree::from_nodes(tree.nodes()).unwrap();
+        assert_eq!(imported_tree.root_at(20), Some(tree.root()));
+        assert_eq!(imported_tree.root_at(9), None);
+    }
+
+    #[test]
+    fn test_clone() {
+        let leaves: Vec<B256> = (0..5).map(|_| B256::random()).collect();
+        let tree: LeanIncrementalMerkleTree = LeanIncrementalMerkleTree::new(&leaves);
+
+        let mut cloned_tree: LeanIncrementalMerkleTree = tree.clone();
+        assert_eq!(cloned_tree, tree);
+
+        // Inserting into the clone leaves the original untouched
+        cloned_tree.insert(B256::random());
+        assert_ne!(cloned_tree, tree);
+        assert_eq!(tree, LeanIncrementalMerkleTree::new(&leaves));
+        assert_eq!(tree.size(), 5);
+        assert_eq!(cloned_tree.size(), 6);
+    }
+
+    #[test]
+    fn test_pop() {
+        let leaves: Vec<B256> = (0..rand::random::<u8>()).map(|_| B256::random()).collect();
+        let mut tree: LeanIncrementalMerkleTree = LeanIncrementalMerkleTree::new(&leaves);
+
+        for size in (0..leaves.len()).rev() {
+            assert_eq!(tree.pop(), Some(leaves[size]));
+
+            let expected_tree: LeanIncrementalMerkleTree =
+                LeanIncrementalMerkleTree::new(&leaves[..size]);
+            assert_eq!(tree.nodes(), expected_tree.nodes());
+            assert_eq!(tree.root(), expected_tree.root());
+        }
+
+        assert_eq!(tree.pop(), None);
+        assert_eq!(tree.nodes(), LeanIncrementalMerkleTree::default().nodes());
+    }
+
+    #[test]
+    fn test_insert_and_prove() {
+        let leaves: Vec<B256> = (0..100).map(|_| B256::random()).collect();
+        let mut tree: LeanIncrementalMerkleTree = LeanIncrementalMerkleTree::default();
+        let proofs: Vec<LeanIMTMerkleProof> = leaves
+            .iter()
+            .map(|leaf| tree.insert_and_prove(*leaf))
+            .collect();
+
+        for (index, proof) in proofs.iter().enumerate() {
+            // Every proof is valid against the root at the time it was generated
+            assert_eq!(proof.leaf, leaves[index]);
+            assert!(tree.verify_proof(proof));
+            // But only the last one still matches the final root, the rest went stale
+            assert_eq!(proof.root == tree.root(), index == leaves.len() - 1);
+
+            let reproof: LeanIMTMerkleProof = tree.generate_proof(index).unwrap();
+            assert_eq!(reproof.root, tree.root());
+            assert!(tree.verify_proof(&reproof));
+        }
+    }
+
+    #[test]
+    fn test_from_nodes() {
+        let leaves: Vec<B256> = (0..rand::random::<u8>()).map(|_| B256::random()).collect();
+        let tree: LeanIncrementalMerkleTree = LeanIncrementalMerkleTree::new(&leaves);
+
+        let imported_tree: LeanIncrementalMerkleTree =
+            LeanIncrementalMerkleTree::from_nodes(tree.nodes()).unwrap();
+        assert_eq!(imported_tree.root(), tree.root());
+        assert_eq!(imported_tree.leaves(), leaves);
+    }
+
+    #[test]
+    fn test_from_nodes_rejects_tampered_nodes() {
+        let leaves: Vec<B256> = (0..5).map(|_| B256::random()).collect();
+        let nodes: Vec<Vec<B256>> = LeanIncrementalMerkleTree::new(&leaves).nodes();
+
+        // An intermediate node that is not the hash of its children
+        let mut tampered_nodes: Vec<Vec<B256>> = nodes.clone();
+        tampered_nodes[1][1] = B256::random();
+        assert_eq!(
+            LeanIncrementalMerkleTree::from_nodes(tampered_nodes).err(),
+            Some(LeanImtError::InconsistentNode { level: 1, index: 1 })
+        );
+
+        // A missing root level
+        let mut truncated_nodes: Vec<Vec<B256>> = nodes.clone();
+        truncated_nodes.pop();
+        assert_eq!(
+            LeanIncrementalMerkleTree::from_nodes(truncated_nodes).err(),
+            Some(LeanImtError::InvalidShape)
+        );
+
+        // An extra node at the root level
+        let mut extended_nodes: Vec<Vec<B256>> = nodes;
+        extended_nodes[3].push(B256::random());
+        assert_eq!(
+            LeanIncrementalMerkleTree::from_nodes(extended_nodes).err(),
+            Some(LeanImtError::InvalidShape)
+        );
+    }
+
+    #[test]
+    fn test_verify_integrity() {
+        let leaves: Vec<B256> = (0..11).map(|_| B256::random()).collect();
+        let mut tree: LeanIncrementalMerkleTree = LeanIncrementalMerkleTree::new(&leaves);
+        assert!(tree.verify_integrity(&|x: &[u8]| keccak256(x)));
+        // The nodes are only valid for the hash they were computed with
+        assert!(!tree.verify_integrity(&|x: &[u8]| keccak256(keccak256(x))));
+
+        // Flipping a single byte of an internal node breaks the integrity
+        tree.nodes[2][1][7] ^= 1;
+        assert!(!tree.verify_integrity(&|x: &[u8]| keccak256(x)));
+        tree.nodes[2][1][7] ^= 1;
+        assert!(tree.verify_integrity(&|x: &[u8]| keccak256(x)));
+
+        // As does flipping a byte of the root
+        let depth: usize = tree.depth();
+        tree.nodes[depth][0][31] ^= 1;
+        assert!(!tree.verify_integrity(&|x: &[u8]| keccak256(x)));
+
+        // Empty trees have nothing to recompute
+        assert!(LeanIncrementalMerkleTree::new(&[]).verify_integrity(&|x: &[u8]| keccak256(x)));
+    }
+
+    #[test]
+    fn test_proof_abi_encode() {
+        let leaves: Vec<B256> = (0..42).map(|_| B256::random()).collect();
+        let tree: LeanIncrementalMerkleTree = LeanIncrementalMerkleTree::new(&leaves);
+        let proof: LeanIMTMerkleProof = tree.generate_proof(rand::random::<usize>() % 42).unwrap();
+
+        let decoded_proof: SolLeanIMTMerkleProof =
+            SolLeanIMTMerkleProof::abi_decode(&proof.abi_encode(), true).unwrap();
+
+        assert_eq!(decoded_proof.root, proof.root);
+        assert_eq!(decoded_proof.leaf, proof.leaf);
+        assert_eq!(decoded_proof.index, U256::from(proof.index));
+        assert_eq!(decoded_proof.siblings, proof.siblings);
+    }
+
+    #[test]
+    fn test_proof_display() {
+        let leaves: Vec<B256> = (0..5).map(|_| B256::random()).collect();
+        let tree: LeanIncrementalMerkleTree = LeanIncrementalMerkleTree::new(&leaves);
+        let proof: LeanIMTMerkleProof = tree.generate_proof(3).unwrap();
+
+        let formatted: String = proof.to_string();
+
+        assert!(formatted.contains(&format!("root: {}", proof.root)));
+        assert!(formatted.contains(&format!("leaf: {}", leaves[3])));
+        assert!(formatted.contains("index: 3"));
+        assert!(formatted.contains(&format!("siblings: {}", proof.siblings.len())));
+        for sibling in proof.siblings.iter() {
+            assert!(formatted.contains(&sibling.to_string()));
+        }
+    }
 }
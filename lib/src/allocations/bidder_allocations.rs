@@ -1,15 +1,16 @@
+use alloc::collections::BTreeMap;
 use alloy_primitives::{Address, U256};
-use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     auction_parameters::AuctionParameters,
-    exit_tree::{ExitLeaf, ExitLeafRepurchaseObligation, ExitLeafTokenWithdrawal, ExitLeaves},
+    exit_tree::{ExitLeaf, ExitLeafRepurchaseObligation, ExitLeafSink, ExitLeafTokenWithdrawal},
     orders::bids::Bid,
 };
 
-use super::{Allocation, Allocations};
+use super::{expect_no_overflow, Allocation, AllocationMap, Allocations, OverflowError};
 
-#[derive(PartialEq, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
 /// Represents a repurchase obligation for a bidder.
 pub struct RepurchaseObligation {
     /// The amount to be repurchased.
@@ -28,7 +29,61 @@ impl Default for RepurchaseObligation {
     }
 }
 
+impl RepurchaseObligation {
+    /// Updates the repurchase obligation by adding the given amounts.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The repurchase obligation to be updated.
+    /// * `repurchase_amount` - The amount to add to the current repurchase amount.
+    /// * `collateral_amount` - The amount to add to the current collateral amount.
+    fn update(&mut self, repurchase_amount: U256, collateral_amount: U256) {
+        self.repurchase_amount = self.repurchase_amount.saturating_add(repurchase_amount);
+        self.collateral_amount = self.collateral_amount.saturating_add(collateral_amount);
+    }
+
+    /// Updates the repurchase obligation by adding the given amounts, failing on overflow.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The repurchase obligation to be updated.
+    /// * `repurchase_amount` - The amount to add to the current repurchase amount.
+    /// * `collateral_amount` - The amount to add to the current collateral amount.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), OverflowError>` - An error if either amount overflows, in which case neither is updated.
+    fn try_update(
+        &mut self,
+        repurchase_amount: U256,
+        collateral_amount: U256,
+    ) -> Result<(), OverflowError> {
+        let repurchase_amount: U256 = self
+            .repurchase_amount
+            .checked_add(repurchase_amount)
+            .ok_or(OverflowError)?;
+        let collateral_amount: U256 = self
+            .collateral_amount
+            .checked_add(collateral_amount)
+            .ok_or(OverflowError)?;
+        self.repurchase_amount = repurchase_amount;
+        self.collateral_amount = collateral_amount;
+        Ok(())
+    }
+
+    /// Returns the amount to be repurchased.
+    pub fn repurchase_amount(&self) -> U256 {
+        self.repurchase_amount
+    }
+
+    /// Returns the amount of collateral associated with the repurchase.
+    pub fn collateral_amount(&self) -> U256 {
+        self.collateral_amount
+    }
+}
+
 /// Represents the allocation for a bidder in the auction.
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
 pub struct BidderAllocation {
     /// The amount of purchase tokens assigned to the bidder, if any.
     purchase_amount: U256,
@@ -36,6 +91,12 @@ pub struct BidderAllocation {
     collateral_amount: U256,
     /// The bidder's repurchase obligation, if any.
     repurchase_obligation: RepurchaseObligation,
+    /// The amount of purchase tokens paying off the bidder's rolled over loans, indexed by the term repo servicer of
+    /// each loan.
+    rollover_pair_offs: BTreeMap<Address, U256>,
+    /// Whether updates panic on overflow instead of saturating.
+    #[serde(skip)]
+    checked: bool,
 }
 
 impl Default for BidderAllocation {
@@ -48,11 +109,34 @@ impl Default for BidderAllocation {
                 repurchase_amount: U256::ZERO,
                 collateral_amount: U256::ZERO,
             },
+            rollover_pair_offs: BTreeMap::new(),
+            checked: false,
         }
     }
 }
 
 impl BidderAllocation {
+    /// Returns the amount of purchase tokens assigned to the bidder.
+    pub fn purchase_amount(&self) -> U256 {
+        self.purchase_amount
+    }
+
+    /// Returns the amount of collateral tokens left on the table for the bidder.
+    pub fn collateral_amount(&self) -> U256 {
+        self.collateral_amount
+    }
+
+    /// Returns the bidder's repurchase obligation.
+    pub fn repurchase_obligation(&self) -> &RepurchaseObligation {
+        &self.repurchase_obligation
+    }
+
+    /// Returns the amount of purchase tokens paying off the bidder's rolled over loans, indexed by the term repo
+    /// servicer of each loan.
+    pub fn rollover_pair_offs(&self) -> &BTreeMap<Address, U256> {
+        &self.rollover_pair_offs
+    }
+
     /// Updates the purchase token amount for the bidder.
     ///
     /// # Arguments
@@ -60,7 +144,11 @@ impl BidderAllocation {
     /// * `self` - The allocation to be updated.
     /// * `amount` - The amount to add to the current purchase amount.
     pub fn update_purchase_amount(&mut self, amount: U256) {
-        self.purchase_amount = self.purchase_amount.saturating_add(amount);
+        if self.checked {
+            expect_no_overflow(self.try_update_purchase_amount(amount));
+        } else {
+            self.purchase_amount = self.purchase_amount.saturating_add(amount);
+        }
     }
 
     /// Updates the collateral amount for the bidder.
@@ -70,7 +158,11 @@ impl BidderAllocation {
     /// * `self` - The allocation to be updated.
     /// * `amount` - The amount to add to the current collateral amount.
     pub fn update_collateral_amount(&mut self, amount: U256) {
-        self.collateral_amount = self.collateral_amount.saturating_add(amount);
+        if self.checked {
+            expect_no_overflow(self.try_update_collateral_amount(amount));
+        } else {
+            self.collateral_amount = self.collateral_amount.saturating_add(amount);
+        }
     }
 
     /// Updates the repurchase obligation for the bidder.
@@ -85,23 +177,145 @@ impl BidderAllocation {
         repurchase_amount: U256,
         collateral_amount: U256,
     ) {
-        self.repurchase_obligation.repurchase_amount = self
-            .repurchase_obligation
-            .repurchase_amount
-            .saturating_add(repurchase_amount);
-        self.repurchase_obligation.collateral_amount = self
-            .repurchase_obligation
+        if self.checked {
+            expect_no_overflow(
+                self.try_update_repurchase_obligation(repurchase_amount, collateral_amount),
+            );
+        } else {
+            self.repurchase_obligation
+                .update(repurchase_amount, collateral_amount);
+        }
+    }
+
+    /// Updates the amount of purchase tokens paying off the bidder's loan at the given term repo servicer.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The allocation to be updated.
+    /// * `term_repo_servicer` - The term repo servicer of the loan being rolled over.
+    /// * `amount` - The amount to add to the current pair off amount.
+    pub fn update_rollover_pair_off(&mut self, term_repo_servicer: Address, amount: U256) {
+        if self.checked {
+            expect_no_overflow(self.try_update_rollover_pair_off(term_repo_servicer, amount));
+        } else {
+            let pair_off_amount: &mut U256 = self
+                .rollover_pair_offs
+                .entry(term_repo_servicer)
+                .or_default();
+            *pair_off_amount = pair_off_amount.saturating_add(amount);
+        }
+    }
+
+    /// Updates the purchase token amount for the bidder, failing on overflow.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The allocation to be updated.
+    /// * `amount` - The amount to add to the current purchase amount.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), OverflowError>` - An error if the amount overflows, in which case the allocation is left untouched.
+    pub fn try_update_purchase_amount(&mut self, amount: U256) -> Result<(), OverflowError> {
+        self.purchase_amount = self
+            .purchase_amount
+            .checked_add(amount)
+            .ok_or(OverflowError)?;
+        Ok(())
+    }
+
+    /// Updates the collateral amount for the bidder, failing on overflow.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The allocation to be updated.
+    /// * `amount` - The amount to add to the current collateral amount.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), OverflowError>` - An error if the amount overflows, in which case the allocation is left untouched.
+    pub fn try_update_collateral_amount(&mut self, amount: U256) -> Result<(), OverflowError> {
+        self.collateral_amount = self
             .collateral_amount
-            .saturating_add(collateral_amount);
+            .checked_add(amount)
+            .ok_or(OverflowError)?;
+        Ok(())
+    }
+
+    /// Updates the repurchase obligation for the bidder, failing on overflow.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The allocation to be updated.
+    /// * `repurchase_amount` - The amount to add to the current repurchase amount.
+    /// * `collateral_amount` - The amount to add to the current collateral amount associated with the repurchase obligation.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), OverflowError>` - An error if either amount overflows, in which case the allocation is left untouched.
+    pub fn try_update_repurchase_obligation(
+        &mut self,
+        repurchase_amount: U256,
+        collateral_amount: U256,
+    ) -> Result<(), OverflowError> {
+        self.repurchase_obligation
+            .try_update(repurchase_amount, collateral_amount)
+    }
+
+    /// Updates the amount of purchase tokens paying off the bidder's loan at the given term repo servicer, failing on
+    /// overflow.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The allocation to be updated.
+    /// * `term_repo_servicer` - The term repo servicer of the loan being rolled over.
+    /// * `amount` - The amount to add to the current pair off amount.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), OverflowError>` - An error if the amount overflows, in which case the allocation is left untouched.
+    pub fn try_update_rollover_pair_off(
+        &mut self,
+        term_repo_servicer: Address,
+        amount: U256,
+    ) -> Result<(), OverflowError> {
+        let pair_off_amount: U256 = self
+            .rollover_pair_offs
+            .get(&term_repo_servicer)
+            .copied()
+            .unwrap_or_default()
+            .checked_add(amount)
+            .ok_or(OverflowError)?;
+        self.rollover_pair_offs
+            .insert(term_repo_servicer, pair_off_amount);
+        Ok(())
+    }
+
+    /// Adds every amount of another allocation of the same bidder to this one.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The allocation to be updated.
+    /// * `other` - The allocation being merged into it.
+    pub fn merge(&mut self, other: BidderAllocation) {
+        self.update_purchase_amount(other.purchase_amount);
+        self.update_collateral_amount(other.collateral_amount);
+        self.update_repurchase_obligation(
+            other.repurchase_obligation.repurchase_amount,
+            other.repurchase_obligation.collateral_amount,
+        );
+        for (term_repo_servicer, pair_off_amount) in other.rollover_pair_offs {
+            self.update_rollover_pair_off(term_repo_servicer, pair_off_amount);
+        }
     }
 }
 
 impl Allocation for BidderAllocation {
-    fn into_exit_leaves(
+    fn into_exit_leaves<S: ExitLeafSink>(
         self,
         address: Address,
         tokens: &AuctionParameters,
-        exit_leaves: &mut ExitLeaves,
+        exit_leaves: &mut S,
     ) {
         if self.purchase_amount != U256::ZERO {
             exit_leaves.push(ExitLeaf::TokenWithdrawal(ExitLeafTokenWithdrawal {
@@ -128,11 +342,20 @@ impl Allocation for BidderAllocation {
                 },
             ));
         }
+
+        // The purchase tokens of rolled over loans are paid to their term repo servicers, pairing the loans off
+        for (term_repo_servicer, pair_off_amount) in self.rollover_pair_offs.into_iter() {
+            exit_leaves.push(ExitLeaf::TokenWithdrawal(ExitLeafTokenWithdrawal {
+                recipient: term_repo_servicer,
+                token: tokens.purchaseToken,
+                amount: pair_off_amount,
+            }));
+        }
     }
 }
 
 /// A map of bidder addresses to their respective allocations.
-pub type BidderAllocations = BTreeMap<Address, BidderAllocation>;
+pub type BidderAllocations = AllocationMap<BidderAllocation>;
 
 impl Allocations for BidderAllocations {
     type Allocation = BidderAllocation;
@@ -144,7 +367,13 @@ impl Allocations for BidderAllocations {
     }
 
     fn get_allocation(&mut self, address: &Address) -> &mut BidderAllocation {
-        self.entry(*address).or_default()
+        let checked: bool = self.checked;
+        self.allocations
+            .entry(*address)
+            .or_insert_with(|| BidderAllocation {
+                checked,
+                ..BidderAllocation::default()
+            })
     }
 }
 
@@ -153,6 +382,8 @@ mod test {
     use crate::{
         allocations::AuctionResults,
         auction_parameters::tests::random_auction_parameters,
+        constants::MIN_ORDER_AMOUNT,
+        exit_tree::ExitLeaves,
         orders::{
             bids::{
                 tests::{
@@ -215,6 +446,123 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_update_rollover_pair_off() {
+        let mut bidder_allocation: BidderAllocation = BidderAllocation::default();
+        assert!(bidder_allocation.rollover_pair_offs.is_empty());
+
+        let term_repo_servicer: Address = Address::random();
+        let increase_amount: U256 = U256::from(rand::random::<u64>());
+        bidder_allocation.update_rollover_pair_off(term_repo_servicer, increase_amount);
+        bidder_allocation.update_rollover_pair_off(term_repo_servicer, increase_amount);
+
+        // Pair offs at the same servicer accumulate, and leave the purchase amount of the bidder untouched
+        assert_eq!(bidder_allocation.rollover_pair_offs.len(), 1);
+        assert_eq!(
+            bidder_allocation.rollover_pair_offs[&term_repo_servicer],
+            increase_amount * U256::from(2)
+        );
+        assert_eq!(bidder_allocation.purchase_amount, U256::ZERO);
+    }
+
+    #[test]
+    fn test_try_update_bidder_amounts_overflow() {
+        let mut bidder_allocation: BidderAllocation = BidderAllocation::default();
+        let term_repo_servicer: Address = Address::random();
+        bidder_allocation.update_purchase_amount(U256::MAX);
+        bidder_allocation.update_collateral_amount(U256::MAX);
+        bidder_allocation.update_repurchase_obligation(U256::MAX, U256::ZERO);
+        bidder_allocation.update_rollover_pair_off(term_repo_servicer, U256::MAX);
+
+        // The checked variants error and leave the amounts untouched
+        let one: U256 = U256::from(1);
+        assert_eq!(
+            bidder_allocation.try_update_purchase_amount(one),
+            Err(OverflowError)
+        );
+        assert_eq!(
+            bidder_allocation.try_update_collateral_amount(one),
+            Err(OverflowError)
+        );
+        assert_eq!(
+            bidder_allocation.try_update_repurchase_obligation(one, one),
+            Err(OverflowError)
+        );
+        assert_eq!(
+            bidder_allocation.try_update_rollover_pair_off(term_repo_servicer, one),
+            Err(OverflowError)
+        );
+        assert_eq!(bidder_allocation.purchase_amount, U256::MAX);
+        assert_eq!(bidder_allocation.collateral_amount, U256::MAX);
+        assert_eq!(
+            bidder_allocation.repurchase_obligation,
+            RepurchaseObligation {
+                repurchase_amount: U256::MAX,
+                collateral_amount: U256::ZERO,
+            }
+        );
+        assert_eq!(
+            bidder_allocation.rollover_pair_offs[&term_repo_servicer],
+            U256::MAX
+        );
+
+        // While the saturating ones cap
+        bidder_allocation.update_purchase_amount(one);
+        bidder_allocation.update_collateral_amount(one);
+        bidder_allocation.update_repurchase_obligation(one, one);
+        bidder_allocation.update_rollover_pair_off(term_repo_servicer, one);
+        assert_eq!(bidder_allocation.purchase_amount, U256::MAX);
+        assert_eq!(bidder_allocation.collateral_amount, U256::MAX);
+        assert_eq!(
+            bidder_allocation.repurchase_obligation,
+            RepurchaseObligation {
+                repurchase_amount: U256::MAX,
+                collateral_amount: one,
+            }
+        );
+        assert_eq!(
+            bidder_allocation.rollover_pair_offs[&term_repo_servicer],
+            U256::MAX
+        );
+    }
+
+    #[test]
+    fn test_bidder_allocation_getters() {
+        let mut bidder_allocation: BidderAllocation = BidderAllocation::default();
+        let purchase_amount: U256 = U256::from(rand::random::<u64>());
+        let collateral_amount: U256 = U256::from(rand::random::<u64>());
+        let repurchase_amount: U256 = U256::from(rand::random::<u64>());
+        let repurchase_collateral_amount: U256 = U256::from(rand::random::<u64>());
+
+        bidder_allocation.update_purchase_amount(purchase_amount);
+        assert_eq!(bidder_allocation.purchase_amount(), purchase_amount);
+
+        bidder_allocation.update_collateral_amount(collateral_amount);
+        assert_eq!(bidder_allocation.collateral_amount(), collateral_amount);
+
+        bidder_allocation
+            .update_repurchase_obligation(repurchase_amount, repurchase_collateral_amount);
+        assert_eq!(
+            bidder_allocation
+                .repurchase_obligation()
+                .repurchase_amount(),
+            repurchase_amount
+        );
+        assert_eq!(
+            bidder_allocation
+                .repurchase_obligation()
+                .collateral_amount(),
+            repurchase_collateral_amount
+        );
+
+        let term_repo_servicer: Address = Address::random();
+        bidder_allocation.update_rollover_pair_off(term_repo_servicer, purchase_amount);
+        assert_eq!(
+            bidder_allocation.rollover_pair_offs()[&term_repo_servicer],
+            purchase_amount
+        );
+    }
+
     #[test]
     fn test_bidder_add_from_order() {
         let mut bidder_allocations: BidderAllocations = BidderAllocations::new();
@@ -290,6 +638,9 @@ mod test {
             random_undercollateralized_bid(&tokens.purchasePrice, &tokens.collateralPrice);
         let non_revealed_bid: Bid =
             random_collateralized_non_revealed_bid(&tokens.purchasePrice, &tokens.collateralPrice);
+        let mut dust_bid: Bid =
+            random_collateralized_revealed_bid(&tokens.purchasePrice, &tokens.collateralPrice);
+        dust_bid.amount = U256::from(MIN_ORDER_AMOUNT - 1);
 
         let placed_bids: Bids = Bids::from([
             (
@@ -304,6 +655,7 @@ mod test {
                 get_key(&undercollateralized_bid.bidder, &undercollateralized_bid.id),
                 undercollateralized_bid.clone(),
             ),
+            (get_key(&dust_bid.bidder, &dust_bid.id), dust_bid.clone()),
         ]);
 
         let validated_bids: ValidatedBids =
@@ -358,6 +710,22 @@ mod test {
                 .repurchase_obligation,
             RepurchaseObligation::default()
         );
+
+        // Dust bid is added to allocations
+        assert_eq!(
+            bidder_allocations
+                .get(&dust_bid.bidder)
+                .unwrap()
+                .collateral_amount,
+            dust_bid.collateral_amount
+        );
+        assert_eq!(
+            bidder_allocations
+                .get(&dust_bid.bidder)
+                .unwrap()
+                .purchase_amount,
+            U256::ZERO
+        );
     }
 
     #[test]
@@ -423,5 +791,23 @@ mod test {
                 collateralAmount: bidder_collateral_amount,
             }),
         );
+
+        // Proper bidder rollover pair off allocation pushes a withdrawal to the term repo servicer
+        let mut exit_leaves: ExitLeaves = ExitLeaves::new();
+        let bidder_address: Address = Address::random();
+        let term_repo_servicer: Address = Address::random();
+        let pair_off_amount: U256 = U256::from(rand::random::<u128>());
+        let mut bidder_allocation: BidderAllocation = BidderAllocation::default();
+        bidder_allocation.update_rollover_pair_off(term_repo_servicer, pair_off_amount);
+        bidder_allocation.into_exit_leaves(bidder_address, &tokens, &mut exit_leaves);
+        assert_eq!(exit_leaves.len(), 1);
+        assert_eq!(
+            exit_leaves[0],
+            ExitLeaf::TokenWithdrawal(ExitLeafTokenWithdrawal {
+                recipient: term_repo_servicer,
+                token: tokens.purchaseToken,
+                amount: pair_off_amount,
+            }),
+        );
     }
 }
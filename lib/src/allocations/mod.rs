@@ -1,21 +1,56 @@
 pub mod bidder_allocations;
 pub mod offeror_allocations;
 
+use alloc::collections::BTreeMap;
 use alloy_primitives::{Address, U256};
 use bidder_allocations::BidderAllocations;
+use core::{
+    error, fmt,
+    ops::{Deref, DerefMut},
+};
 use offeror_allocations::OfferorAllocations;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     auction_parameters::AuctionParameters,
-    exit_tree::{ExitLeaf, ExitLeafTokenWithdrawal, ExitLeaves},
+    exit_tree::{ExitLeaf, ExitLeafSink, ExitLeafTokenWithdrawal},
 };
 
+/// Error returned by the checked `try_update_*` methods when an allocation amount would overflow.
+///
+/// The regular `update_*` methods saturate at `U256::MAX` instead, which would hide an accounting bug.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct OverflowError;
+
+impl fmt::Display for OverflowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "allocation amount overflows")
+    }
+}
+
+impl error::Error for OverflowError {}
+
+/// Panics if a `try_update_*` call overflowed, which is how the `update_*` methods of checked allocations fail.
+///
+/// # Arguments
+///
+/// * `result` - The result of the `try_update_*` call.
+fn expect_no_overflow(result: Result<(), OverflowError>) {
+    if let Err(error) = result {
+        panic!("{}", error);
+    }
+}
+
 /// Represents the allocation for the prover, which is credited with all the accrued fees
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
 pub struct ProverAllocation {
     /// The Ethereum address of the prover
     prover_address: Address,
     /// The amount of purchase tokens, result of accrued fees, that are to be credited to the prover
     purchase_amount: U256,
+    /// Whether updates panic on overflow instead of saturating
+    #[serde(skip)]
+    checked: bool,
 }
 
 impl ProverAllocation {
@@ -28,9 +63,20 @@ impl ProverAllocation {
         Self {
             prover_address: *prover_address,
             purchase_amount: U256::ZERO,
+            checked: false,
         }
     }
 
+    /// Returns the address of the prover.
+    pub fn prover_address(&self) -> Address {
+        self.prover_address
+    }
+
+    /// Returns the amount of purchase tokens credited to the prover.
+    pub fn purchase_amount(&self) -> U256 {
+        self.purchase_amount
+    }
+
     /// Updates the prover allocation purchase amount by adding the given amount.
     ///
     /// # Arguments
@@ -38,7 +84,29 @@ impl ProverAllocation {
     /// * `self` - The prover allocation to update.
     /// * `amount` - The amount to increase it by.
     pub fn update_purchase_amount(&mut self, amount: U256) {
-        self.purchase_amount = self.purchase_amount.saturating_add(amount);
+        if self.checked {
+            expect_no_overflow(self.try_update_purchase_amount(amount));
+        } else {
+            self.purchase_amount = self.purchase_amount.saturating_add(amount);
+        }
+    }
+
+    /// Updates the prover allocation purchase amount by adding the given amount, failing on overflow.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The prover allocation to update.
+    /// * `amount` - The amount to increase it by.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), OverflowError>` - An error if the amount overflows, in which case the allocation is left untouched.
+    pub fn try_update_purchase_amount(&mut self, amount: U256) -> Result<(), OverflowError> {
+        self.purchase_amount = self
+            .purchase_amount
+            .checked_add(amount)
+            .ok_or(OverflowError)?;
+        Ok(())
     }
 
     /// Converts the ProverAllocation into exit leaves
@@ -47,8 +115,8 @@ impl ProverAllocation {
     ///
     /// * `self` - The prover allocation to convert.
     /// * `tokens` - A reference to the `AuctionParameters` struct containing token information.
-    /// * `exit_leaves` - A mutable reference to the vector of exit leaves to update.
-    fn into_exit_leaves(self, tokens: &AuctionParameters, exit_leaves: &mut ExitLeaves) {
+    /// * `exit_leaves` - A mutable reference to the sink the exit leaves are pushed to, such as a vector of exit leaves.
+    fn into_exit_leaves<S: ExitLeafSink>(self, tokens: &AuctionParameters, exit_leaves: &mut S) {
         if self.purchase_amount != U256::ZERO {
             exit_leaves.push(ExitLeaf::TokenWithdrawal(ExitLeafTokenWithdrawal {
                 recipient: self.prover_address,
@@ -61,19 +129,19 @@ impl ProverAllocation {
 
 /// Trait for converting allocations into exit leaves
 pub trait Allocation {
-    /// Converts the allocation mapping into exit leaves and adds them to the `exit_leaves` vector.
+    /// Converts the allocation mapping into exit leaves and pushes them to `exit_leaves`.
     ///
     /// # Arguments
     ///
     /// * `self` - The allocation to convert.
     /// * `address` - The address associated with this allocation.
     /// * `tokens` - A reference to the `AuctionParameters` struct containing token information.
-    /// * `exit_leaves` - A mutable reference to the vector of exit leaves to update.
-    fn into_exit_leaves(
+    /// * `exit_leaves` - A mutable reference to the sink the exit leaves are pushed to, such as a vector of exit leaves.
+    fn into_exit_leaves<S: ExitLeafSink>(
         self,
         address: Address,
         tokens: &AuctionParameters,
-        exit_leaves: &mut ExitLeaves,
+        exit_leaves: &mut S,
     );
 }
 
@@ -99,7 +167,69 @@ pub trait Allocations {
     fn add_from_order(&mut self, order: &Self::Order);
 }
 
+/// A map of addresses to their respective allocations.
+///
+/// Allocations handed out by a checked map go through their `try_update_*` methods and panic on overflow, instead of
+/// saturating at `U256::MAX`. Whether a map is checked is not serialized, so deserialized maps always saturate.
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(transparent)]
+pub struct AllocationMap<A> {
+    /// The allocation of every address.
+    allocations: BTreeMap<Address, A>,
+    /// Whether the allocations handed out panic on overflow instead of saturating.
+    #[serde(skip)]
+    checked: bool,
+}
+
+impl<A> AllocationMap<A> {
+    /// Creates an empty map, whose allocations saturate on overflow.
+    pub fn new() -> Self {
+        Self {
+            allocations: BTreeMap::new(),
+            checked: false,
+        }
+    }
+
+    /// Creates an empty map, whose allocations panic on overflow.
+    pub fn new_checked() -> Self {
+        Self {
+            allocations: BTreeMap::new(),
+            checked: true,
+        }
+    }
+}
+
+impl<A> Default for AllocationMap<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A> Deref for AllocationMap<A> {
+    type Target = BTreeMap<Address, A>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.allocations
+    }
+}
+
+impl<A> DerefMut for AllocationMap<A> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.allocations
+    }
+}
+
+impl<A> IntoIterator for AllocationMap<A> {
+    type Item = (Address, A);
+    type IntoIter = alloc::collections::btree_map::IntoIter<Address, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.allocations.into_iter()
+    }
+}
+
 /// Represents the results of the auction
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
 pub struct AuctionResults {
     /// The prover's allocation
     pub prover_allocation: ProverAllocation,
@@ -123,14 +253,74 @@ impl AuctionResults {
         }
     }
 
+    /// Creates a new AuctionResults instance with the given prover address, whose allocations panic on overflow.
+    ///
+    /// Allocations created by `new` saturate at `U256::MAX` instead, which keeps the auction running but would hide
+    /// an accounting bug. Meant for development and testing.
+    ///
+    /// # Arguments
+    ///
+    /// * `prover_address` - The address of the prover.
+    pub fn new_checked(prover_address: &Address) -> Self {
+        AuctionResults {
+            prover_allocation: ProverAllocation {
+                checked: true,
+                ..ProverAllocation::new(prover_address)
+            },
+            bidder_allocations: BidderAllocations::new_checked(),
+            offeror_allocations: OfferorAllocations::new_checked(),
+        }
+    }
+
+    /// Merges the allocations of another `AuctionResults` into these, such as those computed by another worker over
+    /// a different share of the orders. Allocations of addresses present in both are added up, saturating on overflow.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The auction results being merged into.
+    /// * `other` - The auction results to merge.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `other` credits a different prover.
+    pub fn merge(&mut self, other: AuctionResults) {
+        assert_eq!(
+            self.prover_allocation.prover_address, other.prover_allocation.prover_address,
+            "Cannot merge auction results credited to different provers"
+        );
+        self.prover_allocation
+            .update_purchase_amount(other.prover_allocation.purchase_amount);
+
+        for (address, bidder_allocation) in other.bidder_allocations.into_iter() {
+            self.bidder_allocations
+                .get_allocation(&address)
+                .merge(bidder_allocation);
+        }
+
+        for (address, offeror_allocation) in other.offeror_allocations.into_iter() {
+            self.offeror_allocations
+                .get_allocation(&address)
+                .merge(offeror_allocation);
+        }
+    }
+
     /// Converts all auction result allocations into exit leaves
     ///
+    /// The leaves are pushed in a canonical order, so that the same results always hash to the same exit root: first
+    /// the prover leaf, then the leaves of every bidder and then those of every offeror, each in ascending order of
+    /// address. Allocations are kept in address-ordered maps, so the order in which they were filled in does not
+    /// matter.
+    ///
     /// # Arguments
     ///
     /// * `self` - The allocations instance
     /// * `tokens` - A reference to the `AuctionParameters` struct containing token information.
-    /// * `exit_leaves` - A mutable reference to the vector of exit leaves to update.
-    pub fn into_exit_leaves(self, tokens: &AuctionParameters, exit_leaves: &mut ExitLeaves) {
+    /// * `exit_leaves` - A mutable reference to the sink the exit leaves are pushed to, such as a vector of exit leaves.
+    pub fn into_exit_leaves<S: ExitLeafSink>(
+        self,
+        tokens: &AuctionParameters,
+        exit_leaves: &mut S,
+    ) {
         self.prover_allocation.into_exit_leaves(tokens, exit_leaves);
 
         for (address, bidder_allocation) in self.bidder_allocations.into_iter() {
@@ -146,12 +336,20 @@ impl AuctionResults {
 #[cfg(test)]
 mod test {
     use crate::{
+        auction::run,
         auction_parameters::tests::random_auction_parameters,
-        exit_tree::{ExitLeafRepoTokenWithdrawal, ExitLeafRepurchaseObligation},
+        exit_tree::{
+            ExitLeafRepoTokenWithdrawal, ExitLeafRepurchaseObligation, ExitLeaves, ExitTree,
+        },
+        orders::{
+            bids::{tests::random_bid_submission, BidSubmission, Bids},
+            offers::{tests::random_offer_submission, OfferSubmission, Offers},
+            PlacedOrders,
+        },
     };
 
     use super::*;
-    use alloy_primitives::{Address, U256};
+    use alloy_primitives::{keccak256, Address, U256};
     use bidder_allocations::BidderAllocation;
     use offeror_allocations::OfferorAllocation;
 
@@ -165,6 +363,29 @@ mod test {
         assert_eq!(prover_allocation.purchase_amount, increase_amount);
     }
 
+    #[test]
+    fn test_try_update_purchase_amount_prover() {
+        let mut prover_allocation: ProverAllocation = ProverAllocation::new(&Address::random());
+        prover_allocation.update_purchase_amount(U256::MAX);
+
+        // The checked variant errors and leaves the amount untouched, the saturating one caps
+        assert_eq!(
+            prover_allocation.try_update_purchase_amount(U256::from(1)),
+            Err(OverflowError)
+        );
+        assert_eq!(prover_allocation.purchase_amount, U256::MAX);
+        prover_allocation.update_purchase_amount(U256::from(1));
+        assert_eq!(prover_allocation.purchase_amount, U256::MAX);
+
+        let mut prover_allocation: ProverAllocation = ProverAllocation::new(&Address::random());
+        let increase_amount: U256 = U256::from(rand::random::<u64>());
+        assert_eq!(
+            prover_allocation.try_update_purchase_amount(increase_amount),
+            Ok(())
+        );
+        assert_eq!(prover_allocation.purchase_amount, increase_amount);
+    }
+
     #[test]
     fn test_prover_into_exit_leaves() {
         let mut exit_leaves: ExitLeaves = ExitLeaves::new();
@@ -266,4 +487,295 @@ mod test {
             }
         )));
     }
+
+    #[test]
+    fn test_into_exit_leaves_deterministic() {
+        let tokens: AuctionParameters = random_auction_parameters();
+        let prover_address: Address = Address::random();
+        let addresses: Vec<Address> = (0..5).map(|_| Address::random()).collect();
+        let term_repo_servicers: Vec<Address> = (0..3).map(|_| Address::random()).collect();
+
+        // The same allocations, filled in forwards and backwards
+        let into_exit_leaves = |addresses: Vec<Address>, term_repo_servicers: &[Address]| {
+            let mut auction_results: AuctionResults = AuctionResults::new(&prover_address);
+            auction_results
+                .prover_allocation
+                .update_purchase_amount(U256::from(10));
+            for address in addresses {
+                let amount: U256 = U256::from_be_slice(address.as_slice());
+                let bidder_allocation: &mut BidderAllocation =
+                    auction_results.bidder_allocations.get_allocation(&address);
+                bidder_allocation.update_purchase_amount(amount);
+                bidder_allocation.update_collateral_amount(amount);
+                bidder_allocation.update_repurchase_obligation(amount, amount);
+                for term_repo_servicer in term_repo_servicers {
+                    bidder_allocation.update_rollover_pair_off(*term_repo_servicer, amount);
+                }
+                let offeror_allocation: &mut OfferorAllocation =
+                    auction_results.offeror_allocations.get_allocation(&address);
+                offeror_allocation.update_repo_amount(amount);
+                offeror_allocation.update_purchase_amount(amount);
+            }
+
+            let mut exit_leaves: ExitLeaves = ExitLeaves::new();
+            auction_results.into_exit_leaves(&tokens, &mut exit_leaves);
+            exit_leaves
+        };
+        let forwards: ExitLeaves = into_exit_leaves(addresses.clone(), &term_repo_servicers);
+        let mut reversed_term_repo_servicers: Vec<Address> = term_repo_servicers.clone();
+        reversed_term_repo_servicers.reverse();
+        let backwards: ExitLeaves = into_exit_leaves(
+            addresses.iter().rev().copied().collect(),
+            &reversed_term_repo_servicers,
+        );
+
+        assert_eq!(forwards, backwards);
+        assert_eq!(
+            forwards.hash_exit_root(&|x: &[u8]| keccak256(x)),
+            backwards.hash_exit_root(&|x: &[u8]| keccak256(x))
+        );
+
+        // The prover leaf comes first, then bidders and offerors in ascending order of address
+        let mut sorted_addresses: Vec<Address> = addresses.clone();
+        sorted_addresses.sort();
+        let mut sorted_term_repo_servicers: Vec<Address> = term_repo_servicers.clone();
+        sorted_term_repo_servicers.sort();
+        let mut expected_recipients: Vec<Address> = vec![prover_address];
+        for address in sorted_addresses.iter() {
+            // Purchase and collateral withdrawals, repurchase obligation and rollover pair-offs
+            expected_recipients.extend([*address; 3]);
+            expected_recipients.extend(sorted_term_repo_servicers.iter().copied());
+        }
+        for address in sorted_addresses.iter() {
+            // Repo token and purchase token withdrawals
+            expected_recipients.extend([*address; 2]);
+        }
+        let recipients: Vec<Address> = forwards.iter().map(ExitLeaf::recipient).collect();
+        assert_eq!(recipients, expected_recipients);
+    }
+
+    #[test]
+    fn test_auction_results_serde_round_trip() {
+        let prover_address: Address = Address::random();
+        let mut auction_results: AuctionResults = AuctionResults::new(&prover_address);
+        auction_results
+            .prover_allocation
+            .update_purchase_amount(U256::from(50));
+
+        let offeror_address: Address = Address::random();
+        let offeror_allocation: &mut OfferorAllocation = auction_results
+            .offeror_allocations
+            .get_allocation(&offeror_address);
+        offeror_allocation.update_repo_amount(U256::from(100));
+        offeror_allocation.update_purchase_amount(U256::from(200));
+
+        let bidder_address: Address = Address::random();
+        let term_repo_servicer: Address = Address::random();
+        let bidder_allocation: &mut BidderAllocation = auction_results
+            .bidder_allocations
+            .get_allocation(&bidder_address);
+        bidder_allocation.update_purchase_amount(U256::from(300));
+        bidder_allocation.update_collateral_amount(U256::from(400));
+        bidder_allocation.update_repurchase_obligation(U256::from(500), U256::from(600));
+        bidder_allocation.update_rollover_pair_off(term_repo_servicer, U256::from(700));
+
+        let json: String = serde_json::to_string(&auction_results).unwrap();
+        let deserialized: AuctionResults = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, auction_results);
+
+        // Every amount survives the round trip
+        assert_eq!(
+            deserialized.prover_allocation.prover_address(),
+            prover_address
+        );
+        assert_eq!(
+            deserialized.prover_allocation.purchase_amount(),
+            U256::from(50)
+        );
+
+        let offeror_allocation: &OfferorAllocation =
+            &deserialized.offeror_allocations[&offeror_address];
+        assert_eq!(offeror_allocation.repo_amount(), U256::from(100));
+        assert_eq!(offeror_allocation.purchase_amount(), U256::from(200));
+
+        let bidder_allocation: &BidderAllocation =
+            &deserialized.bidder_allocations[&bidder_address];
+        assert_eq!(bidder_allocation.purchase_amount(), U256::from(300));
+        assert_eq!(bidder_allocation.collateral_amount(), U256::from(400));
+        assert_eq!(
+            bidder_allocation
+                .repurchase_obligation()
+                .repurchase_amount(),
+            U256::from(500)
+        );
+        assert_eq!(
+            bidder_allocation
+                .repurchase_obligation()
+                .collateral_amount(),
+            U256::from(600)
+        );
+        assert_eq!(
+            bidder_allocation.rollover_pair_offs()[&term_repo_servicer],
+            U256::from(700)
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let prover_address: Address = Address::random();
+        let tokens: AuctionParameters = random_auction_parameters();
+
+        // Orders are left unrevealed, so that each share unlocks them the same way the whole auction does. Every
+        // address places orders in both shares, so their allocations need to be added up.
+        let bidders: Vec<Address> = (0..3).map(|_| Address::random()).collect();
+        let offerors: Vec<Address> = (0..3).map(|_| Address::random()).collect();
+        let bid_submissions: Vec<BidSubmission> = (0..12)
+            .map(|i| BidSubmission {
+                bidder: bidders[i % bidders.len()],
+                ..random_bid_submission()
+            })
+            .collect();
+        let offer_submissions: Vec<OfferSubmission> = (0..12)
+            .map(|i| OfferSubmission {
+                offeror: offerors[i % offerors.len()],
+                ..random_offer_submission()
+            })
+            .collect();
+
+        let (bids, offers) = place_orders(&bid_submissions, &offer_submissions);
+        let single_pass: AuctionResults = run(&prover_address, bids, offers, &tokens).unwrap().0;
+
+        let (first_bids, first_offers) =
+            place_orders(&bid_submissions[..6], &offer_submissions[..6]);
+        let (second_bids, second_offers) =
+            place_orders(&bid_submissions[6..], &offer_submissions[6..]);
+        let mut merged: AuctionResults = run(&prover_address, first_bids, first_offers, &tokens)
+            .unwrap()
+            .0;
+        merged.merge(
+            run(&prover_address, second_bids, second_offers, &tokens)
+                .unwrap()
+                .0,
+        );
+
+        assert_eq!(merged, single_pass);
+        let exit_root = |auction_results: AuctionResults| {
+            let mut exit_leaves: ExitLeaves = ExitLeaves::new();
+            auction_results.into_exit_leaves(&tokens, &mut exit_leaves);
+            exit_leaves.hash_exit_root(&|x: &[u8]| keccak256(x))
+        };
+        assert_eq!(exit_root(merged), exit_root(single_pass));
+    }
+
+    #[test]
+    fn test_auction_results_saturate_on_overflow() {
+        let mut auction_results: AuctionResults = AuctionResults::new(&Address::random());
+        let address: Address = Address::random();
+
+        let offeror_allocation: &mut OfferorAllocation =
+            auction_results.offeror_allocations.get_allocation(&address);
+        offeror_allocation.update_repo_amount(U256::MAX);
+        offeror_allocation.update_repo_amount(U256::from(1));
+        assert_eq!(offeror_allocation.repo_amount(), U256::MAX);
+
+        let bidder_allocation: &mut BidderAllocation =
+            auction_results.bidder_allocations.get_allocation(&address);
+        bidder_allocation.update_repurchase_obligation(U256::MAX, U256::ZERO);
+        bidder_allocation.update_repurchase_obligation(U256::from(1), U256::ZERO);
+        assert_eq!(
+            bidder_allocation
+                .repurchase_obligation()
+                .repurchase_amount(),
+            U256::MAX
+        );
+
+        auction_results
+            .prover_allocation
+            .update_purchase_amount(U256::MAX);
+        auction_results
+            .prover_allocation
+            .update_purchase_amount(U256::from(1));
+        assert_eq!(auction_results.prover_allocation.purchase_amount, U256::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "allocation amount overflows")]
+    fn test_checked_auction_results_panic_on_overflow() {
+        let mut auction_results: AuctionResults = AuctionResults::new_checked(&Address::random());
+        let offeror_allocation: &mut OfferorAllocation = auction_results
+            .offeror_allocations
+            .get_allocation(&Address::random());
+
+        // Sums that fit are still added up
+        offeror_allocation.update_repo_amount(U256::MAX - U256::from(1));
+        offeror_allocation.update_repo_amount(U256::from(1));
+        assert_eq!(offeror_allocation.repo_amount(), U256::MAX);
+
+        offeror_allocation.update_repo_amount(U256::from(1));
+    }
+
+    #[test]
+    fn test_checked_auction_results_panic_on_every_allocation() {
+        let overflows = |update: fn(&mut AuctionResults)| {
+            std::panic::catch_unwind(|| {
+                let mut auction_results: AuctionResults =
+                    AuctionResults::new_checked(&Address::random());
+                update(&mut auction_results);
+            })
+            .is_err()
+        };
+
+        assert!(overflows(|auction_results| {
+            let prover_allocation: &mut ProverAllocation = &mut auction_results.prover_allocation;
+            prover_allocation.update_purchase_amount(U256::MAX);
+            prover_allocation.update_purchase_amount(U256::from(1));
+        }));
+        assert!(overflows(|auction_results| {
+            let bidder_allocation: &mut BidderAllocation = auction_results
+                .bidder_allocations
+                .get_allocation(&Address::ZERO);
+            bidder_allocation.update_collateral_amount(U256::MAX);
+            bidder_allocation.update_collateral_amount(U256::from(1));
+        }));
+        assert!(overflows(|auction_results| {
+            let bidder_allocation: &mut BidderAllocation = auction_results
+                .bidder_allocations
+                .get_allocation(&Address::ZERO);
+            bidder_allocation.update_rollover_pair_off(Address::ZERO, U256::MAX);
+            bidder_allocation.update_rollover_pair_off(Address::ZERO, U256::from(1));
+        }));
+        assert!(overflows(|auction_results| {
+            let offeror_allocation: &mut OfferorAllocation = auction_results
+                .offeror_allocations
+                .get_allocation(&Address::ZERO);
+            offeror_allocation.update_purchase_amount(U256::MAX);
+            offeror_allocation.update_purchase_amount(U256::from(1));
+        }));
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot merge auction results credited to different provers")]
+    fn test_merge_different_provers() {
+        let mut auction_results: AuctionResults = AuctionResults::new(&Address::random());
+        auction_results.merge(AuctionResults::new(&Address::random()));
+    }
+
+    // HELPER FUNCTIONS
+    /// Places the given bid and offer submissions into new orders mappings.
+    fn place_orders(
+        bid_submissions: &[BidSubmission],
+        offer_submissions: &[OfferSubmission],
+    ) -> (Bids, Offers) {
+        let mut bids: Bids = Bids::new();
+        for bid_submission in bid_submissions {
+            bids.save_or_update_order(bid_submission);
+        }
+
+        let mut offers: Offers = Offers::new();
+        for offer_submission in offer_submissions {
+            offers.save_or_update_order(offer_submission);
+        }
+
+        (bids, offers)
+    }
 }
@@ -1,20 +1,24 @@
 use alloy_primitives::{Address, U256};
-use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     auction_parameters::AuctionParameters,
-    exit_tree::{ExitLeaf, ExitLeafRepoTokenWithdrawal, ExitLeafTokenWithdrawal, ExitLeaves},
+    exit_tree::{ExitLeaf, ExitLeafRepoTokenWithdrawal, ExitLeafSink, ExitLeafTokenWithdrawal},
     orders::offers::Offer,
 };
 
-use super::{Allocation, Allocations};
+use super::{expect_no_overflow, Allocation, AllocationMap, Allocations, OverflowError};
 
 /// Represents the allocation for an offeror in the auction.
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
 pub struct OfferorAllocation {
     /// The amount of repo tokens assigned to the offeror, if any.
     repo_amount: U256,
     /// The amount of purchase tokens left on the table for the offeror, if any.
     purchase_amount: U256,
+    /// Whether updates panic on overflow instead of saturating.
+    #[serde(skip)]
+    checked: bool,
 }
 
 impl Default for OfferorAllocation {
@@ -23,11 +27,22 @@ impl Default for OfferorAllocation {
         Self {
             repo_amount: U256::ZERO,
             purchase_amount: U256::ZERO,
+            checked: false,
         }
     }
 }
 
 impl OfferorAllocation {
+    /// Returns the amount of repo tokens assigned to the offeror.
+    pub fn repo_amount(&self) -> U256 {
+        self.repo_amount
+    }
+
+    /// Returns the amount of purchase tokens left on the table for the offeror.
+    pub fn purchase_amount(&self) -> U256 {
+        self.purchase_amount
+    }
+
     /// Updates the repo token amount for the offeror.
     ///
     /// # Arguments
@@ -35,7 +50,11 @@ impl OfferorAllocation {
     /// * `self` - The allocation to be updated.
     /// * `amount` - The amount to add to the current repo amount.
     pub fn update_repo_amount(&mut self, amount: U256) {
-        self.repo_amount = self.repo_amount.saturating_add(amount);
+        if self.checked {
+            expect_no_overflow(self.try_update_repo_amount(amount));
+        } else {
+            self.repo_amount = self.repo_amount.saturating_add(amount);
+        }
     }
 
     /// Updates the purchase token amount for the offeror.
@@ -45,16 +64,64 @@ impl OfferorAllocation {
     /// * `self` - The allocation to be updated.
     /// * `amount` - The amount to add to the current purchase amount.
     pub fn update_purchase_amount(&mut self, amount: U256) {
-        self.purchase_amount = self.purchase_amount.saturating_add(amount);
+        if self.checked {
+            expect_no_overflow(self.try_update_purchase_amount(amount));
+        } else {
+            self.purchase_amount = self.purchase_amount.saturating_add(amount);
+        }
+    }
+
+    /// Updates the repo token amount for the offeror, failing on overflow.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The allocation to be updated.
+    /// * `amount` - The amount to add to the current repo amount.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), OverflowError>` - An error if the amount overflows, in which case the allocation is left untouched.
+    pub fn try_update_repo_amount(&mut self, amount: U256) -> Result<(), OverflowError> {
+        self.repo_amount = self.repo_amount.checked_add(amount).ok_or(OverflowError)?;
+        Ok(())
+    }
+
+    /// Updates the purchase token amount for the offeror, failing on overflow.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The allocation to be updated.
+    /// * `amount` - The amount to add to the current purchase amount.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), OverflowError>` - An error if the amount overflows, in which case the allocation is left untouched.
+    pub fn try_update_purchase_amount(&mut self, amount: U256) -> Result<(), OverflowError> {
+        self.purchase_amount = self
+            .purchase_amount
+            .checked_add(amount)
+            .ok_or(OverflowError)?;
+        Ok(())
+    }
+
+    /// Adds every amount of another allocation of the same offeror to this one.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The allocation to be updated.
+    /// * `other` - The allocation being merged into it.
+    pub fn merge(&mut self, other: OfferorAllocation) {
+        self.update_repo_amount(other.repo_amount);
+        self.update_purchase_amount(other.purchase_amount);
     }
 }
 
 impl Allocation for OfferorAllocation {
-    fn into_exit_leaves(
+    fn into_exit_leaves<S: ExitLeafSink>(
         self,
         address: Address,
         tokens: &AuctionParameters,
-        exit_leaves: &mut ExitLeaves,
+        exit_leaves: &mut S,
     ) {
         if self.repo_amount != U256::ZERO {
             exit_leaves.push(ExitLeaf::RepoTokenWithdrawal(ExitLeafRepoTokenWithdrawal {
@@ -74,7 +141,7 @@ impl Allocation for OfferorAllocation {
 }
 
 /// A map of offeror addresses to their respective allocations.
-pub type OfferorAllocations = BTreeMap<Address, OfferorAllocation>;
+pub type OfferorAllocations = AllocationMap<OfferorAllocation>;
 
 impl Allocations for OfferorAllocations {
     type Allocation = OfferorAllocation;
@@ -86,7 +153,13 @@ impl Allocations for OfferorAllocations {
     }
 
     fn get_allocation(&mut self, address: &Address) -> &mut OfferorAllocation {
-        self.entry(*address).or_default()
+        let checked: bool = self.checked;
+        self.allocations
+            .entry(*address)
+            .or_insert_with(|| OfferorAllocation {
+                checked,
+                ..OfferorAllocation::default()
+            })
     }
 }
 
@@ -96,6 +169,8 @@ mod test {
     use crate::{
         allocations::AuctionResults,
         auction_parameters::tests::random_auction_parameters,
+        constants::MIN_ORDER_AMOUNT,
+        exit_tree::ExitLeaves,
         orders::{
             offers::{
                 tests::{random_offer_submission, random_revealed_offer},
@@ -129,6 +204,57 @@ mod test {
         assert_eq!(offeror_allocation.purchase_amount, increase_amount);
     }
 
+    #[test]
+    fn test_try_update_offeror_amounts_overflow() {
+        let mut offeror_allocation: OfferorAllocation = OfferorAllocation::default();
+        offeror_allocation.update_repo_amount(U256::MAX);
+        offeror_allocation.update_purchase_amount(U256::MAX);
+
+        // The checked variants error and leave the amounts untouched, the saturating ones cap
+        assert_eq!(
+            offeror_allocation.try_update_repo_amount(U256::from(1)),
+            Err(OverflowError)
+        );
+        assert_eq!(
+            offeror_allocation.try_update_purchase_amount(U256::from(1)),
+            Err(OverflowError)
+        );
+        assert_eq!(offeror_allocation.repo_amount, U256::MAX);
+        assert_eq!(offeror_allocation.purchase_amount, U256::MAX);
+
+        offeror_allocation.update_repo_amount(U256::from(1));
+        offeror_allocation.update_purchase_amount(U256::from(1));
+        assert_eq!(offeror_allocation.repo_amount, U256::MAX);
+        assert_eq!(offeror_allocation.purchase_amount, U256::MAX);
+
+        // Below the limit they behave like the saturating ones
+        let mut offeror_allocation: OfferorAllocation = OfferorAllocation::default();
+        let increase_amount: U256 = U256::from(rand::random::<u64>());
+        assert_eq!(
+            offeror_allocation.try_update_repo_amount(increase_amount),
+            Ok(())
+        );
+        assert_eq!(
+            offeror_allocation.try_update_purchase_amount(increase_amount),
+            Ok(())
+        );
+        assert_eq!(offeror_allocation.repo_amount, increase_amount);
+        assert_eq!(offeror_allocation.purchase_amount, increase_amount);
+    }
+
+    #[test]
+    fn test_offeror_allocation_getters() {
+        let mut offeror_allocation: OfferorAllocation = OfferorAllocation::default();
+        let repo_amount: U256 = U256::from(rand::random::<u64>());
+        let purchase_amount: U256 = U256::from(rand::random::<u64>());
+
+        offeror_allocation.update_repo_amount(repo_amount);
+        assert_eq!(offeror_allocation.repo_amount(), repo_amount);
+
+        offeror_allocation.update_purchase_amount(purchase_amount);
+        assert_eq!(offeror_allocation.purchase_amount(), purchase_amount);
+    }
+
     #[test]
     fn test_offeror_add_from_order() {
         let mut offeror_allocations: OfferorAllocations = OfferorAllocations::new();
@@ -192,6 +318,8 @@ mod test {
         let mut offeror_allocations: OfferorAllocations = OfferorAllocations::new();
         let revealed_offer: Offer = random_revealed_offer();
         let non_revealed_offer: Offer = Offer::from_order_submission(&random_offer_submission());
+        let mut dust_offer: Offer = random_revealed_offer();
+        dust_offer.amount = U256::from(MIN_ORDER_AMOUNT - 1);
 
         let placed_offers: Offers = Offers::from([
             (
@@ -202,6 +330,10 @@ mod test {
                 get_key(&non_revealed_offer.offeror, &non_revealed_offer.id),
                 non_revealed_offer.clone(),
             ),
+            (
+                get_key(&dust_offer.offeror, &dust_offer.id),
+                dust_offer.clone(),
+            ),
         ]);
 
         let validated_offers: ValidatedOffers =
@@ -226,6 +358,22 @@ mod test {
                 .repo_amount,
             U256::ZERO
         );
+
+        // Dust offer is refunded through the allocations
+        assert_eq!(
+            offeror_allocations
+                .get(&dust_offer.offeror)
+                .unwrap()
+                .purchase_amount,
+            dust_offer.amount
+        );
+        assert_eq!(
+            offeror_allocations
+                .get(&dust_offer.offeror)
+                .unwrap()
+                .repo_amount,
+            U256::ZERO
+        );
     }
 
     #[test]
@@ -1,53 +1,189 @@
-use alloy_primitives::U256;
+use alloy_primitives::{B256, U256, U512};
 
 use crate::{
     allocations::{
         bidder_allocations::{BidderAllocation, BidderAllocations},
         Allocations,
     },
+    auction_parameters::AuctionParameters,
+    constants::{BPS, INITIAL_COLLATERAL_RATIO},
     orders::bids::{Bid, ValidatedBids},
+    utils::get_key,
 };
 
 use super::{
-    calculate_repurchase_price, find_first_index_for_price, AssignableOrder, AssignableOrders,
+    calculate_repurchase_price, find_first_index_for_price, mul_div, report::OrderOutcomeSink,
+    AssignableOrder, AssignableOrders, AssignmentSummary,
 };
 
-impl AssignableOrder for Bid {
-    type Allocations = BidderAllocations;
+impl Bid {
+    /// Records the purchase tokens lent by assigning this bid.
+    ///
+    /// Rollover bids pair off an existing loan, so the purchase tokens they are lent are paid to the
+    /// `rollover_pair_off_term_repo_servicer` of that loan instead of to the bidder.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The bid being assigned.
+    /// * `bidder_allocation` - The allocation of the bidder.
+    /// * `assigned_amount` - The amount that was assigned.
+    fn update_purchase_amount(
+        &self,
+        bidder_allocation: &mut BidderAllocation,
+        assigned_amount: U256,
+    ) {
+        if self.is_rollover {
+            bidder_allocation.update_rollover_pair_off(
+                self.rollover_pair_off_term_repo_servicer,
+                assigned_amount,
+            );
+        } else {
+            bidder_allocation.update_purchase_amount(assigned_amount);
+        }
+    }
 
-    fn fully_assign(
+    /// Returns the collateral needed to back a repurchase obligation at the initial collateral ratio, rounded up and
+    /// never above the collateral locked by this bid.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The bid being assigned.
+    /// * `repurchase_amount` - The repurchase amount of the obligation.
+    /// * `auction_parameters` - The auction parameters, giving the token prices.
+    fn required_collateral(
+        &self,
+        repurchase_amount: &U256,
+        auction_parameters: &AuctionParameters,
+    ) -> U256 {
+        // Computed over 512 bits, mirroring the collateral check of `Bid::validate`
+        let obligation_side: Option<U512> = repurchase_amount
+            .widening_mul(auction_parameters.purchasePrice)
+            .checked_mul(U512::from(INITIAL_COLLATERAL_RATIO));
+        let collateral_price_side: U512 = auction_parameters
+            .collateralPrice
+            .widening_mul(U256::from(BPS));
+
+        match obligation_side {
+            Some(obligation_side) => {
+                U256::saturating_from(obligation_side.div_ceil(collateral_price_side))
+                    .min(self.collateral_amount)
+            }
+            // Keep all the collateral if the requirement cannot even be computed
+            None => self.collateral_amount,
+        }
+    }
+
+    /// Returns the value of the collateral locked by this bid, denominated in purchase tokens.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The bid being assigned.
+    /// * `auction_parameters` - The auction parameters, giving the token prices.
+    fn collateral_value(&self, auction_parameters: &AuctionParameters) -> U256 {
+        mul_div(
+            &self.collateral_amount,
+            &auction_parameters.collateralPrice,
+            &auction_parameters.purchasePrice,
+        )
+    }
+
+    /// Assigns up to `amount` of this bid, capping it so that the resulting repurchase obligation does not exceed the
+    /// value of the collateral locked, which onchain liquidation could not cover otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The bid being assigned.
+    /// * `clearing_price` - The clearing rate at which to assign the bid.
+    /// * `auction_parameters` - The auction parameters, giving the day count and the token prices.
+    /// * `amount` - The amount to assign before capping.
+    /// * `bidder_allocations` - The allocations of the bidders.
+    ///
+    /// # Returns
+    ///
+    /// * `U256` - The amount that was assigned, which is below `amount` if the cap kicked in.
+    fn assign_capped(
         &self,
         clearing_price: &U256,
-        day_count: &U256,
+        auction_parameters: &AuctionParameters,
+        amount: &U256,
         bidder_allocations: &mut BidderAllocations,
     ) -> U256 {
-        let repurchase_amount: U256 =
-            calculate_repurchase_price(&self.amount, clearing_price, day_count);
+        let mut assigned_amount: U256 = *amount;
+        let mut repurchase_amount: U256 = calculate_repurchase_price(
+            amount,
+            clearing_price,
+            &auction_parameters.dayCount,
+            &auction_parameters.dayCountConvention,
+        );
+
+        let collateral_value: U256 = self.collateral_value(auction_parameters);
+        if repurchase_amount > collateral_value {
+            // Only lend as much as the collateral covers once interest accrues, rounding down
+            assigned_amount = mul_div(amount, &collateral_value, &repurchase_amount);
+            repurchase_amount = calculate_repurchase_price(
+                &assigned_amount,
+                clearing_price,
+                &auction_parameters.dayCount,
+                &auction_parameters.dayCountConvention,
+            );
+        }
+
+        if assigned_amount == U256::ZERO {
+            self.unlock(bidder_allocations);
+            return U256::ZERO;
+        }
+
+        // A fully filled bid only keeps the collateral its obligation requires, and the excess is returned
+        let obligation_collateral: U256 = if assigned_amount == self.amount {
+            self.required_collateral(&repurchase_amount, auction_parameters)
+        } else {
+            self.collateral_amount
+        };
 
         let bidder_allocation: &mut BidderAllocation =
             bidder_allocations.get_allocation(&self.bidder);
-        bidder_allocation.update_purchase_amount(self.amount);
-        bidder_allocation.update_repurchase_obligation(repurchase_amount, self.collateral_amount);
+        self.update_purchase_amount(bidder_allocation, assigned_amount);
+        bidder_allocation.update_collateral_amount(self.collateral_amount - obligation_collateral);
+        bidder_allocation.update_repurchase_obligation(repurchase_amount, obligation_collateral);
+
+        assigned_amount
+    }
+}
 
-        self.amount
+impl AssignableOrder for Bid {
+    type Allocations = BidderAllocations;
+
+    fn key(&self) -> B256 {
+        get_key(&self.bidder, &self.id)
+    }
+
+    fn fully_assign(
+        &self,
+        clearing_price: &U256,
+        auction_parameters: &AuctionParameters,
+        bidder_allocations: &mut BidderAllocations,
+    ) -> U256 {
+        self.assign_capped(
+            clearing_price,
+            auction_parameters,
+            &self.amount,
+            bidder_allocations,
+        )
     }
 
     fn partially_assign(
         &self,
         clearing_price: &U256,
-        day_count: &U256,
+        auction_parameters: &AuctionParameters,
         assigned_amount: &U256,
         bidder_allocations: &mut BidderAllocations,
     ) -> U256 {
-        let repurchase_amount: U256 =
-            calculate_repurchase_price(assigned_amount, clearing_price, day_count);
-
-        let bidder_allocation: &mut BidderAllocation =
-            bidder_allocations.get_allocation(&self.bidder);
-        bidder_allocation.update_purchase_amount(*assigned_amount);
-        bidder_allocation.update_repurchase_obligation(repurchase_amount, self.collateral_amount);
-
-        *assigned_amount
+        self.assign_capped(
+            clearing_price,
+            auction_parameters,
+            assigned_amount,
+            bidder_allocations,
+        )
     }
 
     fn unlock(&self, bidder_allocations: &mut BidderAllocations) {
@@ -58,16 +194,16 @@ impl AssignableOrder for Bid {
 impl AssignableOrders for ValidatedBids {
     type Allocations = BidderAllocations;
 
-    fn assign(
+    fn assign_with_outcomes<O: OrderOutcomeSink + Default>(
         self,
         max_assignable: &U256,
         clearing_price: &U256,
-        day_count: &U256,
+        auction_parameters: &AuctionParameters,
         allocations: &mut BidderAllocations,
-    ) {
+    ) -> AssignmentSummary<O> {
         // Process revealed bids
+        let mut summary: AssignmentSummary<O> = AssignmentSummary::default();
         let mut total_assigned_bids: U256 = U256::ZERO;
-        let mut inner_index: usize;
         let mut i: usize;
         let mut j: usize = self.len();
 
@@ -84,74 +220,595 @@ impl AssignableOrders for ValidatedBids {
                 && price_group_amount <= (*max_assignable - total_assigned_bids)
             {
                 // FULL ASSIGNMENT
-                inner_index = 0;
-
-                while i - inner_index >= k {
-                    total_assigned_bids +=
-                        self[i - inner_index].fully_assign(clearing_price, day_count, allocations);
-
-                    if i == inner_index {
-                        break;
-                    }
-
-                    inner_index += 1;
+                for index in (k..=i).rev() {
+                    let assigned_amount: U256 =
+                        self[index].fully_assign(clearing_price, auction_parameters, allocations);
+                    summary.record(self[index].key(), &self[index].amount, &assigned_amount);
+                    total_assigned_bids += assigned_amount;
                 }
 
-                if inner_index > 0 {
-                    j -= inner_index - 1;
-                }
+                // Skip the rest of the price group
+                j = k + 1;
             } else if self[i].bid_price_revealed >= *clearing_price
                 && total_assigned_bids < *max_assignable
             {
                 // PARTIAL ASSIGNMENT
-                // Partial assignment for the entire price group
-                inner_index = 0;
-
-                while i - inner_index >= k {
-                    if i - inner_index == k {
+                // Partial assignment for the entire price group. Whatever capped bids leave unassigned is not handed
+                // to the rest of the group, so no bid is assigned more than its amount.
+                let mut remaining_assignable: U256 = max_assignable - total_assigned_bids;
+                for index in (k..=i).rev() {
+                    let assigned_amount: U256 = if index == k {
                         // Last iteration of loop. Assign remaining amount left to assign.
-                        total_assigned_bids += self[i - inner_index].partially_assign(
-                            clearing_price,
-                            day_count,
-                            &(max_assignable - total_assigned_bids),
-                            allocations,
-                        );
-                        price_group_amount -= max_assignable - total_assigned_bids;
+                        remaining_assignable
+                    } else if price_group_amount.is_zero() {
+                        // Only zero-amount bids are left in the price group, there is nothing to split among them.
+                        U256::ZERO
                     } else {
                         // Assign an amount based upon the partial assignment ratio.
-                        let assigned_amount: U256 = (self[i - inner_index].amount
-                            * (max_assignable - total_assigned_bids))
-                            / price_group_amount;
-
-                        total_assigned_bids += self[i - inner_index].partially_assign(
-                            clearing_price,
-                            day_count,
-                            &assigned_amount,
-                            allocations,
-                        );
-                        price_group_amount -= self[i - inner_index].amount;
-                    }
-
-                    inner_index += 1;
-                }
+                        mul_div(
+                            &self[index].amount,
+                            &remaining_assignable,
+                            &price_group_amount,
+                        )
+                    };
 
-                if inner_index > 0 {
-                    j -= inner_index - 1;
+                    let capped_amount: U256 = self[index].partially_assign(
+                        clearing_price,
+                        auction_parameters,
+                        &assigned_amount,
+                        allocations,
+                    );
+                    summary.record(self[index].key(), &self[index].amount, &capped_amount);
+                    total_assigned_bids += capped_amount;
+                    remaining_assignable -= assigned_amount;
+                    price_group_amount -= self[index].amount;
                 }
+
+                // Skip the rest of the price group
+                j = k + 1;
             } else {
                 // NO ASSIGNMENT
                 self[i].unlock(allocations);
+                summary.record(self[i].key(), &self[i].amount, &U256::ZERO);
             }
 
             j -= 1;
         }
+
+        summary
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use crate::{
+        allocations::Allocation,
+        auction::{
+            compute_clearing_price,
+            report::{OrderOutcome, OrderOutcomes},
+        },
+        auction_parameters::{tests::random_auction_parameters, AuctionParameters},
+        exit_tree::{ExitLeaf, ExitLeafRepurchaseObligation, ExitLeafTokenWithdrawal, ExitLeaves},
+        orders::{
+            bids::tests::random_revealed_bid,
+            offers::{tests::random_revealed_offer, Offer, ValidatedOffers},
+            ValidatedOrders,
+        },
+        utils::bps_to_internal,
+    };
+    use alloy_primitives::Address;
+
+    // A 10% clearing price over 36 days accrues 1% of interest
+    const CLEARING_PRICE: u64 = 1_000;
+    const DAY_COUNT: u64 = 36;
+
     #[test]
     fn test_assign_bids() {
-        unimplemented!()
+        let tokens: AuctionParameters = auction_parameters(DAY_COUNT);
+        let bids: ValidatedBids = vec![
+            revealed_bid(500, 1_000),
+            revealed_bid(1_500, 2_000),
+            revealed_bid(2_000, 3_000),
+        ];
+        let mut bidder_allocations: BidderAllocations = BidderAllocations::new();
+
+        bids.clone().assign(
+            &U256::from(5_000),
+            &bps_to_internal(&U256::from(CLEARING_PRICE)),
+            &tokens,
+            &mut bidder_allocations,
+        );
+
+        // Both bids above the clearing price are fully assigned, keeping 150% of their obligation as collateral and
+        // getting the excess back, while the one below gets all its collateral back
+        assert_exit_leaves(
+            bidder_allocations,
+            &tokens,
+            vec![
+                collateral_withdrawal(&bids[0], &tokens),
+                purchase_withdrawal(&bids[1], 2_000, &tokens),
+                excess_collateral_withdrawal(&bids[1], 970, &tokens),
+                covered_repurchase_obligation(&bids[1], 2_020, 3_030),
+                purchase_withdrawal(&bids[2], 3_000, &tokens),
+                excess_collateral_withdrawal(&bids[2], 1_455, &tokens),
+                covered_repurchase_obligation(&bids[2], 3_030, 4_545),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_partially_assign_bids() {
+        let tokens: AuctionParameters = auction_parameters(DAY_COUNT);
+        let bids: ValidatedBids = vec![
+            revealed_bid(500, 1_000),
+            revealed_bid(1_500, 3_000),
+            revealed_bid(1_500, 1_000),
+            revealed_bid(2_000, 2_000),
+        ];
+        let mut bidder_allocations: BidderAllocations = BidderAllocations::new();
+
+        bids.clone().assign(
+            &U256::from(4_000),
+            &bps_to_internal(&U256::from(CLEARING_PRICE)),
+            &tokens,
+            &mut bidder_allocations,
+        );
+
+        // The most competitive bid is fully assigned and gets its excess collateral back, the price group at the edge
+        // splits the remaining 2,000 pro-rata (500 + 1,500) keeping all its collateral, and the bid below the
+        // clearing price gets its collateral back
+        assert_exit_leaves(
+            bidder_allocations,
+            &tokens,
+            vec![
+                collateral_withdrawal(&bids[0], &tokens),
+                purchase_withdrawal(&bids[1], 1_500, &tokens),
+                repurchase_obligation(&bids[1], 1_515),
+                purchase_withdrawal(&bids[2], 500, &tokens),
+                repurchase_obligation(&bids[2], 505),
+                purchase_withdrawal(&bids[3], 2_000, &tokens),
+                excess_collateral_withdrawal(&bids[3], 970, &tokens),
+                covered_repurchase_obligation(&bids[3], 2_020, 3_030),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_partially_assign_bids_large_amounts() {
+        let mut bidder_allocations: BidderAllocations = BidderAllocations::new();
+
+        // Two equal bids near `U256::MAX / 2` whose pro-rata product would overflow 256 bits
+        let amount: U256 = U256::MAX / U256::from(2);
+        let bids: ValidatedBids = [amount, amount]
+            .into_iter()
+            .map(|amount| {
+                let mut bid: Bid = random_revealed_bid();
+                bid.bid_price_revealed = bps_to_internal(&U256::from(CLEARING_PRICE));
+                bid.amount = amount;
+                bid.collateral_amount = amount;
+                bid
+            })
+            .collect();
+
+        let summary: AssignmentSummary = bids.assign(
+            &amount,
+            &bps_to_internal(&U256::from(CLEARING_PRICE)),
+            &auction_parameters(0),
+            &mut bidder_allocations,
+        );
+
+        // Each bid gets half of the assignable amount, the remainder going to the last one
+        assert_eq!(summary.total_assigned, amount);
+        assert_eq!(summary.partially_filled_count, 2);
+    }
+
+    #[test]
+    fn test_assign_bids_no_assignment() {
+        let tokens: AuctionParameters = auction_parameters(DAY_COUNT);
+        let bids: ValidatedBids = vec![revealed_bid(500, 1_000), revealed_bid(500, 2_000)];
+        let mut bidder_allocations: BidderAllocations = BidderAllocations::new();
+
+        bids.clone().assign(
+            &U256::from(5_000),
+            &bps_to_internal(&U256::from(CLEARING_PRICE)),
+            &tokens,
+            &mut bidder_allocations,
+        );
+
+        // Every bid is below the clearing price and gets its collateral back
+        assert_exit_leaves(
+            bidder_allocations,
+            &tokens,
+            vec![
+                collateral_withdrawal(&bids[0], &tokens),
+                collateral_withdrawal(&bids[1], &tokens),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_assign_bids_at_computed_clearing_price() {
+        let bids: ValidatedBids = vec![
+            revealed_bid(500, 1_000),
+            revealed_bid(800, 3_000),
+            revealed_bid(800, 1_000),
+        ];
+        let offers: ValidatedOffers = [(300, 1_000), (600, 2_000), (900, 1_000)]
+            .into_iter()
+            .map(|(price, amount)| {
+                let mut offer: Offer = random_revealed_offer();
+                offer.offer_price_revealed = bps_to_internal(&U256::from(price));
+                offer.amount = U256::from(amount);
+                offer
+            })
+            .collect();
+        let mut bidder_allocations: BidderAllocations = BidderAllocations::new();
+
+        // Only the offer at 300 is below the 550 clearing price, so 1,000 can be assigned
+        let (clearing_price, max_assignable) = compute_clearing_price(&bids, &offers);
+        assert_eq!(clearing_price, bps_to_internal(&U256::from(550)));
+        assert_eq!(max_assignable, U256::from(1_000));
+
+        bids.clone().assign(
+            &max_assignable,
+            &clearing_price,
+            &auction_parameters(DAY_COUNT),
+            &mut bidder_allocations,
+        );
+
+        // The price group at 800 splits the 1,000 pro-rata (750 + 250), owing 0.55% of interest on top
+        let allocation: &BidderAllocation = &bidder_allocations[&bids[1].bidder];
+        assert_eq!(allocation.purchase_amount(), U256::from(750));
+        assert_eq!(allocation.collateral_amount(), U256::ZERO);
+        assert_eq!(
+            allocation.repurchase_obligation().repurchase_amount(),
+            U256::from(754)
+        );
+        assert_eq!(
+            allocation.repurchase_obligation().collateral_amount(),
+            bids[1].collateral_amount
+        );
+
+        let allocation: &BidderAllocation = &bidder_allocations[&bids[2].bidder];
+        assert_eq!(allocation.purchase_amount(), U256::from(250));
+        assert_eq!(
+            allocation.repurchase_obligation().repurchase_amount(),
+            U256::from(251)
+        );
+        assert_eq!(
+            allocation.repurchase_obligation().collateral_amount(),
+            bids[2].collateral_amount
+        );
+
+        // The bid below the clearing price only gets its collateral back
+        let allocation: &BidderAllocation = &bidder_allocations[&bids[0].bidder];
+        assert_eq!(allocation.purchase_amount(), U256::ZERO);
+        assert_eq!(allocation.collateral_amount(), bids[0].collateral_amount);
+        assert_eq!(
+            allocation.repurchase_obligation().repurchase_amount(),
+            U256::ZERO
+        );
+    }
+
+    #[test]
+    fn test_assign_bids_summary() {
+        // Bids sorted by ascending price: (price, amount)
+        let bids: ValidatedBids = [(10, 100), (20, 50), (20, 150), (30, 200)]
+            .into_iter()
+            .map(|(price, amount)| {
+                let mut bid: Bid = random_revealed_bid();
+                bid.bid_price_revealed = U256::from(price);
+                bid.amount = U256::from(amount);
+                bid
+            })
+            .collect();
+        let mut bidder_allocations: BidderAllocations = BidderAllocations::new();
+
+        let summary: AssignmentSummary<OrderOutcomes> = bids.clone().assign_with_outcomes(
+            &U256::from(300),
+            &U256::from(15),
+            &auction_parameters(rand::random::<u8>().into()),
+            &mut bidder_allocations,
+        );
+
+        // The bid at 30 is fully filled (200), the price group at 20 splits the remaining 100 pro-rata
+        // (75 + 25), and the bid at 10 is below the clearing price
+        assert_eq!(
+            summary,
+            AssignmentSummary {
+                total_assigned: U256::from(300),
+                fully_filled_count: 1,
+                partially_filled_count: 2,
+                outcomes: OrderOutcomes::from([
+                    (bids[0].key(), OrderOutcome::Refunded),
+                    (
+                        bids[1].key(),
+                        OrderOutcome::Partial {
+                            amount: U256::from(25)
+                        }
+                    ),
+                    (
+                        bids[2].key(),
+                        OrderOutcome::Partial {
+                            amount: U256::from(75)
+                        }
+                    ),
+                    (
+                        bids[3].key(),
+                        OrderOutcome::Filled {
+                            amount: U256::from(200)
+                        }
+                    ),
+                ]),
+            }
+        );
+    }
+
+    #[test]
+    fn test_assign_bids_independent_of_input_order() {
+        let tokens: AuctionParameters = auction_parameters(rand::random::<u8>().into());
+
+        // Equal price and amount bids split 200 pro-rata as 66 + 66 + 68, only their keys decide who gets the
+        // remainder. The bid priced under the clearing price is left unassigned.
+        let bids: ValidatedBids = [5, 20, 20, 20]
+            .into_iter()
+            .map(|price| {
+                let mut bid: Bid = random_revealed_bid();
+                bid.bid_price_revealed = U256::from(price);
+                bid.amount = U256::from(100);
+                bid
+            })
+            .collect();
+
+        let assign_and_get_leaves = |mut bids: ValidatedBids| -> ExitLeaves {
+            bids.sort_orders();
+            let mut bidder_allocations: BidderAllocations = BidderAllocations::new();
+            bids.assign(
+                &U256::from(200),
+                &U256::from(15),
+                &tokens,
+                &mut bidder_allocations,
+            );
+            let mut exit_leaves: ExitLeaves = ExitLeaves::new();
+            for (address, bidder_allocation) in bidder_allocations.into_iter() {
+                bidder_allocation.into_exit_leaves(address, &tokens, &mut exit_leaves);
+            }
+            exit_leaves
+        };
+
+        let expected_exit_leaves: ExitLeaves = assign_and_get_leaves(bids.clone());
+        for rotation in 1..bids.len() {
+            let mut shuffled_bids: ValidatedBids = bids.clone();
+            shuffled_bids.rotate_left(rotation);
+            assert_eq!(assign_and_get_leaves(shuffled_bids), expected_exit_leaves);
+        }
+        let reversed_bids: ValidatedBids = bids.into_iter().rev().collect();
+        assert_eq!(assign_and_get_leaves(reversed_bids), expected_exit_leaves);
+    }
+
+    #[test]
+    fn test_fully_assign_rollover_bid() {
+        let tokens: AuctionParameters = auction_parameters(DAY_COUNT);
+        let clearing_price: U256 = bps_to_internal(&U256::from(CLEARING_PRICE));
+
+        // A regular bid is lent the purchase tokens
+        let bid: Bid = revealed_bid(1_500, 1_000);
+        let mut bidder_allocations: BidderAllocations = BidderAllocations::new();
+        bid.fully_assign(&clearing_price, &tokens, &mut bidder_allocations);
+        let mut exit_leaves: ExitLeaves = ExitLeaves::new();
+        bidder_allocations
+            .remove(&bid.bidder)
+            .unwrap()
+            .into_exit_leaves(bid.bidder, &tokens, &mut exit_leaves);
+        assert_eq!(exit_leaves[0], purchase_withdrawal(&bid, 1_000, &tokens));
+
+        // A rollover bid pays them to the term repo servicer of the loan it pairs off instead
+        let mut rollover_bid: Bid = revealed_bid(1_500, 1_000);
+        rollover_bid.is_rollover = true;
+        rollover_bid.rollover_pair_off_term_repo_servicer = Address::random();
+        let mut bidder_allocations: BidderAllocations = BidderAllocations::new();
+        rollover_bid.fully_assign(&clearing_price, &tokens, &mut bidder_allocations);
+        let mut exit_leaves: ExitLeaves = ExitLeaves::new();
+        bidder_allocations
+            .remove(&rollover_bid.bidder)
+            .unwrap()
+            .into_exit_leaves(rollover_bid.bidder, &tokens, &mut exit_leaves);
+        assert!(!exit_leaves.iter().any(|exit_leaf| matches!(
+            exit_leaf,
+            ExitLeaf::TokenWithdrawal(withdrawal)
+                if withdrawal.recipient == rollover_bid.bidder && withdrawal.token == tokens.purchaseToken
+        )));
+        assert_eq!(
+            exit_leaves.last().unwrap(),
+            &ExitLeaf::TokenWithdrawal(ExitLeafTokenWithdrawal {
+                recipient: rollover_bid.rollover_pair_off_term_repo_servicer,
+                token: tokens.purchaseToken,
+                amount: U256::from(1_000),
+            })
+        );
+
+        // The bidder still owes the new loan
+        assert!(exit_leaves.iter().any(|exit_leaf| matches!(
+            exit_leaf,
+            ExitLeaf::RepurchaseObligation(obligation) if obligation.debtor == rollover_bid.bidder
+        )));
+    }
+
+    #[test]
+    fn test_assign_bids_caps_repurchase_obligation_at_collateral_value() {
+        let tokens: AuctionParameters = auction_parameters(DAY_COUNT);
+        // The bid at 2,000 only locks 1,000 of collateral, which cannot cover the 1,010 owed for all of its 1,000
+        let mut capped_bid: Bid = revealed_bid(2_000, 1_000);
+        capped_bid.collateral_amount = U256::from(1_000);
+        let bids: ValidatedBids = vec![revealed_bid(1_500, 1_000), capped_bid];
+        let mut bidder_allocations: BidderAllocations = BidderAllocations::new();
+
+        let summary: AssignmentSummary<OrderOutcomes> = bids.clone().assign_with_outcomes(
+            &U256::from(1_000),
+            &bps_to_internal(&U256::from(CLEARING_PRICE)),
+            &tokens,
+            &mut bidder_allocations,
+        );
+
+        // The capped bid is only lent the 990 its collateral covers once the 1% of interest accrues, and the 10
+        // left over go to the next bid above the clearing price
+        assert_eq!(
+            summary,
+            AssignmentSummary {
+                total_assigned: U256::from(1_000),
+                fully_filled_count: 0,
+                partially_filled_count: 2,
+                outcomes: OrderOutcomes::from([
+                    (
+                        bids[0].key(),
+                        OrderOutcome::Partial {
+                            amount: U256::from(10)
+                        }
+                    ),
+                    (
+                        bids[1].key(),
+                        OrderOutcome::Partial {
+                            amount: U256::from(990)
+                        }
+                    ),
+                ]),
+            }
+        );
+        assert_exit_leaves(
+            bidder_allocations,
+            &tokens,
+            vec![
+                purchase_withdrawal(&bids[1], 990, &tokens),
+                repurchase_obligation(&bids[1], 999),
+                purchase_withdrawal(&bids[0], 10, &tokens),
+                repurchase_obligation(&bids[0], 10),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_fully_assign_returns_excess_collateral() {
+        let tokens: AuctionParameters = auction_parameters(DAY_COUNT);
+        // Locks five times its amount, well above the 150% required
+        let mut over_collateralized_bid: Bid = revealed_bid(2_000, 1_000);
+        over_collateralized_bid.collateral_amount = U256::from(5_000);
+        // Locks exactly the 150% required for its amount, which falls short once interest accrues
+        let mut tightly_collateralized_bid: Bid = revealed_bid(1_500, 1_000);
+        tightly_collateralized_bid.collateral_amount = U256::from(1_500);
+        let bids: ValidatedBids = vec![tightly_collateralized_bid, over_collateralized_bid];
+        let mut bidder_allocations: BidderAllocations = BidderAllocations::new();
+
+        bids.clone().assign(
+            &U256::from(2_000),
+            &bps_to_internal(&U256::from(CLEARING_PRICE)),
+            &tokens,
+            &mut bidder_allocations,
+        );
+
+        // The obligation of 1,010 keeps 1,515 as collateral and the other 3,485 are returned, while the bid without
+        // excess keeps all of its collateral
+        assert_exit_leaves(
+            bidder_allocations,
+            &tokens,
+            vec![
+                purchase_withdrawal(&bids[1], 1_000, &tokens),
+                excess_collateral_withdrawal(&bids[1], 3_485, &tokens),
+                covered_repurchase_obligation(&bids[1], 1_010, 1_515),
+                purchase_withdrawal(&bids[0], 1_000, &tokens),
+                repurchase_obligation(&bids[0], 1_010),
+            ],
+        );
+    }
+
+    // HELPER FUNCTIONS
+    /// Creates a revealed bid with the given price, in basis points, and amount, locking twice the amount as
+    /// collateral.
+    fn revealed_bid(price: u64, amount: u64) -> Bid {
+        let mut bid: Bid = random_revealed_bid();
+        bid.bid_price_revealed = bps_to_internal(&U256::from(price));
+        bid.amount = U256::from(amount);
+        bid.collateral_amount = U256::from(2 * amount);
+        bid
+    }
+
+    /// Creates auction parameters with equally priced tokens and the given day count.
+    fn auction_parameters(day_count: u64) -> AuctionParameters {
+        let mut auction_parameters: AuctionParameters = random_auction_parameters();
+        auction_parameters.purchasePrice = U256::from(1);
+        auction_parameters.collateralPrice = U256::from(1);
+        auction_parameters.dayCount = U256::from(day_count);
+        auction_parameters
+    }
+
+    /// The exit leaf for a bidder receiving the purchase tokens it was assigned.
+    fn purchase_withdrawal(bid: &Bid, amount: u64, tokens: &AuctionParameters) -> ExitLeaf {
+        ExitLeaf::TokenWithdrawal(ExitLeafTokenWithdrawal {
+            recipient: bid.bidder,
+            token: tokens.purchaseToken,
+            amount: U256::from(amount),
+        })
+    }
+
+    /// The exit leaf for a bidder getting back the collateral of an unassigned bid.
+    fn collateral_withdrawal(bid: &Bid, tokens: &AuctionParameters) -> ExitLeaf {
+        ExitLeaf::TokenWithdrawal(ExitLeafTokenWithdrawal {
+            recipient: bid.bidder,
+            token: tokens.collateralToken,
+            amount: bid.collateral_amount,
+        })
+    }
+
+    /// The exit leaf for a bidder getting back the collateral of a fully assigned bid above what its obligation
+    /// requires.
+    fn excess_collateral_withdrawal(
+        bid: &Bid,
+        amount: u64,
+        tokens: &AuctionParameters,
+    ) -> ExitLeaf {
+        ExitLeaf::TokenWithdrawal(ExitLeafTokenWithdrawal {
+            recipient: bid.bidder,
+            token: tokens.collateralToken,
+            amount: U256::from(amount),
+        })
+    }
+
+    /// The exit leaf for the repurchase obligation of an assigned bid, backed by all of its collateral.
+    fn repurchase_obligation(bid: &Bid, repurchase_amount: u64) -> ExitLeaf {
+        ExitLeaf::RepurchaseObligation(ExitLeafRepurchaseObligation {
+            debtor: bid.bidder,
+            repurchaseAmount: U256::from(repurchase_amount),
+            collateralAmount: bid.collateral_amount,
+        })
+    }
+
+    /// The exit leaf for the repurchase obligation of a fully assigned bid, backed by the collateral it requires.
+    fn covered_repurchase_obligation(
+        bid: &Bid,
+        repurchase_amount: u64,
+        collateral_amount: u64,
+    ) -> ExitLeaf {
+        ExitLeaf::RepurchaseObligation(ExitLeafRepurchaseObligation {
+            debtor: bid.bidder,
+            repurchaseAmount: U256::from(repurchase_amount),
+            collateralAmount: U256::from(collateral_amount),
+        })
+    }
+
+    /// Asserts that the bidder allocations convert into exactly the expected exit leaves, in any order.
+    fn assert_exit_leaves(
+        bidder_allocations: BidderAllocations,
+        tokens: &AuctionParameters,
+        expected_exit_leaves: ExitLeaves,
+    ) {
+        let mut exit_leaves: ExitLeaves = ExitLeaves::new();
+        for (address, bidder_allocation) in bidder_allocations.into_iter() {
+            bidder_allocation.into_exit_leaves(address, tokens, &mut exit_leaves);
+        }
+
+        assert_eq!(exit_leaves.len(), expected_exit_leaves.len());
+        for expected_exit_leaf in expected_exit_leaves.iter() {
+            assert!(exit_leaves.contains(expected_exit_leaf));
+        }
     }
 }
@@ -1,25 +1,36 @@
-use alloy_primitives::U256;
+use alloy_primitives::{B256, U256};
 
 use crate::{
     allocations::{offeror_allocations::OfferorAllocations, Allocations},
+    auction_parameters::AuctionParameters,
     orders::offers::{Offer, ValidatedOffers},
+    utils::get_key,
 };
 
 use super::{
-    calculate_repurchase_price, find_last_index_for_price, AssignableOrder, AssignableOrders,
+    calculate_repurchase_price, find_last_index_for_price, mul_div, report::OrderOutcomeSink,
+    AssignableOrder, AssignableOrders, AssignmentSummary,
 };
 
 impl AssignableOrder for Offer {
     type Allocations = OfferorAllocations;
 
+    fn key(&self) -> B256 {
+        get_key(&self.offeror, &self.id)
+    }
+
     fn fully_assign(
         &self,
         clearing_price: &U256,
-        day_count: &U256,
+        auction_parameters: &AuctionParameters,
         offeror_allocations: &mut OfferorAllocations,
     ) -> U256 {
-        let repurchase_amount: U256 =
-            calculate_repurchase_price(&self.amount, clearing_price, day_count);
+        let repurchase_amount: U256 = calculate_repurchase_price(
+            &self.amount,
+            clearing_price,
+            &auction_parameters.dayCount,
+            &auction_parameters.dayCountConvention,
+        );
 
         offeror_allocations
             .get_allocation(&self.offeror)
@@ -31,12 +42,16 @@ impl AssignableOrder for Offer {
     fn partially_assign(
         &self,
         clearing_price: &U256,
-        day_count: &U256,
+        auction_parameters: &AuctionParameters,
         assigned_amount: &U256,
         offeror_allocations: &mut OfferorAllocations,
     ) -> U256 {
-        let repurchase_amount: U256 =
-            calculate_repurchase_price(assigned_amount, clearing_price, day_count);
+        let repurchase_amount: U256 = calculate_repurchase_price(
+            assigned_amount,
+            clearing_price,
+            &auction_parameters.dayCount,
+            &auction_parameters.dayCountConvention,
+        );
 
         let offeror_allocation = offeror_allocations.get_allocation(&self.offeror);
         offeror_allocation.update_repo_amount(repurchase_amount);
@@ -53,16 +68,16 @@ impl AssignableOrder for Offer {
 impl AssignableOrders for ValidatedOffers {
     type Allocations = OfferorAllocations;
 
-    fn assign(
+    fn assign_with_outcomes<O: OrderOutcomeSink + Default>(
         self,
         max_assignable: &U256,
         clearing_price: &U256,
-        day_count: &U256,
+        auction_parameters: &AuctionParameters,
         allocations: &mut OfferorAllocations,
-    ) {
+    ) -> AssignmentSummary<O> {
         // Process revealed offers
+        let mut summary: AssignmentSummary<O> = AssignmentSummary::default();
         let mut total_assigned_offers: U256 = U256::ZERO;
-        let mut inner_index: usize;
         let mut i: usize = 0;
 
         while i < self.len() {
@@ -76,70 +91,352 @@ impl AssignableOrders for ValidatedOffers {
                 && price_group_amount < (*max_assignable - total_assigned_offers)
             {
                 // FULL ASSIGNMENT
-                inner_index = 0;
-
-                while inner_index + i < k {
-                    total_assigned_offers +=
-                        self[inner_index + i].fully_assign(clearing_price, day_count, allocations);
-
-                    inner_index += 1;
+                for offer in self[i..=k].iter() {
+                    let assigned_amount: U256 =
+                        offer.fully_assign(clearing_price, auction_parameters, allocations);
+                    summary.record(offer.key(), &offer.amount, &assigned_amount);
+                    total_assigned_offers += assigned_amount;
                 }
 
-                if inner_index > 0 {
-                    i += inner_index - 1;
-                }
+                // Skip the rest of the price group
+                i = k;
             } else if self[i].offer_price_revealed <= *clearing_price
                 && total_assigned_offers < *max_assignable
             {
                 // PARTIAL ASSIGNMENT
-                let mut inner_index: usize = 0;
-                while inner_index + i < k {
-                    if inner_index + i == k {
+                // Partial assignment for the entire price group
+                for (inner_index, offer) in self[i..=k].iter().enumerate() {
+                    let assigned_amount: U256 = if i + inner_index == k {
                         // Last iteration of loop. Assign remaining amount left to assign.
-                        total_assigned_offers += self[inner_index + i].partially_assign(
-                            clearing_price,
-                            day_count,
-                            &(max_assignable - total_assigned_offers),
-                            allocations,
-                        );
-                        price_group_amount -= max_assignable - total_assigned_offers;
+                        max_assignable - total_assigned_offers
+                    } else if price_group_amount.is_zero() {
+                        // Only zero-amount offers are left in the price group, there is nothing to split among them.
+                        U256::ZERO
                     } else {
                         // Assign an amount based upon the partial assignment ratio.
-                        let assigned_amount: U256 = if inner_index + i != k {
-                            (self[inner_index + i].amount
-                                * (max_assignable - total_assigned_offers))
-                                / price_group_amount
-                        } else {
-                            max_assignable - total_assigned_offers
-                        };
-
-                        total_assigned_offers += self[inner_index + i].partially_assign(
-                            clearing_price,
-                            day_count,
-                            &assigned_amount,
-                            allocations,
-                        );
-                        price_group_amount -= self[inner_index + i].amount;
-                    }
-                    inner_index += 1;
-                }
-                if inner_index > 0 {
-                    i += inner_index - 1;
+                        mul_div(
+                            &offer.amount,
+                            &(max_assignable - total_assigned_offers),
+                            &price_group_amount,
+                        )
+                    };
+
+                    total_assigned_offers += offer.partially_assign(
+                        clearing_price,
+                        auction_parameters,
+                        &assigned_amount,
+                        allocations,
+                    );
+                    summary.record(offer.key(), &offer.amount, &assigned_amount);
+                    price_group_amount -= offer.amount;
                 }
+
+                // Skip the rest of the price group
+                i = k;
             } else {
                 // NO ASSIGNMENT
                 self[i].unlock(allocations);
+                summary.record(self[i].key(), &self[i].amount, &U256::ZERO);
             }
 
             i += 1;
         }
+
+        summary
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use crate::{
+        allocations::offeror_allocations::OfferorAllocation,
+        allocations::Allocation,
+        auction::{
+            compute_clearing_price,
+            report::{OrderOutcome, OrderOutcomes},
+        },
+        auction_parameters::{tests::random_auction_parameters, AuctionParameters},
+        exit_tree::{ExitLeaf, ExitLeafRepoTokenWithdrawal, ExitLeafTokenWithdrawal, ExitLeaves},
+        orders::{
+            bids::{tests::random_revealed_bid, Bid, ValidatedBids},
+            offers::tests::random_revealed_offer,
+        },
+        utils::bps_to_internal,
+    };
+
+    // A 10% clearing price over 36 days accrues 1% of interest
+    const CLEARING_PRICE: u64 = 1_000;
+    const DAY_COUNT: u64 = 36;
+
     #[test]
     fn test_assign_offers() {
-        unimplemented!()
+        let tokens: AuctionParameters = auction_parameters(DAY_COUNT);
+        let offers: ValidatedOffers = vec![
+            revealed_offer(500, 2_000),
+            revealed_offer(800, 3_000),
+            revealed_offer(800, 1_000),
+            revealed_offer(1_500, 1_000),
+        ];
+        let mut offeror_allocations: OfferorAllocations = OfferorAllocations::new();
+
+        offers.clone().assign(
+            &U256::from(7_000),
+            &bps_to_internal(&U256::from(CLEARING_PRICE)),
+            &tokens,
+            &mut offeror_allocations,
+        );
+
+        // Every offer below the clearing price is fully assigned, the one above gets its purchase tokens back
+        assert_exit_leaves(
+            offeror_allocations,
+            &tokens,
+            vec![
+                repo_token_withdrawal(&offers[0], 2_020),
+                repo_token_withdrawal(&offers[1], 3_030),
+                repo_token_withdrawal(&offers[2], 1_010),
+                purchase_withdrawal(&offers[3], 1_000, &tokens),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_partially_assign_offers() {
+        let tokens: AuctionParameters = auction_parameters(DAY_COUNT);
+        let offers: ValidatedOffers = vec![
+            revealed_offer(500, 2_000),
+            revealed_offer(800, 3_000),
+            revealed_offer(800, 1_000),
+            revealed_offer(1_500, 1_000),
+        ];
+        let mut offeror_allocations: OfferorAllocations = OfferorAllocations::new();
+
+        offers.clone().assign(
+            &U256::from(4_000),
+            &bps_to_internal(&U256::from(CLEARING_PRICE)),
+            &tokens,
+            &mut offeror_allocations,
+        );
+
+        // The most competitive offer is fully assigned, the price group at the edge splits the remaining 2,000
+        // pro-rata (1,500 + 500) and gets the rest back, and the offer above the clearing price gets its purchase
+        // tokens back
+        assert_exit_leaves(
+            offeror_allocations,
+            &tokens,
+            vec![
+                repo_token_withdrawal(&offers[0], 2_020),
+                repo_token_withdrawal(&offers[1], 1_515),
+                purchase_withdrawal(&offers[1], 1_500, &tokens),
+                repo_token_withdrawal(&offers[2], 505),
+                purchase_withdrawal(&offers[2], 500, &tokens),
+                purchase_withdrawal(&offers[3], 1_000, &tokens),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_partially_assign_offers_absorbs_rounding_remainder() {
+        let offers: ValidatedOffers = vec![
+            revealed_offer(500, 1_000),
+            revealed_offer(500, 1_000),
+            revealed_offer(500, 1_000),
+        ];
+        let mut offeror_allocations: OfferorAllocations = OfferorAllocations::new();
+
+        // Splitting 1,000 three ways rounds each share down to 333, which naively would only assign 999
+        let summary: AssignmentSummary = offers.clone().assign(
+            &U256::from(1_000),
+            &bps_to_internal(&U256::from(CLEARING_PRICE)),
+            &auction_parameters(0),
+            &mut offeror_allocations,
+        );
+
+        // The last offer of the price group absorbs the dust
+        assert_eq!(summary.total_assigned, U256::from(1_000));
+        for (offer, assigned_amount) in offers.iter().zip([333, 333, 334]) {
+            let allocation: &OfferorAllocation = &offeror_allocations[&offer.offeror];
+            assert_eq!(allocation.repo_amount(), U256::from(assigned_amount));
+            assert_eq!(
+                allocation.purchase_amount(),
+                U256::from(1_000 - assigned_amount)
+            );
+        }
+    }
+
+    #[test]
+    fn test_assign_offers_at_computed_clearing_price() {
+        let bids: ValidatedBids = [(500, 1_000), (900, 3_000)]
+            .into_iter()
+            .map(|(price, amount)| {
+                let mut bid: Bid = random_revealed_bid();
+                bid.bid_price_revealed = bps_to_internal(&U256::from(price));
+                bid.amount = U256::from(amount);
+                bid
+            })
+            .collect();
+        let offers: ValidatedOffers = vec![
+            revealed_offer(300, 1_000),
+            revealed_offer(600, 2_000),
+            revealed_offer(600, 1_000),
+            revealed_offer(900, 1_000),
+        ];
+        let mut offeror_allocations: OfferorAllocations = OfferorAllocations::new();
+
+        // Only the bid at 900 is above the 600 clearing price, so 3,000 can be assigned
+        let (clearing_price, max_assignable) = compute_clearing_price(&bids, &offers);
+        assert_eq!(clearing_price, bps_to_internal(&U256::from(600)));
+        assert_eq!(max_assignable, U256::from(3_000));
+
+        offers.clone().assign(
+            &max_assignable,
+            &clearing_price,
+            &auction_parameters(DAY_COUNT),
+            &mut offeror_allocations,
+        );
+
+        // The offer at 300 is fully assigned, earning 0.6% of interest
+        let allocation: &OfferorAllocation = &offeror_allocations[&offers[0].offeror];
+        assert_eq!(allocation.repo_amount(), U256::from(1_006));
+        assert_eq!(allocation.purchase_amount(), U256::ZERO);
+
+        // The price group at 600 splits the remaining 2,000 pro-rata (1,333 + 667) and gets the rest back
+        let allocation: &OfferorAllocation = &offeror_allocations[&offers[1].offeror];
+        assert_eq!(allocation.repo_amount(), U256::from(1_340));
+        assert_eq!(allocation.purchase_amount(), U256::from(667));
+
+        let allocation: &OfferorAllocation = &offeror_allocations[&offers[2].offeror];
+        assert_eq!(allocation.repo_amount(), U256::from(671));
+        assert_eq!(allocation.purchase_amount(), U256::from(333));
+
+        // The offer above the clearing price gets all of its purchase tokens back
+        let allocation: &OfferorAllocation = &offeror_allocations[&offers[3].offeror];
+        assert_eq!(allocation.repo_amount(), U256::ZERO);
+        assert_eq!(allocation.purchase_amount(), U256::from(1_000));
+    }
+
+    #[test]
+    fn test_assign_offers_no_assignment() {
+        let tokens: AuctionParameters = auction_parameters(DAY_COUNT);
+        let offers: ValidatedOffers =
+            vec![revealed_offer(1_500, 1_000), revealed_offer(1_500, 2_000)];
+        let mut offeror_allocations: OfferorAllocations = OfferorAllocations::new();
+
+        offers.clone().assign(
+            &U256::from(5_000),
+            &bps_to_internal(&U256::from(CLEARING_PRICE)),
+            &tokens,
+            &mut offeror_allocations,
+        );
+
+        // Every offer is above the clearing price and gets its purchase tokens back
+        assert_exit_leaves(
+            offeror_allocations,
+            &tokens,
+            vec![
+                purchase_withdrawal(&offers[0], 1_000, &tokens),
+                purchase_withdrawal(&offers[1], 2_000, &tokens),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_assign_offers_zero_amount_price_group() {
+        let offers: ValidatedOffers = vec![
+            revealed_offer(500, 0),
+            revealed_offer(500, 0),
+            revealed_offer(800, 1_000),
+            revealed_offer(800, 0),
+            revealed_offer(800, 0),
+        ];
+        let mut offeror_allocations: OfferorAllocations = OfferorAllocations::new();
+
+        // Once the offer at 800 takes the whole 500 left, the rest of its price group adds up to zero
+        let summary: AssignmentSummary<OrderOutcomes> = offers.clone().assign_with_outcomes(
+            &U256::from(500),
+            &bps_to_internal(&U256::from(CLEARING_PRICE)),
+            &auction_parameters(0),
+            &mut offeror_allocations,
+        );
+
+        assert_eq!(
+            summary,
+            AssignmentSummary {
+                total_assigned: U256::from(500),
+                fully_filled_count: 0,
+                partially_filled_count: 1,
+                // Every offer is refunded but the one at 800
+                outcomes: offers
+                    .iter()
+                    .map(|offer| (offer.key(), OrderOutcome::Refunded))
+                    .chain([(
+                        offers[2].key(),
+                        OrderOutcome::Partial {
+                            amount: U256::from(500)
+                        }
+                    )])
+                    .collect(),
+            }
+        );
+        let allocation: &OfferorAllocation = &offeror_allocations[&offers[2].offeror];
+        assert_eq!(allocation.repo_amount(), U256::from(500));
+        assert_eq!(allocation.purchase_amount(), U256::from(500));
+
+        // Zero-amount offers are assigned nothing, and have nothing to get back
+        for index in [0, 1, 3, 4] {
+            let allocation: &OfferorAllocation = &offeror_allocations[&offers[index].offeror];
+            assert_eq!(allocation.repo_amount(), U256::ZERO);
+            assert_eq!(allocation.purchase_amount(), U256::ZERO);
+        }
+    }
+
+    // HELPER FUNCTIONS
+    /// Creates a revealed offer with the given price, in basis points, and amount.
+    fn revealed_offer(price: u64, amount: u64) -> Offer {
+        let mut offer: Offer = random_revealed_offer();
+        offer.offer_price_revealed = bps_to_internal(&U256::from(price));
+        offer.amount = U256::from(amount);
+        offer
+    }
+
+    /// The exit leaf for an offeror receiving the repo tokens for the amount it was assigned.
+    fn repo_token_withdrawal(offer: &Offer, amount: u64) -> ExitLeaf {
+        ExitLeaf::RepoTokenWithdrawal(ExitLeafRepoTokenWithdrawal {
+            recipient: offer.offeror,
+            amount: U256::from(amount),
+        })
+    }
+
+    /// The exit leaf for an offeror getting back the purchase tokens that were not assigned.
+    fn purchase_withdrawal(offer: &Offer, amount: u64, tokens: &AuctionParameters) -> ExitLeaf {
+        ExitLeaf::TokenWithdrawal(ExitLeafTokenWithdrawal {
+            recipient: offer.offeror,
+            token: tokens.purchaseToken,
+            amount: U256::from(amount),
+        })
+    }
+
+    /// Asserts that the offeror allocations convert into exactly the expected exit leaves, in any order.
+    fn assert_exit_leaves(
+        offeror_allocations: OfferorAllocations,
+        tokens: &AuctionParameters,
+        expected_exit_leaves: ExitLeaves,
+    ) {
+        let mut exit_leaves: ExitLeaves = ExitLeaves::new();
+        for (address, offeror_allocation) in offeror_allocations.into_iter() {
+            offeror_allocation.into_exit_leaves(address, tokens, &mut exit_leaves);
+        }
+
+        assert_eq!(exit_leaves.len(), expected_exit_leaves.len());
+        for expected_exit_leaf in expected_exit_leaves.iter() {
+            assert!(exit_leaves.contains(expected_exit_leaf));
+        }
+    }
+
+    /// Creates auction parameters with the given day count.
+    fn auction_parameters(day_count: u64) -> AuctionParameters {
+        let mut auction_parameters: AuctionParameters = random_auction_parameters();
+        auction_parameters.dayCount = U256::from(day_count);
+        auction_parameters
     }
 }
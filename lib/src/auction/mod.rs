@@ -1,13 +1,279 @@
 pub mod assign_bids;
 pub mod assign_offers;
+pub mod report;
 
-use alloy_primitives::U256;
+use alloy_primitives::{Address, B256, U256, U512};
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    constants::{BPS, DAYS_IN_YEAR},
-    orders::{bids::ValidatedBids, offers::ValidatedOffers},
+    allocations::AuctionResults,
+    auction_parameters::{AuctionMode, AuctionParameters, DayCountConvention},
+    constants::BPS,
+    orders::{
+        bids::{Bids, ValidatedBids},
+        offers::{Offers, ValidatedOffers},
+        PlacedOrders, ValidatedOrders,
+    },
+    utils::bps_to_internal,
+    AuctionError,
 };
 
+use report::{OrderOutcome, OrderOutcomeSink};
+
+/// Whether the market cleared, as decided when running the auction.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ClearingOutcome {
+    /// The market intersected, and orders were assigned at the clearing price.
+    Cleared {
+        /// The clearing price, as a percentage with 9 decimal places.
+        price: U256,
+        /// The total amount that was lent.
+        volume: U256,
+    },
+    /// The market did not intersect, so every validated order was unlocked back to its owner.
+    NoClear,
+}
+
+impl ClearingOutcome {
+    /// Returns the clearing price, as a percentage with 9 decimal places, or zero if the market did not clear.
+    pub fn price(&self) -> U256 {
+        match self {
+            ClearingOutcome::Cleared { price, .. } => *price,
+            ClearingOutcome::NoClear => U256::ZERO,
+        }
+    }
+
+    /// Returns the total amount that was lent, or zero if the market did not clear.
+    pub fn volume(&self) -> U256 {
+        match self {
+            ClearingOutcome::Cleared { volume, .. } => *volume,
+            ClearingOutcome::NoClear => U256::ZERO,
+        }
+    }
+}
+
+/// Runs the auction over the placed orders, returning the resulting allocations and whether the market cleared.
+///
+/// Invalid orders are refunded, valid ones are sorted and, if the market intersects, assigned at the clearing price.
+/// Unlike `run_auction`, nothing is hashed, so this can be used offchain to inspect the allocation of every address.
+///
+/// # Arguments
+///
+/// * `prover_address` - The address of the prover, credited with the accrued fees.
+/// * `bids` - The placed bids, updated with their reveals.
+/// * `offers` - The placed offers, updated with their reveals.
+/// * `auction_parameters` - The token information for the assets involved in the auction.
+///
+/// # Returns
+///
+/// * `Result<(AuctionResults, ClearingOutcome), AuctionError>` - The allocations of the prover, bidders and offerors
+///   with the `ClearingOutcome`, or `AuctionError::ArithmeticOverflow` if the amounts being cleared do not fit in 256
+///   bits.
+pub fn run(
+    prover_address: &Address,
+    bids: Bids,
+    offers: Offers,
+    auction_parameters: &AuctionParameters,
+) -> Result<(AuctionResults, ClearingOutcome), AuctionError> {
+    // Define the auction results
+    let mut auction_results: AuctionResults = AuctionResults::new(prover_address);
+
+    // Get validated bids and offers
+    let mut validated_bids: ValidatedBids =
+        bids.into_validated_orders(auction_parameters, &mut auction_results.bidder_allocations);
+    let mut validated_offers: ValidatedOffers =
+        offers.into_validated_orders(auction_parameters, &mut auction_results.offeror_allocations);
+
+    // Without bids or without offers the market cannot clear, as happens when no orders were revealed, so the
+    // validated orders are unlocked without sorting them first
+    if validated_bids.is_empty() || validated_offers.is_empty() {
+        validated_bids.unlock_outstanding_orders(&mut auction_results.bidder_allocations);
+        validated_offers.unlock_outstanding_orders(&mut auction_results.offeror_allocations);
+
+        return Ok((auction_results, ClearingOutcome::NoClear));
+    }
+
+    // Sort validated bids by *ascending* price. Orders right on the price edge will be partially filled.
+    validated_bids.sort_orders();
+    // Sort validated offers by *ascending* price. Orders right on the price edge will be partially filled.
+    validated_offers.sort_orders();
+
+    let (clearing_outcome, (), ()) = assign_sorted_orders(
+        &mut auction_results,
+        validated_bids,
+        validated_offers,
+        auction_parameters,
+    )?;
+
+    Ok((auction_results, clearing_outcome))
+}
+
+/// Clears the market and assigns the sorted validated orders, or unlocks them all if the market does not intersect.
+///
+/// The outcome of every assigned order is pushed into a sink of type `O`, so that `()` discards them when only the
+/// allocations are needed.
+///
+/// # Arguments
+///
+/// * `auction_results` - The auction results the orders are allocated to.
+/// * `validated_bids` - The validated bids, sorted by ascending price.
+/// * `validated_offers` - The validated offers, sorted by ascending price.
+/// * `auction_parameters` - The token information for the assets involved in the auction.
+///
+/// # Returns
+///
+/// * `Result<(ClearingOutcome, O, O), AuctionError>` - Whether the market cleared, with the outcomes of the bids and
+///   offers assigned, or `AuctionError::ArithmeticOverflow` if the amounts being cleared do not fit in 256 bits, in
+///   which case nothing is assigned. No outcome is pushed if the market does not intersect.
+fn assign_sorted_orders<O: OrderOutcomeSink + Default>(
+    auction_results: &mut AuctionResults,
+    validated_bids: ValidatedBids,
+    validated_offers: ValidatedOffers,
+    auction_parameters: &AuctionParameters,
+) -> Result<(ClearingOutcome, O, O), AuctionError> {
+    // Calculate a clearing price and assign bids and offers only if both bids and offers exist and market intersects
+    let market_intersects: bool = match (validated_bids.last(), validated_offers.first()) {
+        (Some(highest_bid), Some(lowest_offer)) => {
+            highest_bid.bid_price_revealed >= lowest_offer.offer_price_revealed
+        }
+        // A market without bids or without offers has nothing to clear
+        _ => false,
+    };
+
+    if market_intersects {
+        // Cumulative amounts wrap around silently on overflow, so either side must add up within 256 bits
+        let bid_amounts = validated_bids.iter().map(|bid| bid.amount);
+        let offer_amounts = validated_offers.iter().map(|offer| offer.amount);
+        if checked_sum(bid_amounts).is_none() || checked_sum(offer_amounts).is_none() {
+            return Err(AuctionError::ArithmeticOverflow);
+        }
+
+        let (clearing_price, max_assignable) = match auction_parameters.auctionMode {
+            AuctionMode::SecondPriceMidpoint => {
+                compute_clearing_price(&validated_bids, &validated_offers)
+            }
+            AuctionMode::VolumeWeighted => {
+                compute_vwap_clearing_price(&validated_bids, &validated_offers)
+            }
+            AuctionMode::__Invalid => {
+                unreachable!("auction mode is checked by AuctionParameters::validate")
+            }
+        };
+
+        // No order is assigned more than `max_assignable`, so every repurchase price fits if this one does
+        if checked_repurchase_price(
+            &max_assignable,
+            &clearing_price,
+            &auction_parameters.dayCount,
+            &auction_parameters.dayCountConvention,
+        )
+        .is_none()
+        {
+            return Err(AuctionError::ArithmeticOverflow);
+        }
+
+        // Assign bids and offers. Bids capped at their collateral value may take less than `max_assignable`, in
+        // which case offers are only assigned what bids took, leaving the rest to the offerors.
+        let bids_summary: AssignmentSummary<O> = validated_bids.assign_with_outcomes(
+            &max_assignable,
+            &clearing_price,
+            auction_parameters,
+            &mut auction_results.bidder_allocations,
+        );
+        let offers_summary: AssignmentSummary<O> = validated_offers.assign_with_outcomes(
+            &bids_summary.total_assigned,
+            &clearing_price,
+            auction_parameters,
+            &mut auction_results.offeror_allocations,
+        );
+
+        Ok((
+            ClearingOutcome::Cleared {
+                price: clearing_price,
+                volume: bids_summary.total_assigned,
+            },
+            bids_summary.outcomes,
+            offers_summary.outcomes,
+        ))
+    } else {
+        // Dump all validated bids and offers to their corresponding allocations
+        validated_bids.unlock_outstanding_orders(&mut auction_results.bidder_allocations);
+        validated_offers.unlock_outstanding_orders(&mut auction_results.offeror_allocations);
+
+        Ok((ClearingOutcome::NoClear, O::default(), O::default()))
+    }
+}
+
+/// Adds up the given amounts, returning `None` if the sum overflows.
+///
+/// # Arguments
+///
+/// * `amounts` - The amounts being added up.
+fn checked_sum(mut amounts: impl Iterator<Item = U256>) -> Option<U256> {
+    amounts.try_fold(U256::ZERO, |acc: U256, amount: U256| {
+        acc.checked_add(amount)
+    })
+}
+
+/// Computes the clearing rate as the average price of the orders that cross at the `compute_clearing_price` rate,
+/// weighted by their amounts, alongside the amount that can be assigned at that rate.
+///
+/// Crossing bids are priced at or above the midpoint and crossing offers at or below it, so the weighted price always
+/// lies between the lowest offer and the highest bid. Moving the price away from the midpoint may leave some of the
+/// orders that crossed out, so the assignable amount is recomputed at the new price.
+///
+/// # Arguments
+///
+/// * `bids` - The validated bids, sorted by ascending price.
+/// * `offers` - The validated offers, sorted by ascending price.
+///
+/// # Returns
+///
+/// * `clearing_price` - The clearing price as a U256.
+/// * `max_assignable` - The maximum assignable amount as a U256.
+pub fn compute_vwap_clearing_price(bids: &ValidatedBids, offers: &ValidatedOffers) -> (U256, U256) {
+    let (midpoint_price, _) = compute_clearing_price(bids, offers);
+
+    // Computed over 512 bits, as prices times amounts may not fit in 256
+    let crossing_bids = bids
+        .iter()
+        .filter(|bid| bid.bid_price_revealed >= midpoint_price)
+        .map(|bid| (bid.bid_price_revealed, bid.amount));
+    let crossing_offers = offers
+        .iter()
+        .filter(|offer| offer.offer_price_revealed <= midpoint_price)
+        .map(|offer| (offer.offer_price_revealed, offer.amount));
+    let (weighted_prices, total_amount): (U512, U512) = crossing_bids.chain(crossing_offers).fold(
+        (U512::ZERO, U512::ZERO),
+        |(weighted_prices, total_amount), (price, amount)| {
+            (
+                weighted_prices + price.widening_mul(amount),
+                total_amount + U512::from(amount),
+            )
+        },
+    );
+    if total_amount.is_zero() {
+        return (midpoint_price, U256::ZERO);
+    }
+    let clearing_price: U256 = U256::saturating_from(weighted_prices / total_amount);
+
+    // Only bids at or above the price borrow, and only offers at or below it lend
+    let bid_amounts = bids
+        .iter()
+        .filter(|bid| bid.bid_price_revealed >= clearing_price)
+        .map(|bid| bid.amount);
+    let offer_amounts = offers
+        .iter()
+        .filter(|offer| offer.offer_price_revealed <= clearing_price)
+        .map(|offer| offer.amount);
+    let max_assignable: U256 = U256::min(
+        bid_amounts.fold(U256::ZERO, U256::saturating_add),
+        offer_amounts.fold(U256::ZERO, U256::saturating_add),
+    );
+
+    (clearing_price, max_assignable)
+}
+
 /// Computes the clearing rate as the average of the second most competitive bid and the second most competitive offer.
 ///
 /// This implementation is just a rough Rust translation of the [original Solidity implementation](https://github.com/term-finance/term-finance-contracts/blob/262098c71578bbb9e54d6c2a8d2d88d112b9662a/contracts/TermAuction.sol#L512),
@@ -23,9 +289,9 @@ use crate::{
 /// * `clearing_price` - The clearing price as a U256.
 /// * `max_assignable` - The maximum assignable amount as a U256.
 pub fn compute_clearing_price(bids: &ValidatedBids, offers: &ValidatedOffers) -> (U256, U256) {
-    let offer_price: U256 = offers.last().unwrap().offer_price_revealed; // p^o_i
-    let mut offer_index: usize = 1; // idxo(offerPrice)
-    let mut cum_sum_offers: U256 = offers.last().unwrap().amount; // cso(offerPrice)
+    let offer_price: U256 = offers[0].offer_price_revealed; // p^o_i
+    let mut offer_index: usize = 0; // idxo(offerPrice)
+    let mut cum_sum_offers: U256 = U256::ZERO; // cso(offerPrice)
     let mut bid_index: usize = bids.len();
     let mut cum_sum_bids: U256 = U256::ZERO;
     let mut next_offer_index: usize;
@@ -37,8 +303,14 @@ pub fn compute_clearing_price(bids: &ValidatedBids, offers: &ValidatedOffers) ->
     /* let mut min_cum_sum_correction: bool = false; // Seemingly useless, see comment below*/
     let mut next_bid_price: U256;
 
+    // Start from the cheapest offers, sweeping their whole price group
+    while offer_index < offers.len() && offers[offer_index].offer_price_revealed == offer_price {
+        cum_sum_offers += offers[offer_index].amount;
+        offer_index += 1;
+    }
+
     (cum_sum_bids, bid_index) =
-        increase_cum_sum_bids(bids, &(bid_index - 1), &cum_sum_bids, &offer_price);
+        increase_cum_sum_bids(bids, &bid_index, &cum_sum_bids, &offer_price);
 
     // Calculate initial maximal clearing volume
     let mut max_clearing_volume: U256 = U256::min(cum_sum_bids, cum_sum_offers);
@@ -186,42 +458,42 @@ pub fn compute_clearing_price(bids: &ValidatedBids, offers: &ValidatedOffers) ->
     // Update cum_sum_bids
     if bid_index < bids.len() && bids[bid_index].bid_price_revealed < clearing_price {
         (cum_sum_bids, _) = decrease_cum_sum_bids(bids, &bid_index, &cum_sum_bids, &clearing_price);
-    } else if bid_index > 0 {
-        (cum_sum_bids, _) =
-            increase_cum_sum_bids(bids, &(bid_index - 1), &cum_sum_bids, &clearing_price);
+    } else {
+        (cum_sum_bids, _) = increase_cum_sum_bids(bids, &bid_index, &cum_sum_bids, &clearing_price);
     }
 
     (clearing_price, U256::min(cum_sum_bids, cum_sum_offers))
 }
 
 /// Increases the cumulative sum of bids at a given price.
+///
+/// Walks down from `end_index` (exclusive) adding every bid priced at or above `current_price`, and returns the
+/// updated cumulative sum alongside the index of the first bid that was added, or `end_index` if none were.
 fn increase_cum_sum_bids(
     bids: &ValidatedBids,
-    start_index: &usize,
+    end_index: &usize,
     prev_cum_sum_bids: &U256,
     current_price: &U256,
 ) -> (U256, usize) {
     let mut cum_sum_bids: U256 = *prev_cum_sum_bids;
-    let mut i: usize = *start_index;
+    let mut i: usize = *end_index;
 
-    while bids[i].bid_price_revealed >= *current_price {
-        cum_sum_bids += bids[i].amount;
+    while let Some(bid) = i
+        .checked_sub(1)
+        .and_then(|index| bids.get(index))
+        .filter(|bid| bid.bid_price_revealed >= *current_price)
+    {
+        cum_sum_bids += bid.amount;
         i -= 1;
-        if i == 0 {
-            break;
-        }
     }
 
-    let final_index: usize = if bids[i].bid_price_revealed < *current_price {
-        i + 1
-    } else {
-        i
-    };
-
-    (cum_sum_bids, final_index)
+    (cum_sum_bids, i)
 }
 
 /// Decreases the cumulative sum of bids at a given price.
+///
+/// Walks up from `start_index` (inclusive) removing every bid priced below `current_price`, and returns the
+/// updated cumulative sum alongside the index of the first bid that was kept, or `bids.len()` if none were.
 fn decrease_cum_sum_bids(
     bids: &ValidatedBids,
     start_index: &usize,
@@ -231,8 +503,11 @@ fn decrease_cum_sum_bids(
     let mut cum_sum_bids: U256 = *prev_cum_sum_bids;
     let mut i: usize = *start_index;
 
-    while i < bids.len() && bids[i].bid_price_revealed < *current_price {
-        cum_sum_bids -= bids[i].amount;
+    while let Some(bid) = bids
+        .get(i)
+        .filter(|bid| bid.bid_price_revealed < *current_price)
+    {
+        cum_sum_bids -= bid.amount;
         i += 1;
     }
 
@@ -243,13 +518,16 @@ fn decrease_cum_sum_bids(
 pub trait AssignableOrder {
     type Allocations;
 
+    /// Returns the key of the order, as computed by `get_key` from its owner and ID.
+    fn key(&self) -> B256;
+
     /// Fully assigns an order.
     ///
     /// # Arguments
     ///
     /// * `self` - The order to fully assign.
     /// * `clearing_price` - The clearing rate at which to assign the order.
-    /// * `day_count` - The day count.
+    /// * `auction_parameters` - The auction parameters, giving the day count and the token prices.
     /// * `allocations` - The allocations.
     ///
     /// # Returns
@@ -258,7 +536,7 @@ pub trait AssignableOrder {
     fn fully_assign(
         &self,
         clearing_price: &U256,
-        day_count: &U256,
+        auction_parameters: &AuctionParameters,
         allocations: &mut Self::Allocations,
     ) -> U256;
 
@@ -268,7 +546,7 @@ pub trait AssignableOrder {
     ///
     /// * `self` - The order to partially assign.
     /// * `clearing_price` - The clearing rate at which to assign the order.
-    /// * `day_count` - The day count.
+    /// * `auction_parameters` - The auction parameters, giving the day count and the token prices.
     /// * `assigned_amount` - The amount to partially assign.
     /// * `allocations` - The allocations.
     ///
@@ -278,7 +556,7 @@ pub trait AssignableOrder {
     fn partially_assign(
         &self,
         clearing_price: &U256,
-        day_count: &U256,
+        auction_parameters: &AuctionParameters,
         assigned_amount: &U256,
         allocations: &mut Self::Allocations,
     ) -> U256;
@@ -292,27 +570,113 @@ pub trait AssignableOrder {
     fn unlock(&self, allocations: &mut Self::Allocations);
 }
 
+/// Summary of how much of the market cleared when assigning bids or offers.
+#[derive(Default, PartialEq, Eq, Debug)]
+pub struct AssignmentSummary<O: OrderOutcomeSink = ()> {
+    /// The total amount that was assigned.
+    pub total_assigned: U256,
+    /// The number of orders whose whole amount was assigned.
+    pub fully_filled_count: usize,
+    /// The number of orders that were assigned a nonzero amount below their whole amount.
+    pub partially_filled_count: usize,
+    /// The sink the outcome of every order was pushed into, `()` unless collected into `OrderOutcomes`.
+    pub outcomes: O,
+}
+
+impl<O: OrderOutcomeSink> AssignmentSummary<O> {
+    /// Records the assignment of an order.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The summary being updated.
+    /// * `key` - The key of the order.
+    /// * `order_amount` - The whole amount of the order.
+    /// * `assigned_amount` - The amount of the order that was assigned.
+    fn record(&mut self, key: B256, order_amount: &U256, assigned_amount: &U256) {
+        self.total_assigned += *assigned_amount;
+        self.outcomes.push(
+            key,
+            OrderOutcome::from_assignment(order_amount, *assigned_amount),
+        );
+        // Zero-amount orders are never counted as filled
+        if assigned_amount.is_zero() {
+            return;
+        }
+        if assigned_amount == order_amount {
+            self.fully_filled_count += 1;
+        } else {
+            self.partially_filled_count += 1;
+        }
+    }
+}
+
 /// Trait for assigning bids and offers to auction results.
-pub trait AssignableOrders {
+pub trait AssignableOrders: Sized {
     type Allocations;
 
-    /// Assigns bids or offers up to a maximum assignable amount at a clearing rate.
+    /// Assigns bids or offers like `assign_with_outcomes`, discarding the outcome of every order.
     ///
     /// # Arguments
     ///
     /// * `self` - The bids or offers to assign.
     /// * `max_assignable` - The maximum amount that can be assigned.
     /// * `clearing_price` - The clearing rate at which to assign the orders.
+    /// * `auction_parameters` - The auction parameters, giving the day count and the token prices.
+    /// * `allocations` - The allocations to record the assignments in.
+    ///
+    /// # Returns
+    ///
+    /// * `AssignmentSummary` - A summary of the assigned volume and filled orders.
     fn assign(
         self,
         max_assignable: &U256,
         clearing_price: &U256,
-        day_count: &U256,
+        auction_parameters: &AuctionParameters,
         allocations: &mut Self::Allocations,
-    );
+    ) -> AssignmentSummary {
+        self.assign_with_outcomes(
+            max_assignable,
+            clearing_price,
+            auction_parameters,
+            allocations,
+        )
+    }
+
+    /// Assigns bids or offers up to a maximum assignable amount at a clearing rate.
+    ///
+    /// The price group at the edge of the clearing price is split pro-rata, rounding down. Each order takes its share
+    /// of what is still left to assign, and the last order assigned in the group takes all that remains, so the
+    /// rounding dust is absorbed there and exactly `max_assignable` is assigned whenever enough volume clears.
+    ///
+    /// Bids are also capped so that their repurchase obligation does not exceed the value of their collateral. The
+    /// volume a capped bid leaves is offered to the following price groups, so bids may assign less than
+    /// `max_assignable`.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The bids or offers to assign.
+    /// * `max_assignable` - The maximum amount that can be assigned.
+    /// * `clearing_price` - The clearing rate at which to assign the orders.
+    /// * `auction_parameters` - The auction parameters, giving the day count and the token prices.
+    /// * `allocations` - The allocations to record the assignments in.
+    ///
+    /// # Returns
+    ///
+    /// * `AssignmentSummary<O>` - A summary of the assigned volume and filled orders, along with the outcome of every
+    ///   order pushed into a sink of type `O`.
+    fn assign_with_outcomes<O: OrderOutcomeSink + Default>(
+        self,
+        max_assignable: &U256,
+        clearing_price: &U256,
+        auction_parameters: &AuctionParameters,
+        allocations: &mut Self::Allocations,
+    ) -> AssignmentSummary<O>;
 }
 
 /// Finds the index of the first bid with a bidPrice of `price` and calculates the cumulative sum of the bid amounts up to that index.
+///
+/// The scan stops at the start of the bids, so a price group starting at index 0 is handled like any other. The
+/// bid at `start_index` must exist.
 pub fn find_first_index_for_price(
     price: &U256,
     bids: &ValidatedBids,
@@ -321,12 +685,13 @@ pub fn find_first_index_for_price(
     let mut i: usize = *start_index;
     let mut total_amount: U256 = bids[i].amount;
 
-    loop {
-        if i == 0 || bids[i - 1].bid_price_revealed != *price {
+    // `checked_sub` ends the scan at the first bid instead of underflowing
+    while let Some(previous_bid) = i.checked_sub(1).and_then(|index| bids.get(index)) {
+        if previous_bid.bid_price_revealed != *price {
             break;
         }
 
-        total_amount += bids[i - 1].amount;
+        total_amount = total_amount.saturating_add(previous_bid.amount);
         i -= 1;
     }
 
@@ -334,6 +699,9 @@ pub fn find_first_index_for_price(
 }
 
 /// Finds the index of the last offer with a offerPrice of `price` and calculates the cumulative sum of the offer amounts up to that index.
+///
+/// The scan stops at the end of the offers, so a price group ending at the last index is handled like any other. The
+/// offer at `start_index` must exist.
 pub fn find_last_index_for_price(
     price: &U256,
     offers: &ValidatedOffers,
@@ -342,34 +710,1038 @@ pub fn find_last_index_for_price(
     let mut i: usize = *start_index;
     let mut total_amount: U256 = offers[i].amount;
 
-    loop {
-        if i < offers.len() - 1 || offers[i + 1].offer_price_revealed != *price {
+    // `get` ends the scan past the last offer, without computing `offers.len() - 1`
+    while let Some(next_offer) = offers.get(i + 1) {
+        if next_offer.offer_price_revealed != *price {
             break;
         }
 
-        total_amount += offers[i + 1].amount;
+        total_amount = total_amount.saturating_add(next_offer.amount);
         i += 1;
     }
 
     (i, total_amount)
 }
 
-/// Computes the repurchase price using the 360 day count convention.
+/// Computes `a * b / denominator`, rounding down, without the intermediate product overflowing.
+///
+/// The product is computed over 512 bits, so the result is exact whenever it fits in a `U256`, and saturates otherwise.
+///
+/// # Arguments
+///
+/// * `a` - The first factor.
+/// * `b` - The second factor.
+/// * `denominator` - The divisor, which must be nonzero.
+pub fn mul_div(a: &U256, b: &U256, denominator: &U256) -> U256 {
+    let product: U512 = a.widening_mul(*b);
+    U256::saturating_from(product / U512::from(*denominator))
+}
+
+/// Computes the repurchase price using the given day count convention, rounding down.
+///
+/// Uses integer math only, so the result is exact for any amount rather than losing precision past
+/// 2^53 as a floating point computation would.
+///
+/// # Panics
+///
+/// If the day count convention is not a known variant, which `AuctionParameters::validate` rejects.
 pub fn calculate_repurchase_price(
     purchase_price: &U256,
     clearing_price: &U256,
     day_count: &U256,
+    day_count_convention: &DayCountConvention,
 ) -> U256 {
-    // RepurchasePrice = PurchasePrice * (1 + RepoRate * DayCountFactor)
-    let aux: f64 = f64::from(day_count * clearing_price) / f64::from(DAYS_IN_YEAR * BPS);
-    U256::from(f64::from(purchase_price) * (1.0 + aux))
+    // RepoRate is a percentage with 9 decimal places, so 100% is `bps_to_internal(BPS)`
+    // RepurchasePrice = PurchasePrice + PurchasePrice * DayCount * RepoRate / (100% * DaysInYear)
+    let interest: U256 = purchase_price * day_count * clearing_price
+        / (bps_to_internal(&U256::from(BPS)) * days_in_year(day_count_convention));
+    purchase_price + interest
+}
+
+/// Computes the repurchase price like `calculate_repurchase_price`, returning `None` if it overflows.
+///
+/// # Arguments
+///
+/// * `purchase_price` - The amount being lent.
+/// * `clearing_price` - The clearing rate at which it is lent.
+/// * `day_count` - The number of days it is lent for.
+/// * `day_count_convention` - The convention the interest is computed with.
+fn checked_repurchase_price(
+    purchase_price: &U256,
+    clearing_price: &U256,
+    day_count: &U256,
+    day_count_convention: &DayCountConvention,
+) -> Option<U256> {
+    let interest: U256 = purchase_price
+        .checked_mul(*day_count)?
+        .checked_mul(*clearing_price)?
+        / (bps_to_internal(&U256::from(BPS)) * days_in_year(day_count_convention));
+    purchase_price.checked_add(interest)
+}
+
+/// Returns the number of days in a year under the day count convention.
+///
+/// # Panics
+///
+/// If the day count convention is not a known variant, which `AuctionParameters::validate` rejects.
+fn days_in_year(day_count_convention: &DayCountConvention) -> U256 {
+    U256::from(
+        day_count_convention
+            .days_in_year()
+            .expect("day count convention is checked by AuctionParameters::validate"),
+    )
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use crate::{
+        allocations::{
+            bidder_allocations::BidderAllocations, offeror_allocations::OfferorAllocations,
+        },
+        auction::report::OrderOutcomes,
+        auction_parameters::tests::random_auction_parameters,
+        constants::{DAYS_IN_YEAR, MAX_DAY_COUNT, MIN_ORDER_AMOUNT, PRICE_UNITS_PER_BPS},
+        orders::{
+            bids::{tests::random_revealed_bid, Bid},
+            offers::{tests::random_revealed_offer, Offer},
+        },
+        utils::get_key,
+    };
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_run() {
+        let prover_address: Address = Address::random();
+        let mut auction_parameters: AuctionParameters = random_auction_parameters();
+        auction_parameters.purchasePrice = U256::from(1);
+        auction_parameters.collateralPrice = U256::from(1);
+        // A full year at the 7.5% clearing price
+        auction_parameters.dayCount = U256::from(360);
+
+        // Only the bid at 1,000 and the offer at 500 are on the right side of the 750 clearing price
+        let cleared_bid: Bid = collateralized_bid(1_000 * PRICE_UNITS_PER_BPS, 1_000);
+        let unassigned_bid: Bid = collateralized_bid(600 * PRICE_UNITS_PER_BPS, 1_000);
+        let bids: Bids = Bids::from([
+            (
+                get_key(&cleared_bid.bidder, &cleared_bid.id),
+                cleared_bid.clone(),
+            ),
+            (
+                get_key(&unassigned_bid.bidder, &unassigned_bid.id),
+                unassigned_bid.clone(),
+            ),
+        ]);
+        let offers: Offers = [(500, 1_000), (700, 1_000)]
+            .into_iter()
+            .map(|(price, amount)| {
+                let mut offer: Offer = random_revealed_offer();
+                offer.offer_price_revealed = bps_to_internal(&U256::from(price));
+                offer.amount = U256::from(amount);
+                (get_key(&offer.offeror, &offer.id), offer)
+            })
+            .collect();
+
+        let (auction_results, clearing_outcome) =
+            run(&prover_address, bids, offers, &auction_parameters).unwrap();
+        assert_eq!(
+            clearing_outcome,
+            ClearingOutcome::Cleared {
+                price: bps_to_internal(&U256::from(750)),
+                volume: U256::from(1_000)
+            }
+        );
+
+        let cleared_allocation = &auction_results.bidder_allocations[&cleared_bid.bidder];
+        assert_eq!(cleared_allocation.purchase_amount(), U256::from(1_000));
+        assert_eq!(
+            cleared_allocation
+                .repurchase_obligation()
+                .repurchase_amount(),
+            U256::from(1_075)
+        );
+        // The obligation keeps 150% of its 1,075 as collateral, rounded up, and the rest is returned
+        assert_eq!(
+            cleared_allocation
+                .repurchase_obligation()
+                .collateral_amount(),
+            U256::from(1_613)
+        );
+        assert_eq!(cleared_allocation.collateral_amount(), U256::from(387));
+
+        let unassigned_allocation = &auction_results.bidder_allocations[&unassigned_bid.bidder];
+        assert_eq!(unassigned_allocation.purchase_amount(), U256::ZERO);
+        assert_eq!(unassigned_allocation.collateral_amount(), U256::from(2_000));
+    }
+
+    #[test]
+    fn test_run_no_clear() {
+        let auction_parameters: AuctionParameters = random_auction_parameters();
+
+        // Every bid is priced below every offer, so the market does not intersect
+        let bids: Vec<Bid> = vec![
+            collateralized_bid(400 * PRICE_UNITS_PER_BPS, 1_000),
+            collateralized_bid(500 * PRICE_UNITS_PER_BPS, 2_000),
+        ];
+        let offers: Vec<Offer> = vec![
+            revealed_offer(600 * PRICE_UNITS_PER_BPS, 1_000),
+            revealed_offer(700 * PRICE_UNITS_PER_BPS, 3_000),
+        ];
+
+        let (auction_results, clearing_outcome) = run(
+            &Address::random(),
+            bids.iter()
+                .map(|bid| (get_key(&bid.bidder, &bid.id), bid.clone()))
+                .collect(),
+            offers
+                .iter()
+                .map(|offer| (get_key(&offer.offeror, &offer.id), offer.clone()))
+                .collect(),
+            &auction_parameters,
+        )
+        .unwrap();
+        assert_eq!(clearing_outcome, ClearingOutcome::NoClear);
+
+        // Bidders get their collateral back, and offerors their purchase tokens
+        for bid in bids.iter() {
+            let bidder_allocation = &auction_results.bidder_allocations[&bid.bidder];
+            assert_eq!(bidder_allocation.purchase_amount(), U256::ZERO);
+            assert_eq!(bidder_allocation.collateral_amount(), bid.collateral_amount);
+        }
+        for offer in offers.iter() {
+            let offeror_allocation = &auction_results.offeror_allocations[&offer.offeror];
+            assert_eq!(offeror_allocation.purchase_amount(), offer.amount);
+            assert_eq!(offeror_allocation.repo_amount(), U256::ZERO);
+        }
+    }
+
+    #[test]
+    fn test_run_without_revealed_bids() {
+        let auction_parameters: AuctionParameters = random_auction_parameters();
+
+        // No bid was revealed, so only the offers are validated and the market cannot clear
+        let bids: Vec<Bid> = (0..3)
+            .map(|_| {
+                let mut bid: Bid = collateralized_bid(500 * PRICE_UNITS_PER_BPS, 1_000);
+                bid.is_revealed = false;
+                bid
+            })
+            .collect();
+        let offers: Vec<Offer> = vec![
+            revealed_offer(700 * PRICE_UNITS_PER_BPS, 3_000),
+            revealed_offer(100 * PRICE_UNITS_PER_BPS, 1_000),
+        ];
+
+        let (auction_results, clearing_outcome) = run(
+            &Address::random(),
+            bids.iter()
+                .map(|bid| (get_key(&bid.bidder, &bid.id), bid.clone()))
+                .collect(),
+            offers
+                .iter()
+                .map(|offer| (get_key(&offer.offeror, &offer.id), offer.clone()))
+                .collect(),
+            &auction_parameters,
+        )
+        .unwrap();
+        assert_eq!(clearing_outcome, ClearingOutcome::NoClear);
+
+        // Every order is refunded, whether it was validated or not
+        for bid in bids.iter() {
+            let bidder_allocation = &auction_results.bidder_allocations[&bid.bidder];
+            assert_eq!(bidder_allocation.purchase_amount(), U256::ZERO);
+            assert_eq!(bidder_allocation.collateral_amount(), bid.collateral_amount);
+        }
+        for offer in offers.iter() {
+            let offeror_allocation = &auction_results.offeror_allocations[&offer.offeror];
+            assert_eq!(offeror_allocation.purchase_amount(), offer.amount);
+            assert_eq!(offeror_allocation.repo_amount(), U256::ZERO);
+        }
+    }
+
+    #[test]
+    fn test_run_capped_bid() {
+        let mut auction_parameters: AuctionParameters = random_auction_parameters();
+        auction_parameters.purchasePrice = U256::from(1);
+        auction_parameters.collateralPrice = U256::from(1);
+        // A full year at the 75% clearing price
+        auction_parameters.dayCount = U256::from(360);
+
+        // The bid locks the minimum 1,500 of collateral, which cannot cover the 1,750 owed for all of its 1,000
+        let mut capped_bid: Bid = collateralized_bid(10_000 * PRICE_UNITS_PER_BPS, 1_000);
+        capped_bid.collateral_amount = U256::from(1_500);
+        let offer: Offer = revealed_offer(5_000 * PRICE_UNITS_PER_BPS, 1_000);
+        let bids: Bids = Bids::from([(
+            get_key(&capped_bid.bidder, &capped_bid.id),
+            capped_bid.clone(),
+        )]);
+        let offers: Offers = Offers::from([(get_key(&offer.offeror, &offer.id), offer.clone())]);
+
+        let (auction_results, clearing_outcome) =
+            run(&Address::random(), bids, offers, &auction_parameters).unwrap();
+        assert_eq!(
+            clearing_outcome,
+            ClearingOutcome::Cleared {
+                price: bps_to_internal(&U256::from(7_500)),
+                volume: U256::from(857)
+            }
+        );
+
+        // The bid is only lent the 857 its collateral covers once interest accrues
+        let bidder_allocation = &auction_results.bidder_allocations[&capped_bid.bidder];
+        assert_eq!(bidder_allocation.purchase_amount(), U256::from(857));
+        assert_eq!(
+            bidder_allocation
+                .repurchase_obligation()
+                .repurchase_amount(),
+            U256::from(1_499)
+        );
+        assert_eq!(
+            bidder_allocation
+                .repurchase_obligation()
+                .collateral_amount(),
+            U256::from(1_500)
+        );
+
+        // So the offer is only assigned those 857, and gets the other 143 back
+        let offeror_allocation = &auction_results.offeror_allocations[&offer.offeror];
+        assert_eq!(offeror_allocation.repo_amount(), U256::from(1_499));
+        assert_eq!(offeror_allocation.purchase_amount(), U256::from(143));
+    }
+
     #[test]
     fn test_compute_clearing_price() {
-        // We're just gonna assume Term Finance is correct and move on and our bug infested code
-        unimplemented!()
+        // Clears in the middle: offers at 200 and 400 meet bids at 500 and 700, the clearing price averages the
+        // marginal offer and bid prices
+        let bids: ValidatedBids = vec![
+            collateralized_bid(100, 1_000),
+            collateralized_bid(300, 1_000),
+            collateralized_bid(500, 1_000),
+            collateralized_bid(700, 1_000),
+        ];
+        let offers: ValidatedOffers = vec![
+            revealed_offer(200, 1_000),
+            revealed_offer(400, 1_000),
+            revealed_offer(600, 1_000),
+            revealed_offer(800, 1_000),
+        ];
+        assert_eq!(
+            compute_clearing_price(&bids, &offers),
+            (U256::from(450), U256::from(2_000))
+        );
+
+        // No intersection: every bid is below every offer, so nothing clears
+        let bids: ValidatedBids = vec![
+            collateralized_bid(100, 1_000),
+            collateralized_bid(200, 1_000),
+        ];
+        let offers: ValidatedOffers = vec![revealed_offer(500, 1_000), revealed_offer(700, 1_000)];
+        assert_eq!(
+            compute_clearing_price(&bids, &offers),
+            (U256::from(350), U256::ZERO)
+        );
+
+        // A single price group on each side: the 4,000 bid at 500 will be assigned pro-rata against the 2,000
+        // offered at 300
+        let bids: ValidatedBids = vec![
+            collateralized_bid(500, 3_000),
+            collateralized_bid(500, 1_000),
+        ];
+        let offers: ValidatedOffers = vec![revealed_offer(300, 1_000), revealed_offer(300, 1_000)];
+        assert_eq!(
+            compute_clearing_price(&bids, &offers),
+            (U256::from(400), U256::from(2_000))
+        );
+
+        // The search starts from the cheapest offer price group, so only the offer at 200 clears at 500
+        let bids: ValidatedBids = vec![
+            collateralized_bid(300, 2_000),
+            collateralized_bid(500, 3_000),
+            collateralized_bid(500, 2_000),
+            collateralized_bid(800, 3_000),
+        ];
+        let offers: ValidatedOffers = vec![revealed_offer(200, 1_000), revealed_offer(800, 4_000)];
+        assert_eq!(
+            compute_clearing_price(&bids, &offers),
+            (U256::from(500), U256::from(1_000))
+        );
+    }
+
+    #[test]
+    fn test_compute_vwap_clearing_price() {
+        // Equal amounts on both sides of the midpoint leave the price where it was
+        let bids: ValidatedBids = vec![
+            collateralized_bid(100, 1_000),
+            collateralized_bid(300, 1_000),
+            collateralized_bid(500, 1_000),
+            collateralized_bid(700, 1_000),
+        ];
+        let offers: ValidatedOffers = vec![
+            revealed_offer(200, 1_000),
+            revealed_offer(400, 1_000),
+            revealed_offer(600, 1_000),
+            revealed_offer(800, 1_000),
+        ];
+        assert_eq!(
+            compute_vwap_clearing_price(&bids, &offers),
+            compute_clearing_price(&bids, &offers)
+        );
+
+        // A large bid at 700 pulls the price up from the 450 midpoint to (500 + 3 * 700 + 200 + 400) / 6
+        let bids: ValidatedBids = vec![
+            collateralized_bid(100, 1_000),
+            collateralized_bid(500, 1_000),
+            collateralized_bid(700, 3_000),
+        ];
+        let offers: ValidatedOffers = vec![
+            revealed_offer(200, 1_000),
+            revealed_offer(400, 1_000),
+            revealed_offer(800, 1_000),
+        ];
+        assert_eq!(
+            compute_clearing_price(&bids, &offers),
+            (U256::from(450), U256::from(2_000))
+        );
+        assert_eq!(
+            compute_vwap_clearing_price(&bids, &offers),
+            (U256::from(533), U256::from(2_000))
+        );
+
+        // The bid at 500 no longer crosses at 533, so only the 1,000 bid at 700 is left against both offers
+        let bids: ValidatedBids = vec![
+            collateralized_bid(500, 1_000),
+            collateralized_bid(700, 1_000),
+        ];
+        let offers: ValidatedOffers = vec![revealed_offer(200, 3_000), revealed_offer(400, 1_000)];
+        let (midpoint_price, _) = compute_clearing_price(&bids, &offers);
+        let (vwap_price, vwap_max_assignable) = compute_vwap_clearing_price(&bids, &offers);
+        assert!(vwap_price < midpoint_price);
+        assert_eq!(vwap_max_assignable, U256::from(2_000));
+    }
+
+    #[test]
+    fn test_run_auction_modes() {
+        let mut auction_parameters: AuctionParameters = random_auction_parameters();
+        auction_parameters.purchasePrice = U256::from(1);
+        auction_parameters.collateralPrice = U256::from(1);
+        auction_parameters.dayCount = U256::from(360);
+        let bids: Vec<Bid> = vec![
+            collateralized_bid(100 * PRICE_UNITS_PER_BPS, 1_000),
+            collateralized_bid(500 * PRICE_UNITS_PER_BPS, 1_000),
+            collateralized_bid(700 * PRICE_UNITS_PER_BPS, 3_000),
+        ];
+        let offers: Vec<Offer> = vec![
+            revealed_offer(200 * PRICE_UNITS_PER_BPS, 1_000),
+            revealed_offer(400 * PRICE_UNITS_PER_BPS, 1_000),
+            revealed_offer(800 * PRICE_UNITS_PER_BPS, 1_000),
+        ];
+        let run_in_mode = |auction_mode: AuctionMode| {
+            let mut auction_parameters: AuctionParameters = auction_parameters.clone();
+            auction_parameters.auctionMode = auction_mode;
+            run(
+                &Address::random(),
+                bids.iter()
+                    .map(|bid| (get_key(&bid.bidder, &bid.id), bid.clone()))
+                    .collect(),
+                offers
+                    .iter()
+                    .map(|offer| (get_key(&offer.offeror, &offer.id), offer.clone()))
+                    .collect(),
+                &auction_parameters,
+            )
+            .unwrap()
+        };
+
+        // Both modes lend the same amount, but the large bid at 700 raises the volume weighted price
+        let (midpoint_results, midpoint_outcome) = run_in_mode(AuctionMode::SecondPriceMidpoint);
+        let (vwap_results, vwap_outcome) = run_in_mode(AuctionMode::VolumeWeighted);
+        assert_eq!(
+            midpoint_outcome,
+            ClearingOutcome::Cleared {
+                price: U256::from(450 * PRICE_UNITS_PER_BPS),
+                volume: U256::from(2_000)
+            }
+        );
+        assert_eq!(
+            vwap_outcome,
+            ClearingOutcome::Cleared {
+                price: U256::from(3_200_000 * PRICE_UNITS_PER_BPS / 6_000),
+                volume: U256::from(2_000)
+            }
+        );
+
+        // The bid at 700 borrows the whole volume either way, but repurchases it at a higher price
+        let midpoint_allocation = &midpoint_results.bidder_allocations[&bids[2].bidder];
+        let vwap_allocation = &vwap_results.bidder_allocations[&bids[2].bidder];
+        assert_eq!(midpoint_allocation.purchase_amount(), U256::from(2_000));
+        assert_eq!(vwap_allocation.purchase_amount(), U256::from(2_000));
+        assert!(
+            vwap_allocation.repurchase_obligation().repurchase_amount()
+                > midpoint_allocation
+                    .repurchase_obligation()
+                    .repurchase_amount()
+        );
+    }
+
+    #[test]
+    fn test_increase_cum_sum_bids() {
+        let bids: ValidatedBids = vec![
+            collateralized_bid(100, 1_000),
+            collateralized_bid(200, 2_000),
+            collateralized_bid(300, 3_000),
+        ];
+
+        assert_eq!(
+            increase_cum_sum_bids(&bids, &3, &U256::ZERO, &U256::from(200)),
+            (U256::from(5_000), 1)
+        );
+        // The first bid is also added when every bid is at or above the price
+        assert_eq!(
+            increase_cum_sum_bids(&bids, &3, &U256::ZERO, &U256::from(100)),
+            (U256::from(6_000), 0)
+        );
+        // All bids below the price
+        assert_eq!(
+            increase_cum_sum_bids(&bids, &3, &U256::ZERO, &U256::from(400)),
+            (U256::ZERO, 3)
+        );
+        // Nothing left to walk down from
+        assert_eq!(
+            increase_cum_sum_bids(&bids, &0, &U256::from(6_000), &U256::from(100)),
+            (U256::from(6_000), 0)
+        );
+
+        // A single bid, above and below the price
+        let bids: ValidatedBids = vec![collateralized_bid(100, 1_000)];
+        assert_eq!(
+            increase_cum_sum_bids(&bids, &1, &U256::ZERO, &U256::from(50)),
+            (U256::from(1_000), 0)
+        );
+        assert_eq!(
+            increase_cum_sum_bids(&bids, &1, &U256::ZERO, &U256::from(150)),
+            (U256::ZERO, 1)
+        );
+    }
+
+    #[test]
+    fn test_decrease_cum_sum_bids() {
+        let bids: ValidatedBids = vec![
+            collateralized_bid(100, 1_000),
+            collateralized_bid(200, 2_000),
+            collateralized_bid(300, 3_000),
+        ];
+
+        assert_eq!(
+            decrease_cum_sum_bids(&bids, &0, &U256::from(6_000), &U256::from(250)),
+            (U256::from(3_000), 2)
+        );
+        // All bids below the price
+        assert_eq!(
+            decrease_cum_sum_bids(&bids, &0, &U256::from(6_000), &U256::from(400)),
+            (U256::ZERO, 3)
+        );
+        // Nothing left to walk up from
+        assert_eq!(
+            decrease_cum_sum_bids(&bids, &3, &U256::ZERO, &U256::from(400)),
+            (U256::ZERO, 3)
+        );
+
+        // A single bid, above and below the price
+        let bids: ValidatedBids = vec![collateralized_bid(100, 1_000)];
+        assert_eq!(
+            decrease_cum_sum_bids(&bids, &0, &U256::from(1_000), &U256::from(50)),
+            (U256::from(1_000), 0)
+        );
+        assert_eq!(
+            decrease_cum_sum_bids(&bids, &0, &U256::from(1_000), &U256::from(150)),
+            (U256::ZERO, 1)
+        );
+    }
+
+    #[test]
+    fn test_compute_clearing_price_single_bid() {
+        let offers: ValidatedOffers = vec![revealed_offer(500, 1_000), revealed_offer(700, 1_000)];
+
+        let bids: ValidatedBids = vec![collateralized_bid(1_000, 1_000)];
+        assert_eq!(
+            compute_clearing_price(&bids, &offers),
+            (U256::from(750), U256::from(1_000))
+        );
+
+        // Nothing can be assigned when the only bid is below every offer
+        let bids: ValidatedBids = vec![collateralized_bid(100, 1_000)];
+        assert_eq!(
+            compute_clearing_price(&bids, &offers),
+            (U256::from(300), U256::ZERO)
+        );
+    }
+
+    #[test]
+    fn test_compute_clearing_price_all_bids_below_offers() {
+        let offers: ValidatedOffers = vec![revealed_offer(500, 1_000), revealed_offer(700, 1_000)];
+        let bids: ValidatedBids = vec![
+            collateralized_bid(100, 1_000),
+            collateralized_bid(200, 1_000),
+        ];
+
+        assert_eq!(
+            compute_clearing_price(&bids, &offers),
+            (U256::from(350), U256::ZERO)
+        );
+    }
+
+    #[test]
+    fn test_find_last_index_for_price() {
+        let offers: ValidatedOffers = vec![
+            revealed_offer(500, 1_000),
+            revealed_offer(800, 1_000),
+            revealed_offer(800, 2_000),
+            revealed_offer(800, 3_000),
+            revealed_offer(900, 4_000),
+        ];
+
+        // Scans forward through the whole price group of three offers
+        assert_eq!(
+            find_last_index_for_price(&U256::from(800), &offers, &1),
+            (3, U256::from(6_000))
+        );
+        // Single offer price groups
+        assert_eq!(
+            find_last_index_for_price(&U256::from(500), &offers, &0),
+            (0, U256::from(1_000))
+        );
+        // Stops at the end of the offers
+        assert_eq!(
+            find_last_index_for_price(&U256::from(900), &offers, &4),
+            (4, U256::from(4_000))
+        );
+
+        // A price group that runs up to the end of the offers
+        let offers: ValidatedOffers = vec![
+            revealed_offer(500, 1_000),
+            revealed_offer(800, 2_000),
+            revealed_offer(800, 3_000),
+        ];
+        assert_eq!(
+            find_last_index_for_price(&U256::from(800), &offers, &1),
+            (2, U256::from(5_000))
+        );
+
+        // A price group that starts at the first offer
+        let offers: ValidatedOffers = vec![
+            revealed_offer(800, 2_000),
+            revealed_offer(800, 3_000),
+            revealed_offer(900, 1_000),
+        ];
+        assert_eq!(
+            find_last_index_for_price(&U256::from(800), &offers, &0),
+            (1, U256::from(5_000))
+        );
+
+        // A single offer
+        let offers: ValidatedOffers = vec![revealed_offer(800, 2_000)];
+        assert_eq!(
+            find_last_index_for_price(&U256::from(800), &offers, &0),
+            (0, U256::from(2_000))
+        );
+    }
+
+    #[test]
+    fn test_find_first_index_for_price() {
+        let bids: ValidatedBids = vec![
+            collateralized_bid(500, 4_000),
+            collateralized_bid(800, 1_000),
+            collateralized_bid(800, 2_000),
+            collateralized_bid(800, 3_000),
+            collateralized_bid(900, 1_000),
+        ];
+
+        // Scans backwards through the whole price group of three bids
+        assert_eq!(
+            find_first_index_for_price(&U256::from(800), &bids, &3),
+            (1, U256::from(6_000))
+        );
+        // Single bid price groups, including the start of the bids
+        assert_eq!(
+            find_first_index_for_price(&U256::from(900), &bids, &4),
+            (4, U256::from(1_000))
+        );
+        assert_eq!(
+            find_first_index_for_price(&U256::from(500), &bids, &0),
+            (0, U256::from(4_000))
+        );
+
+        // A price group that starts at the first bid
+        let bids: ValidatedBids = vec![
+            collateralized_bid(800, 2_000),
+            collateralized_bid(800, 3_000),
+            collateralized_bid(900, 1_000),
+        ];
+        assert_eq!(
+            find_first_index_for_price(&U256::from(800), &bids, &1),
+            (0, U256::from(5_000))
+        );
+
+        // A price group that ends at the last bid
+        let bids: ValidatedBids = vec![
+            collateralized_bid(500, 1_000),
+            collateralized_bid(800, 2_000),
+            collateralized_bid(800, 3_000),
+        ];
+        assert_eq!(
+            find_first_index_for_price(&U256::from(800), &bids, &2),
+            (1, U256::from(5_000))
+        );
+
+        // A single bid
+        let bids: ValidatedBids = vec![collateralized_bid(800, 2_000)];
+        assert_eq!(
+            find_first_index_for_price(&U256::from(800), &bids, &0),
+            (0, U256::from(2_000))
+        );
+    }
+
+    #[test]
+    fn test_assignment_conserves_max_assignable() {
+        for _ in 0..100 {
+            // Few distinct prices and odd amounts, so that price groups are split pro-rata with rounding
+            let mut bids: ValidatedBids = (0..rand::random::<usize>() % 10 + 2)
+                .map(|_| {
+                    collateralized_bid(
+                        rand::random::<u64>() % 8 * 100 + 100,
+                        rand::random::<u64>() % 100_000 + 1_000,
+                    )
+                })
+                .collect();
+            let mut offers: ValidatedOffers = (0..rand::random::<usize>() % 10 + 2)
+                .map(|_| {
+                    revealed_offer(
+                        rand::random::<u64>() % 8 * 100 + 100,
+                        rand::random::<u64>() % 100_000 + 1_000,
+                    )
+                })
+                .collect();
+            bids.sort_orders();
+            offers.sort_orders();
+            if bids.last().unwrap().bid_price_revealed < offers[0].offer_price_revealed {
+                continue;
+            }
+
+            let (clearing_price, max_assignable) = compute_clearing_price(&bids, &offers);
+            // Collateral covering twice the amount stays above any obligation at these prices, so no bid is capped
+            let mut auction_parameters: AuctionParameters = random_auction_parameters();
+            auction_parameters.purchasePrice = U256::from(1);
+            auction_parameters.collateralPrice = U256::from(1);
+            auction_parameters.dayCount = U256::from(rand::random::<u32>() % MAX_DAY_COUNT + 1);
+            let bids_summary: AssignmentSummary = bids.assign(
+                &max_assignable,
+                &clearing_price,
+                &auction_parameters,
+                &mut BidderAllocations::new(),
+            );
+            let offers_summary: AssignmentSummary = offers.assign(
+                &max_assignable,
+                &clearing_price,
+                &auction_parameters,
+                &mut OfferorAllocations::new(),
+            );
+
+            // Rounding remainders go to the last order of a split price group, so nothing leaks
+            assert_eq!(bids_summary.total_assigned, max_assignable);
+            assert_eq!(offers_summary.total_assigned, max_assignable);
+        }
+    }
+
+    #[test]
+    fn test_assignment_outcomes_match_allocations() {
+        let mut auction_parameters: AuctionParameters = random_auction_parameters();
+        auction_parameters.purchasePrice = U256::from(1);
+        auction_parameters.collateralPrice = U256::from(1);
+        auction_parameters.dayCount = U256::from(360);
+        let bids: ValidatedBids = vec![
+            collateralized_bid(400, 1_000),
+            collateralized_bid(800, 1_000),
+            collateralized_bid(800, 3_000),
+        ];
+        let offers: ValidatedOffers = vec![
+            revealed_offer(300, 1_000),
+            revealed_offer(500, 2_000),
+            revealed_offer(900, 1_000),
+        ];
+        let (clearing_price, max_assignable) = compute_clearing_price(&bids, &offers);
+        let mut bidder_allocations: BidderAllocations = BidderAllocations::new();
+        let mut offeror_allocations: OfferorAllocations = OfferorAllocations::new();
+
+        let bids_summary: AssignmentSummary<OrderOutcomes> = bids.clone().assign_with_outcomes(
+            &max_assignable,
+            &clearing_price,
+            &auction_parameters,
+            &mut bidder_allocations,
+        );
+        let offers_summary: AssignmentSummary<OrderOutcomes> = offers.clone().assign_with_outcomes(
+            &bids_summary.total_assigned,
+            &clearing_price,
+            &auction_parameters,
+            &mut offeror_allocations,
+        );
+
+        let assigned_amount = |outcome: &OrderOutcome| match outcome {
+            OrderOutcome::Filled { amount } | OrderOutcome::Partial { amount } => *amount,
+            OrderOutcome::Refunded => U256::ZERO,
+        };
+
+        // Bidders are lent what they were assigned
+        assert_eq!(bids_summary.outcomes.len(), bids.len());
+        for bid in bids.iter() {
+            assert_eq!(
+                assigned_amount(&bids_summary.outcomes[&bid.key()]),
+                bidder_allocations[&bid.bidder].purchase_amount()
+            );
+        }
+        // Offerors get back what they were not assigned
+        assert_eq!(offers_summary.outcomes.len(), offers.len());
+        for offer in offers.iter() {
+            assert_eq!(
+                assigned_amount(&offers_summary.outcomes[&offer.key()]),
+                offer.amount - offeror_allocations[&offer.offeror].purchase_amount()
+            );
+        }
+
+        // The bids at 800 split the 3,000 offered at or below the 550 clearing price
+        assert_eq!(clearing_price, U256::from(550));
+        assert_eq!(
+            bids_summary.outcomes[&bids[0].key()],
+            OrderOutcome::Refunded
+        );
+        assert_eq!(
+            bids_summary.outcomes[&bids[1].key()],
+            OrderOutcome::Partial {
+                amount: U256::from(750)
+            }
+        );
+        assert_eq!(
+            offers_summary.outcomes[&offers[1].key()],
+            OrderOutcome::Filled {
+                amount: U256::from(2_000)
+            }
+        );
+        assert_eq!(
+            offers_summary.outcomes[&offers[2].key()],
+            OrderOutcome::Refunded
+        );
+    }
+
+    #[test]
+    fn test_mul_div() {
+        assert_eq!(
+            mul_div(&U256::from(3_000), &U256::from(2_000), &U256::from(4_000)),
+            U256::from(1_500)
+        );
+        // Rounds down
+        assert_eq!(
+            mul_div(&U256::from(10), &U256::from(1), &U256::from(3)),
+            U256::from(3)
+        );
+
+        // The intermediate product does not overflow
+        let half: U256 = U256::MAX / U256::from(2);
+        assert_eq!(mul_div(&U256::MAX, &half, &U256::MAX), half);
+        assert_eq!(mul_div(&U256::MAX, &U256::MAX, &U256::MAX), U256::MAX);
+
+        // A quotient too large for 256 bits saturates
+        assert_eq!(mul_div(&U256::MAX, &U256::MAX, &U256::from(1)), U256::MAX);
+    }
+
+    #[test]
+    fn test_calculate_repurchase_price() {
+        // 5% over 90 days accrues 1.25% of interest
+        let clearing_price: U256 = bps_to_internal(&U256::from(500));
+        let day_count: U256 = U256::from(90);
+        assert_eq!(
+            calculate_repurchase_price(
+                &U256::from(1_000_000),
+                &clearing_price,
+                &day_count,
+                &DayCountConvention::Thirty360
+            ),
+            U256::from(1_012_500)
+        );
+
+        // Rounds down
+        assert_eq!(
+            calculate_repurchase_price(
+                &U256::from(1_001),
+                &clearing_price,
+                &day_count,
+                &DayCountConvention::Thirty360
+            ),
+            U256::from(1_013)
+        );
+
+        // Large amounts are exact, whereas going through `f64` drifts by far more than a unit
+        let purchase_price: U256 = U256::from(10).pow(U256::from(30));
+        let expected: U256 = U256::from(10125) * U256::from(10).pow(U256::from(26));
+        assert_eq!(
+            calculate_repurchase_price(
+                &purchase_price,
+                &clearing_price,
+                &day_count,
+                &DayCountConvention::Thirty360
+            ),
+            expected
+        );
+        let f64_repurchase_price: U256 = U256::from(
+            f64::from(purchase_price)
+                * (1.0
+                    + f64::from(day_count * clearing_price)
+                        / (f64::from(DAYS_IN_YEAR * BPS) * PRICE_UNITS_PER_BPS as f64)),
+        );
+        assert_ne!(f64_repurchase_price, expected);
+    }
+
+    #[test]
+    fn test_calculate_repurchase_price_day_count_conventions() {
+        // 5% over 73 days accrues 1% of interest under actual/365, and more under 30/360
+        let clearing_price: U256 = bps_to_internal(&U256::from(500));
+        let day_count: U256 = U256::from(73);
+        let purchase_price: U256 = U256::from(1_000_000);
+        let actual_365: U256 = calculate_repurchase_price(
+            &purchase_price,
+            &clearing_price,
+            &day_count,
+            &DayCountConvention::Actual365,
+        );
+        let thirty_360: U256 = calculate_repurchase_price(
+            &purchase_price,
+            &clearing_price,
+            &day_count,
+            &DayCountConvention::Thirty360,
+        );
+        assert_eq!(actual_365, U256::from(1_010_000));
+        assert_eq!(thirty_360, U256::from(1_010_138));
+        assert!(thirty_360 > actual_365);
+
+        // The obligations assigned when running the auction follow the convention of the auction parameters
+        let mut auction_parameters: AuctionParameters = random_auction_parameters();
+        auction_parameters.purchasePrice = U256::from(1);
+        auction_parameters.collateralPrice = U256::from(1);
+        auction_parameters.dayCount = day_count;
+        let repo_amount = |day_count_convention: DayCountConvention| {
+            let mut auction_parameters: AuctionParameters = auction_parameters.clone();
+            auction_parameters.dayCountConvention = day_count_convention;
+            let bid: Bid = collateralized_bid(500 * PRICE_UNITS_PER_BPS, 1_000_000);
+            let offer: Offer = revealed_offer(500 * PRICE_UNITS_PER_BPS, 1_000_000);
+            let (auction_results, _) = run(
+                &Address::random(),
+                Bids::from([(get_key(&bid.bidder, &bid.id), bid.clone())]),
+                Offers::from([(get_key(&offer.offeror, &offer.id), offer.clone())]),
+                &auction_parameters,
+            )
+            .unwrap();
+            auction_results.offeror_allocations[&offer.offeror].repo_amount()
+        };
+        assert_eq!(repo_amount(DayCountConvention::Actual365), actual_365);
+        assert_eq!(repo_amount(DayCountConvention::Thirty360), thirty_360);
+    }
+
+    proptest! {
+        /// Checks that the clearing result of random crossing books is self-consistent. Books are generated as plain
+        /// `(price, amount)` pairs, so failures shrink down to the fewest and smallest orders that break an invariant.
+        #[test]
+        fn test_compute_clearing_price_invariants(bid_book in order_book(), offer_book in order_book()) {
+            let mut bids: ValidatedBids = bid_book
+                .iter()
+                .map(|(price, amount)| collateralized_bid(*price, *amount))
+                .collect();
+            let mut offers: ValidatedOffers = offer_book
+                .iter()
+                .map(|(price, amount)| revealed_offer(*price, *amount))
+                .collect();
+            bids.sort_orders();
+            offers.sort_orders();
+
+            // Only crossing books are of interest: the best bid is at or above the best offer
+            let best_bid: U256 = bids[bids.len() - 1].bid_price_revealed;
+            let best_offer: U256 = offers[0].offer_price_revealed;
+            prop_assume!(best_bid >= best_offer);
+
+            let (clearing_price, max_assignable) = compute_clearing_price(&bids, &offers);
+
+            // The clearing price lies between the best offer and the best bid
+            prop_assert!(best_offer <= clearing_price && clearing_price <= best_bid);
+
+            // The volume to assign is all that both sides can take at the clearing price, and a crossing book clears
+            let bid_volume: U256 = bids
+                .iter()
+                .filter(|bid| bid.bid_price_revealed >= clearing_price)
+                .fold(U256::ZERO, |volume, bid| volume + bid.amount);
+            let offer_volume: U256 = offers
+                .iter()
+                .filter(|offer| offer.offer_price_revealed <= clearing_price)
+                .fold(U256::ZERO, |volume, offer| volume + offer.amount);
+            prop_assert_eq!(max_assignable, U256::min(bid_volume, offer_volume));
+            prop_assert!(!max_assignable.is_zero());
+
+            // Once assigned, the volume lent to bidders is the volume taken from offerors
+            let mut auction_parameters: AuctionParameters = random_auction_parameters();
+            auction_parameters.purchasePrice = U256::from(1);
+            auction_parameters.collateralPrice = U256::from(1);
+            auction_parameters.dayCount = U256::from(1);
+            let (auction_results, clearing_outcome) = run(
+                &Address::random(),
+                bids.iter()
+                    .map(|bid| (get_key(&bid.bidder, &bid.id), bid.clone()))
+                    .collect(),
+                offers
+                    .iter()
+                    .map(|offer| (get_key(&offer.offeror, &offer.id), offer.clone()))
+                    .collect(),
+                &auction_parameters,
+            )
+            .unwrap();
+            let lent_volume: U256 = auction_results
+                .bidder_allocations
+                .values()
+                .fold(U256::ZERO, |volume, allocation| volume + allocation.purchase_amount());
+            let offered_volume: U256 = offers
+                .iter()
+                .fold(U256::ZERO, |volume, offer| volume + offer.amount);
+            let refunded_volume: U256 = auction_results
+                .offeror_allocations
+                .values()
+                .fold(U256::ZERO, |volume, allocation| volume + allocation.purchase_amount());
+            prop_assert_eq!(lent_volume, offered_volume - refunded_volume);
+            prop_assert_eq!(
+                clearing_outcome,
+                ClearingOutcome::Cleared { price: clearing_price, volume: lent_volume }
+            );
+        }
+    }
+
+    // HELPER FUNCTIONS
+    /// Creates a revealed bid with the given price and amount, locking twice the amount as collateral.
+    fn collateralized_bid(price: u64, amount: u64) -> Bid {
+        let mut bid: Bid = random_revealed_bid();
+        bid.bid_price_revealed = U256::from(price);
+        bid.amount = U256::from(amount);
+        bid.collateral_amount = U256::from(2 * amount);
+        bid
+    }
+
+    /// Creates a revealed offer with the given price and amount.
+    fn revealed_offer(price: u64, amount: u64) -> Offer {
+        let mut offer: Offer = random_revealed_offer();
+        offer.offer_price_revealed = U256::from(price);
+        offer.amount = U256::from(amount);
+        offer
+    }
+
+    /// Generates the `(price, amount)` pairs of the orders on one side of a book. Prices are drawn from a narrow range
+    /// so that orders often share a price group, and amounts are never below `MIN_ORDER_AMOUNT`.
+    fn order_book() -> impl Strategy<Value = Vec<(u64, u64)>> {
+        prop::collection::vec(
+            (1..=20u64, u64::from(MIN_ORDER_AMOUNT)..=1_000_000u64),
+            1..16,
+        )
     }
 }
@@ -0,0 +1,245 @@
+use alloc::collections::BTreeMap;
+use alloy_primitives::{Address, B256, U256};
+use serde::Serialize;
+
+use crate::{
+    allocations::{
+        bidder_allocations::BidderAllocations, offeror_allocations::OfferorAllocations,
+        AuctionResults,
+    },
+    auction_parameters::AuctionParameters,
+    orders::{
+        bids::{Bids, ValidatedBids},
+        offers::{Offers, ValidatedOffers},
+        PlacedOrders, ValidatedOrders,
+    },
+    AuctionError,
+};
+
+use super::{assign_sorted_orders, ClearingOutcome};
+
+/// The outcome of a single order once the auction ran.
+#[derive(Serialize, PartialEq, Eq, Debug, Clone, Copy)]
+pub enum OrderOutcome {
+    /// The whole amount of the order was assigned.
+    Filled { amount: U256 },
+    /// Only part of the amount of the order was assigned.
+    Partial { amount: U256 },
+    /// Nothing was assigned, and the order gets its tokens back.
+    Refunded,
+}
+
+impl OrderOutcome {
+    /// Classifies the assignment of an order.
+    ///
+    /// # Arguments
+    ///
+    /// * `order_amount` - The whole amount of the order.
+    /// * `assigned_amount` - The amount of the order that was assigned.
+    pub fn from_assignment(order_amount: &U256, assigned_amount: U256) -> Self {
+        if assigned_amount.is_zero() {
+            OrderOutcome::Refunded
+        } else if assigned_amount == *order_amount {
+            OrderOutcome::Filled {
+                amount: assigned_amount,
+            }
+        } else {
+            OrderOutcome::Partial {
+                amount: assigned_amount,
+            }
+        }
+    }
+}
+
+/// Type alias for the outcome of every order, keyed by its `get_key(address, id)` key.
+pub type OrderOutcomes = BTreeMap<B256, OrderOutcome>;
+
+/// A destination for the outcome of every order assigned when running the auction.
+pub trait OrderOutcomeSink {
+    /// Takes the outcome of the next order.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The key of the order.
+    /// * `outcome` - The outcome of the order.
+    fn push(&mut self, key: B256, outcome: OrderOutcome);
+}
+
+impl OrderOutcomeSink for OrderOutcomes {
+    fn push(&mut self, key: B256, outcome: OrderOutcome) {
+        self.insert(key, outcome);
+    }
+}
+
+/// Discards every outcome, as `run` only needs the allocations.
+impl OrderOutcomeSink for () {
+    fn push(&mut self, _key: B256, _outcome: OrderOutcome) {}
+}
+
+/// The outcome of every bid and offer placed in an auction.
+#[derive(Serialize, Default, PartialEq, Eq, Debug)]
+pub struct AuctionReport {
+    /// The outcome of every placed bid.
+    pub bids: OrderOutcomes,
+    /// The outcome of every placed offer.
+    pub offers: OrderOutcomes,
+}
+
+/// Runs the auction over the placed orders like `run`, reporting the outcome of every order instead of the
+/// allocation of every address.
+///
+/// # Arguments
+///
+/// * `bids` - The placed bids, updated with their reveals.
+/// * `offers` - The placed offers, updated with their reveals.
+/// * `auction_parameters` - The token information for the assets involved in the auction.
+///
+/// # Returns
+///
+/// * `Result<AuctionReport, AuctionError>` - The outcome of every placed bid and offer, with invalid orders reported
+///   as refunded, or the error `run` would fail with.
+pub fn report(
+    bids: Bids,
+    offers: Offers,
+    auction_parameters: &AuctionParameters,
+) -> Result<AuctionReport, AuctionError> {
+    // Every order is refunded unless it gets assigned below
+    let mut report: AuctionReport = AuctionReport {
+        bids: bids
+            .keys()
+            .map(|key| (*key, OrderOutcome::Refunded))
+            .collect(),
+        offers: offers
+            .keys()
+            .map(|key| (*key, OrderOutcome::Refunded))
+            .collect(),
+    };
+
+    // Invalid orders are already reported as refunded
+    let mut validated_bids: ValidatedBids =
+        bids.into_validated_orders(auction_parameters, &mut BidderAllocations::new());
+    let mut validated_offers: ValidatedOffers =
+        offers.into_validated_orders(auction_parameters, &mut OfferorAllocations::new());
+    validated_bids.sort_orders();
+    validated_offers.sort_orders();
+
+    // Only the outcomes are reported, so the allocations are discarded
+    let (_, bid_outcomes, offer_outcomes): (ClearingOutcome, OrderOutcomes, OrderOutcomes) =
+        assign_sorted_orders(
+            &mut AuctionResults::new(&Address::ZERO),
+            validated_bids,
+            validated_offers,
+            auction_parameters,
+        )?;
+    report.bids.extend(bid_outcomes);
+    report.offers.extend(offer_outcomes);
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        auction_parameters::tests::random_auction_parameters,
+        orders::{
+            bids::{tests::random_revealed_bid, Bid},
+            offers::{tests::random_revealed_offer, Offer},
+        },
+        utils::{bps_to_internal, get_key},
+    };
+
+    #[test]
+    fn test_report() {
+        let mut auction_parameters: AuctionParameters = random_auction_parameters();
+        auction_parameters.purchasePrice = U256::from(1);
+        auction_parameters.collateralPrice = U256::from(1);
+        auction_parameters.dayCount = U256::from(360);
+
+        // The same bidder places the most competitive bid and one below the clearing price
+        let cleared_bid: Bid = collateralized_bid(1_000, 1_000);
+        let mut unassigned_bid: Bid = collateralized_bid(100, 1_000);
+        unassigned_bid.bidder = cleared_bid.bidder;
+        let mut unrevealed_bid: Bid = collateralized_bid(1_000, 1_000);
+        unrevealed_bid.is_revealed = false;
+        // Both offers at 500 split the 1,000 borrowed
+        let offers: Vec<Offer> = vec![
+            revealed_offer(500, 1_000),
+            revealed_offer(500, 1_000),
+            revealed_offer(900, 1_000),
+        ];
+
+        let auction_report: AuctionReport = report(
+            [&cleared_bid, &unassigned_bid, &unrevealed_bid]
+                .into_iter()
+                .map(|bid| (get_key(&bid.bidder, &bid.id), bid.clone()))
+                .collect(),
+            offers
+                .iter()
+                .map(|offer| (get_key(&offer.offeror, &offer.id), offer.clone()))
+                .collect(),
+            &auction_parameters,
+        )
+        .unwrap();
+
+        assert_eq!(
+            auction_report.bids,
+            OrderOutcomes::from([
+                (
+                    get_key(&cleared_bid.bidder, &cleared_bid.id),
+                    OrderOutcome::Filled {
+                        amount: U256::from(1_000)
+                    }
+                ),
+                (
+                    get_key(&unassigned_bid.bidder, &unassigned_bid.id),
+                    OrderOutcome::Refunded
+                ),
+                (
+                    get_key(&unrevealed_bid.bidder, &unrevealed_bid.id),
+                    OrderOutcome::Refunded
+                ),
+            ])
+        );
+        assert_eq!(
+            auction_report.offers,
+            OrderOutcomes::from([
+                (
+                    get_key(&offers[0].offeror, &offers[0].id),
+                    OrderOutcome::Partial {
+                        amount: U256::from(500)
+                    }
+                ),
+                (
+                    get_key(&offers[1].offeror, &offers[1].id),
+                    OrderOutcome::Partial {
+                        amount: U256::from(500)
+                    }
+                ),
+                (
+                    get_key(&offers[2].offeror, &offers[2].id),
+                    OrderOutcome::Refunded
+                ),
+            ])
+        );
+    }
+
+    // HELPER FUNCTIONS
+    /// Creates a revealed bid with the given price, in basis points, and amount, locking twice the amount as
+    /// collateral.
+    fn collateralized_bid(price: u64, amount: u64) -> Bid {
+        let mut bid: Bid = random_revealed_bid();
+        bid.bid_price_revealed = bps_to_internal(&U256::from(price));
+        bid.amount = U256::from(amount);
+        bid.collateral_amount = U256::from(2 * amount);
+        bid
+    }
+
+    /// Creates a revealed offer with the given price, in basis points, and amount.
+    fn revealed_offer(price: u64, amount: u64) -> Offer {
+        let mut offer: Offer = random_revealed_offer();
+        offer.offer_price_revealed = bps_to_internal(&U256::from(price));
+        offer.amount = U256::from(amount);
+        offer
+    }
+}
@@ -0,0 +1,282 @@
+use crate::{
+    auction_parameters::AuctionParameters,
+    orders::{
+        bids::{BidReveal, BidReveals, BidSubmission, BidSubmissions},
+        offers::{OfferReveal, OfferReveals, OfferSubmission, OfferSubmissions},
+    },
+    precompiles::HashFunction,
+    utils::{get_key, get_price_hash},
+};
+use alloc::vec::Vec;
+use alloy_primitives::{aliases::U96, Address, U256};
+
+/// Marks the first byte of the addresses the builder places bids with.
+const BIDDER_ADDRESS_PREFIX: u8 = 0xb1;
+/// Marks the first byte of the addresses the builder places offers with.
+const OFFEROR_ADDRESS_PREFIX: u8 = 0x0f;
+
+/// The inputs of `run_auction`, as emitted by an `AuctionBuilder`.
+pub struct AuctionInputs {
+    /// The bid submissions, in the order they were added.
+    pub bid_submissions: BidSubmissions,
+    /// The offer submissions, in the order they were added.
+    pub offer_submissions: OfferSubmissions,
+    /// The reveals matching every bid submission.
+    pub bid_reveals: BidReveals,
+    /// The reveals matching every offer submission.
+    pub offer_reveals: OfferReveals,
+    /// The auction parameters the auction runs with.
+    pub auction_parameters: AuctionParameters,
+}
+
+/// Builds consistent `run_auction` inputs out of plain prices and amounts.
+///
+/// Every order is placed by its own deterministic address, and revealed with a nonce such that its price
+/// hash matches the one submitted.
+pub struct AuctionBuilder {
+    /// The auction parameters the auction runs with.
+    auction_parameters: AuctionParameters,
+    /// The bids added so far, as `(price, amount, collateral_amount)`.
+    bids: Vec<(U256, U256, U256)>,
+    /// The offers added so far, as `(price, amount)`.
+    offers: Vec<(U256, U256)>,
+}
+
+impl AuctionBuilder {
+    /// Creates a new `AuctionBuilder` with no orders.
+    ///
+    /// # Arguments
+    ///
+    /// * `auction_parameters` - The auction parameters the auction runs with.
+    pub fn new(auction_parameters: AuctionParameters) -> Self {
+        Self {
+            auction_parameters,
+            bids: Vec::new(),
+            offers: Vec::new(),
+        }
+    }
+
+    /// Adds a bid that gets submitted and revealed.
+    ///
+    /// # Arguments
+    ///
+    /// * `price` - The price of the bid.
+    /// * `amount` - The maximum amount of purchase tokens to borrow.
+    /// * `collateral_amount` - The amount of collateral tokens locked.
+    pub fn add_bid(mut self, price: U256, amount: U256, collateral_amount: U256) -> Self {
+        self.bids.push((price, amount, collateral_amount));
+        self
+    }
+
+    /// Adds an offer that gets submitted and revealed.
+    ///
+    /// # Arguments
+    ///
+    /// * `price` - The price of the offer.
+    /// * `amount` - The maximum amount of purchase tokens to lend.
+    pub fn add_offer(mut self, price: U256, amount: U256) -> Self {
+        self.offers.push((price, amount));
+        self
+    }
+
+    /// Emits the submissions and reveals of every order added, alongside the auction parameters.
+    ///
+    /// # Arguments
+    ///
+    /// * `hash_function` - A function that computes a 32-byte hash from a byte slice, used for the price hashes.
+    ///
+    /// # Returns
+    ///
+    /// The `AuctionInputs` to run the auction with.
+    pub fn build<F: HashFunction + ?Sized>(self, hash_function: &F) -> AuctionInputs {
+        let mut bid_submissions: BidSubmissions = BidSubmissions::new();
+        let mut bid_reveals: BidReveals = BidReveals::new();
+        for (index, (price, amount, collateral_amount)) in self.bids.into_iter().enumerate() {
+            let bidder: Address = order_address(BIDDER_ADDRESS_PREFIX, index);
+            let id: U96 = U96::from(index);
+            let nonce: U256 = U256::from(index);
+            bid_submissions.push(BidSubmission {
+                bidder,
+                id,
+                bidPriceHash: get_price_hash(hash_function, &price, &nonce),
+                amount,
+                collateralAmount: collateral_amount,
+                isRollover: false,
+                rolloverPairOffTermRepoServicer: Address::ZERO,
+            });
+            bid_reveals.push(BidReveal {
+                orderId: get_key(&bidder, &id).into(),
+                price,
+                nonce,
+            });
+        }
+
+        let mut offer_submissions: OfferSubmissions = OfferSubmissions::new();
+        let mut offer_reveals: OfferReveals = OfferReveals::new();
+        for (index, (price, amount)) in self.offers.into_iter().enumerate() {
+            let offeror: Address = order_address(OFFEROR_ADDRESS_PREFIX, index);
+            let id: U96 = U96::from(index);
+            let nonce: U256 = U256::from(index);
+            offer_submissions.push(OfferSubmission {
+                offeror,
+                id,
+                offerPriceHash: get_price_hash(hash_function, &price, &nonce),
+                amount,
+            });
+            offer_reveals.push(OfferReveal {
+                orderId: get_key(&offeror, &id).into(),
+                price,
+                nonce,
+            });
+        }
+
+        AuctionInputs {
+            bid_submissions,
+            offer_submissions,
+            bid_reveals,
+            offer_reveals,
+            auction_parameters: self.auction_parameters,
+        }
+    }
+}
+
+/// Returns the address placing the order at `index`, starting with `prefix` and ending with the index.
+///
+/// # Arguments
+///
+/// * `prefix` - The first byte of the address.
+/// * `index` - The index of the order.
+fn order_address(prefix: u8, index: usize) -> Address {
+    let mut address = [0u8; 20];
+    address[0] = prefix;
+    address[12..20].copy_from_slice(&(index as u64).to_be_bytes());
+    Address::from(address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        auction_parameters::tests::random_auction_parameters,
+        exit_tree::{ExitLeaf, ExitLeafRepurchaseObligation, ExitLeafTokenWithdrawal},
+        run_auction_with_results,
+        utils::bps_to_internal,
+    };
+    use alloy_primitives::keccak256;
+
+    #[test]
+    fn test_build() {
+        let inputs: AuctionInputs = AuctionBuilder::new(random_auction_parameters())
+            .add_bid(U256::from(1_000), U256::from(1_000), U256::from(2_000))
+            .add_bid(U256::from(600), U256::from(1_000), U256::from(2_000))
+            .add_offer(U256::from(500), U256::from(1_000))
+            .build(&|x: &[u8]| keccak256(x));
+
+        assert_eq!(inputs.bid_submissions.len(), 2);
+        assert_eq!(inputs.bid_reveals.len(), 2);
+        assert_eq!(inputs.offer_submissions.len(), 1);
+        assert_eq!(inputs.offer_reveals.len(), 1);
+
+        // Every order is placed by a different address, and its reveal matches the submitted price hash
+        assert_ne!(
+            inputs.bid_submissions[0].bidder,
+            inputs.bid_submissions[1].bidder
+        );
+        assert_ne!(
+            inputs.bid_submissions[0].bidder,
+            inputs.offer_submissions[0].offeror
+        );
+        for (bid_submission, bid_reveal) in inputs.bid_submissions.iter().zip(&inputs.bid_reveals) {
+            assert_eq!(
+                bid_reveal.orderId,
+                get_key(&bid_submission.bidder, &bid_submission.id).into()
+            );
+            assert_eq!(
+                bid_submission.bidPriceHash,
+                get_price_hash(
+                    &|x: &[u8]| keccak256(x),
+                    &bid_reveal.price,
+                    &bid_reveal.nonce
+                )
+            );
+        }
+        let (offer_submission, offer_reveal) =
+            (&inputs.offer_submissions[0], &inputs.offer_reveals[0]);
+        assert_eq!(
+            offer_reveal.orderId,
+            get_key(&offer_submission.offeror, &offer_submission.id).into()
+        );
+        assert_eq!(
+            offer_submission.offerPriceHash,
+            get_price_hash(
+                &|x: &[u8]| keccak256(x),
+                &offer_reveal.price,
+                &offer_reveal.nonce
+            )
+        );
+    }
+
+    #[test]
+    fn test_build_and_run_auction() {
+        let mut auction_parameters: AuctionParameters = random_auction_parameters();
+        auction_parameters.purchasePrice = U256::from(1);
+        auction_parameters.collateralPrice = U256::from(1);
+        // A full year at the 7.5% clearing price
+        auction_parameters.dayCount = U256::from(360);
+
+        // Only the bid at 1,000 and the offer at 500 are on the right side of the 750 clearing price
+        let inputs: AuctionInputs = AuctionBuilder::new(auction_parameters)
+            .add_bid(bps(1_000), U256::from(1_000), U256::from(2_000))
+            .add_bid(bps(600), U256::from(1_000), U256::from(2_000))
+            .add_offer(bps(500), U256::from(1_000))
+            .add_offer(bps(700), U256::from(1_000))
+            .build(&|x: &[u8]| keccak256(x));
+
+        let (_, exit_leaves) = run_auction_with_results(
+            &|x: &[u8]| keccak256(x),
+            &Address::random(),
+            &inputs.bid_submissions,
+            &inputs.offer_submissions,
+            &inputs.bid_reveals,
+            &inputs.offer_reveals,
+            &inputs.auction_parameters,
+        )
+        .unwrap();
+
+        let cleared_bidder: Address = inputs.bid_submissions[0].bidder;
+        let unassigned_bidder: Address = inputs.bid_submissions[1].bidder;
+        let purchase_token: Address = inputs.auction_parameters.purchaseToken;
+        let collateral_token: Address = inputs.auction_parameters.collateralToken;
+        for exit_leaf in [
+            ExitLeaf::TokenWithdrawal(ExitLeafTokenWithdrawal {
+                recipient: cleared_bidder,
+                token: purchase_token,
+                amount: U256::from(1_000),
+            }),
+            // Only 150% of the obligation stays locked as collateral, and the rest is returned
+            ExitLeaf::RepurchaseObligation(ExitLeafRepurchaseObligation {
+                debtor: cleared_bidder,
+                repurchaseAmount: U256::from(1_075),
+                collateralAmount: U256::from(1_613),
+            }),
+            ExitLeaf::TokenWithdrawal(ExitLeafTokenWithdrawal {
+                recipient: cleared_bidder,
+                token: collateral_token,
+                amount: U256::from(387),
+            }),
+            ExitLeaf::TokenWithdrawal(ExitLeafTokenWithdrawal {
+                recipient: unassigned_bidder,
+                token: collateral_token,
+                amount: U256::from(2_000),
+            }),
+        ] {
+            assert!(exit_leaves.contains(&exit_leaf));
+        }
+    }
+
+    // HELPER FUNCTIONS
+    /// Converts a price in basis points into the internal price representation.
+    fn bps(price: u64) -> U256 {
+        bps_to_internal(&U256::from(price))
+    }
+}
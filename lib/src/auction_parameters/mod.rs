@@ -1,8 +1,32 @@
-use alloy_primitives::B256;
+use crate::{
+    constants::{DAYS_IN_ACTUAL_YEAR, DAYS_IN_YEAR, MAX_DAY_COUNT},
+    precompiles::HashFunction,
+};
+use alloy_primitives::{Address, B256, U256};
 use alloy_sol_types::{sol, SolValue};
+use core::fmt;
 use serde::{Deserialize, Serialize};
 
 sol! {
+    /// The convention the interest accrued over `dayCount` days is computed with
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+    enum DayCountConvention {
+        /// Every year is taken to have 360 days
+        Thirty360,
+        /// Every year is taken to have 365 days
+        Actual365,
+    }
+
+    /// How the clearing price is computed once the market intersects
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+    enum AuctionMode {
+        /// The average of the marginal bid and offer prices, as given by `compute_clearing_price`
+        SecondPriceMidpoint,
+        /// The average price of the orders crossing at the midpoint, weighted by their amounts, as given by
+        /// `compute_vwap_clearing_price`
+        VolumeWeighted,
+    }
+
     /// A `TokenPrice` represents a given ERC-20 token address and its oracle price at proof verification time
     #[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
     struct AuctionParameters {
@@ -16,6 +40,143 @@ sol! {
         uint256 collateralPrice;
         // Number of days between auction and maturity dates, used to compute servicing fees and repurchase prices
         uint256 dayCount;
+        /// The convention the repurchase prices are computed with
+        DayCountConvention dayCountConvention;
+        /// How the clearing price is computed
+        AuctionMode auctionMode;
+    }
+}
+
+impl DayCountConvention {
+    /// Returns the number of days in a year under the convention, or `None` for a value out of range, which can only
+    /// come from decoding an invalid `uint8`.
+    pub fn days_in_year(&self) -> Option<u32> {
+        match self {
+            DayCountConvention::Thirty360 => Some(DAYS_IN_YEAR),
+            DayCountConvention::Actual365 => Some(DAYS_IN_ACTUAL_YEAR),
+            _ => None,
+        }
+    }
+}
+
+impl Default for DayCountConvention {
+    /// Defaults to the 30/360 convention repurchase prices were always computed with.
+    fn default() -> Self {
+        DayCountConvention::Thirty360
+    }
+}
+
+impl Default for AuctionMode {
+    /// Defaults to the second price midpoint the clearing price was always computed with.
+    fn default() -> Self {
+        AuctionMode::SecondPriceMidpoint
+    }
+}
+
+/// Reasons for which a set of `AuctionParameters` is rejected.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ParamError {
+    /// The purchase and collateral tokens are the same address.
+    IdenticalTokens,
+    /// The oracle price of the purchase token is zero.
+    ZeroPurchasePrice,
+    /// The oracle price of the collateral token is zero.
+    ZeroCollateralPrice,
+    /// The day count is zero or above `MAX_DAY_COUNT`.
+    InvalidDayCount,
+    /// The day count convention is not one of the `DayCountConvention` variants.
+    InvalidDayCountConvention,
+    /// The auction mode is not one of the `AuctionMode` variants.
+    InvalidAuctionMode,
+}
+
+impl fmt::Display for ParamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamError::IdenticalTokens => {
+                write!(f, "purchase and collateral tokens must be different")
+            }
+            ParamError::ZeroPurchasePrice => write!(f, "purchase token price must be nonzero"),
+            ParamError::ZeroCollateralPrice => write!(f, "collateral token price must be nonzero"),
+            ParamError::InvalidDayCount => {
+                write!(f, "day count must be between 1 and {}", MAX_DAY_COUNT)
+            }
+            ParamError::InvalidDayCountConvention => write!(f, "unknown day count convention"),
+            ParamError::InvalidAuctionMode => write!(f, "unknown auction mode"),
+        }
+    }
+}
+
+impl AuctionParameters {
+    /// Creates a new set of `AuctionParameters`, rejecting those the auction cannot run with.
+    ///
+    /// # Arguments
+    ///
+    /// * `purchase_token` - The purchase token address.
+    /// * `purchase_price` - The oracle price of the purchase token.
+    /// * `collateral_token` - The collateral token address.
+    /// * `collateral_price` - The oracle price of the collateral token.
+    /// * `day_count` - Number of days between auction and maturity dates.
+    /// * `day_count_convention` - The convention the repurchase prices are computed with.
+    /// * `auction_mode` - How the clearing price is computed.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<AuctionParameters, ParamError>` - The parameters, or the reason they were rejected.
+    pub fn try_new(
+        purchase_token: Address,
+        purchase_price: U256,
+        collateral_token: Address,
+        collateral_price: U256,
+        day_count: U256,
+        day_count_convention: DayCountConvention,
+        auction_mode: AuctionMode,
+    ) -> Result<Self, ParamError> {
+        let auction_parameters: AuctionParameters = AuctionParameters {
+            purchaseToken: purchase_token,
+            purchasePrice: purchase_price,
+            collateralToken: collateral_token,
+            collateralPrice: collateral_price,
+            dayCount: day_count,
+            dayCountConvention: day_count_convention,
+            auctionMode: auction_mode,
+        };
+        auction_parameters.validate()?;
+
+        Ok(auction_parameters)
+    }
+
+    /// Checks that the tokens are distinct, that both oracle prices are nonzero, that the day count is within
+    /// `1..=MAX_DAY_COUNT` and that the day count convention and auction mode are known.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The parameters to validate.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), ParamError>` - The reason the parameters were rejected, if any.
+    pub fn validate(&self) -> Result<(), ParamError> {
+        if self.purchaseToken == self.collateralToken {
+            return Err(ParamError::IdenticalTokens);
+        }
+        if self.purchasePrice.is_zero() {
+            return Err(ParamError::ZeroPurchasePrice);
+        }
+        if self.collateralPrice.is_zero() {
+            return Err(ParamError::ZeroCollateralPrice);
+        }
+        if self.dayCount.is_zero() || self.dayCount > U256::from(MAX_DAY_COUNT) {
+            return Err(ParamError::InvalidDayCount);
+        }
+        if self.dayCountConvention.days_in_year().is_none() {
+            return Err(ParamError::InvalidDayCountConvention);
+        }
+        if self.auctionMode == AuctionMode::__Invalid {
+            return Err(ParamError::InvalidAuctionMode);
+        }
+
+        Ok(())
     }
 }
 
@@ -27,8 +188,8 @@ pub trait HashableStruct: SolValue {
     ///
     /// * `self` - The struct to hash.
     /// * `hash_function` - A function that computes a 32-byte hash from a byte slice.
-    fn hash<F: Fn(&[u8]) -> B256>(&self, hash_function: &F) -> B256 {
-        hash_function(&self.abi_encode_packed())
+    fn hash<F: HashFunction + ?Sized>(&self, hash_function: &F) -> B256 {
+        hash_function.hash(&self.abi_encode_packed())
     }
 }
 
@@ -50,15 +211,164 @@ pub mod tests {
         encoded_tokens.extend_from_slice(&tokens.purchasePrice.abi_encode_packed());
         encoded_tokens.extend_from_slice(&tokens.collateralToken.abi_encode_packed());
         encoded_tokens.extend_from_slice(&tokens.collateralPrice.abi_encode_packed());
+        encoded_tokens.extend_from_slice(&tokens.dayCount.abi_encode_packed());
+        // Enums are packed as a single `uint8`
+        encoded_tokens.push(tokens.dayCountConvention as u8);
+        encoded_tokens.push(tokens.auctionMode as u8);
         let expected_output: B256 = keccak256(&encoded_tokens);
 
         // Testing with `sp1_keccak256`
         let sp1_output: B256 = tokens.hash(&|x: &[u8]| keccak256(x));
         assert_eq!(sp1_output, expected_output);
+    }
+
+    #[test]
+    fn test_day_count_convention_days_in_year() {
+        assert_eq!(DayCountConvention::default(), DayCountConvention::Thirty360);
+        assert_eq!(
+            DayCountConvention::Thirty360.days_in_year(),
+            Some(DAYS_IN_YEAR)
+        );
+        assert_eq!(
+            DayCountConvention::Actual365.days_in_year(),
+            Some(DAYS_IN_ACTUAL_YEAR)
+        );
+        assert_eq!(DayCountConvention::__Invalid.days_in_year(), None);
+    }
+
+    #[test]
+    fn test_try_new() {
+        let tokens: AuctionParameters = random_auction_parameters();
+
+        // Valid parameters are accepted as is
+        assert_eq!(
+            AuctionParameters::try_new(
+                tokens.purchaseToken,
+                tokens.purchasePrice,
+                tokens.collateralToken,
+                tokens.collateralPrice,
+                tokens.dayCount,
+                tokens.dayCountConvention,
+                tokens.auctionMode,
+            ),
+            Ok(tokens.clone())
+        );
+
+        // Identical tokens are rejected
+        assert_eq!(
+            AuctionParameters::try_new(
+                tokens.purchaseToken,
+                tokens.purchasePrice,
+                tokens.purchaseToken,
+                tokens.collateralPrice,
+                tokens.dayCount,
+                tokens.dayCountConvention,
+                tokens.auctionMode,
+            ),
+            Err(ParamError::IdenticalTokens)
+        );
+
+        // Zero purchase price is rejected
+        assert_eq!(
+            AuctionParameters::try_new(
+                tokens.purchaseToken,
+                U256::ZERO,
+                tokens.collateralToken,
+                tokens.collateralPrice,
+                tokens.dayCount,
+                tokens.dayCountConvention,
+                tokens.auctionMode,
+            ),
+            Err(ParamError::ZeroPurchasePrice)
+        );
+
+        // Zero collateral price is rejected
+        assert_eq!(
+            AuctionParameters::try_new(
+                tokens.purchaseToken,
+                tokens.purchasePrice,
+                tokens.collateralToken,
+                U256::ZERO,
+                tokens.dayCount,
+                tokens.dayCountConvention,
+                tokens.auctionMode,
+            ),
+            Err(ParamError::ZeroCollateralPrice)
+        );
+
+        // Day counts outside of `1..=MAX_DAY_COUNT` are rejected
+        for day_count in [U256::ZERO, U256::from(MAX_DAY_COUNT + 1)] {
+            assert_eq!(
+                AuctionParameters::try_new(
+                    tokens.purchaseToken,
+                    tokens.purchasePrice,
+                    tokens.collateralToken,
+                    tokens.collateralPrice,
+                    day_count,
+                    tokens.dayCountConvention,
+                    tokens.auctionMode,
+                ),
+                Err(ParamError::InvalidDayCount)
+            );
+        }
+        assert!(AuctionParameters::try_new(
+            tokens.purchaseToken,
+            tokens.purchasePrice,
+            tokens.collateralToken,
+            tokens.collateralPrice,
+            U256::from(MAX_DAY_COUNT),
+            tokens.dayCountConvention,
+            tokens.auctionMode,
+        )
+        .is_ok());
+
+        // Day count conventions outside of the known variants are rejected
+        assert_eq!(
+            AuctionParameters::try_new(
+                tokens.purchaseToken,
+                tokens.purchasePrice,
+                tokens.collateralToken,
+                tokens.collateralPrice,
+                tokens.dayCount,
+                DayCountConvention::__Invalid,
+                tokens.auctionMode,
+            ),
+            Err(ParamError::InvalidDayCountConvention)
+        );
+        assert!(AuctionParameters::try_new(
+            tokens.purchaseToken,
+            tokens.purchasePrice,
+            tokens.collateralToken,
+            tokens.collateralPrice,
+            tokens.dayCount,
+            DayCountConvention::Actual365,
+            tokens.auctionMode,
+        )
+        .is_ok());
 
-        // Testing with `risc0_keccak256`
-        // let risc0_output: B256 = hash_unrolled(&risc0_keccak256, &tokens);
-        // assert_eq!(risc0_output, expected_output);
+        // Auction modes outside of the known variants are rejected
+        assert_eq!(
+            AuctionParameters::try_new(
+                tokens.purchaseToken,
+                tokens.purchasePrice,
+                tokens.collateralToken,
+                tokens.collateralPrice,
+                tokens.dayCount,
+                tokens.dayCountConvention,
+                AuctionMode::__Invalid,
+            ),
+            Err(ParamError::InvalidAuctionMode)
+        );
+        assert!(AuctionParameters::try_new(
+            tokens.purchaseToken,
+            tokens.purchasePrice,
+            tokens.collateralToken,
+            tokens.collateralPrice,
+            tokens.dayCount,
+            tokens.dayCountConvention,
+            AuctionMode::VolumeWeighted,
+        )
+        .is_ok());
     }
 
     // TEST HELPER FUNCTIONS
@@ -66,10 +376,12 @@ pub mod tests {
     pub fn random_auction_parameters() -> AuctionParameters {
         AuctionParameters {
             purchaseToken: Address::random(),
-            purchasePrice: U256::from(rand::random::<u64>()),
+            purchasePrice: U256::from(rand::random::<u64>().max(1)),
             collateralToken: Address::random(),
-            collateralPrice: U256::from(rand::random::<u64>()),
-            dayCount: U256::from(rand::random::<u64>()),
+            collateralPrice: U256::from(rand::random::<u64>().max(1)),
+            dayCount: U256::from(rand::random::<u32>() % MAX_DAY_COUNT + 1),
+            dayCountConvention: DayCountConvention::Thirty360,
+            auctionMode: AuctionMode::SecondPriceMidpoint,
         }
     }
 }
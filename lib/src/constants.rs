@@ -1,14 +1,33 @@
 //! This module contains constants used throughout the project.
 
+use crate::precompiles::HashFunction;
+use alloc::vec::Vec;
+use alloy_primitives::{B256, U256};
+
 /// Basis points
 pub const BPS: u32 = 10_000;
 
+/// Number of decimal places prices are stored with, as percentages
+pub const PRICE_DECIMALS: u32 = 9;
+
+/// Number of price units in a basis point, as prices are percentages stored with 9 decimal places
+pub const PRICE_UNITS_PER_BPS: u64 = 10_000_000; // 0.01% with 9 decimal places
+
 /// Maximum price that can be specified for a bid/offer in basis points (bps)
 pub const MAX_BID_PRICE: u32 = 1_000_000; // 10,000% in bps
 
 /// Maximum price that can be specified for an offer in basis points (bps)
 pub const MAX_OFFER_PRICE: u32 = 1_000_000; // 10,000% in bps
 
+/// Minimum amount of purchase tokens that an order must bid or offer, so dust orders are refunded.
+/// This is in raw token units, regardless of the purchase token decimals, and is part of the `constants_commitment`
+/// the verifier contract checks, so a market only accepts proofs run with the minimum it was deployed for.
+pub const MIN_ORDER_AMOUNT: u32 = 1_000;
+
+/// Maximum number of orders that can be placed on either side of the auction, so the zkVM memory use stays bounded.
+/// The contract enforces the same limit as its `MAX_ORDERS` constant.
+pub const MAX_ORDERS: u32 = 10_000;
+
 /// Initial collateral ratio in basis points (bps)
 pub const INITIAL_COLLATERAL_RATIO: u32 = 15_000;
 
@@ -18,3 +37,95 @@ pub const SERVICING_FEE: u32 = 50; // 0.5% in bps
 
 /// Number of days in a year for 360 day count convention
 pub const DAYS_IN_YEAR: u32 = 360;
+
+/// Number of days in a year for the actual/365 day count convention
+pub const DAYS_IN_ACTUAL_YEAR: u32 = 365;
+
+/// Maximum number of days between auction and maturity dates, ten years under the 360 day count convention
+pub const MAX_DAY_COUNT: u32 = 3_600;
+
+/// The constants that govern how an auction is run, in the order `constants_commitment` hashes them. Only constants
+/// that cannot be derived from the others are listed.
+pub const GOVERNING_CONSTANTS: [u32; 11] = [
+    BPS,
+    PRICE_DECIMALS,
+    MAX_BID_PRICE,
+    MAX_OFFER_PRICE,
+    MIN_ORDER_AMOUNT,
+    MAX_ORDERS,
+    INITIAL_COLLATERAL_RATIO,
+    SERVICING_FEE,
+    DAYS_IN_YEAR,
+    DAYS_IN_ACTUAL_YEAR,
+    MAX_DAY_COUNT,
+];
+
+/// Hashes the `GOVERNING_CONSTANTS` together, so that the verifier contract can check the proof was produced with the
+/// constants it expects.
+///
+/// Every constant is encoded as a `uint256`, so the commitment matches `keccak256(abi.encode(...))` over the same
+/// constants onchain.
+///
+/// # Arguments
+///
+/// * `hash_function` - A function that computes a 32-byte hash from a byte slice.
+///
+/// # Returns
+///
+/// The `B256` commitment to the governing constants.
+pub fn constants_commitment<F: HashFunction + ?Sized>(hash_function: &F) -> B256 {
+    let encoded_constants: Vec<u8> = GOVERNING_CONSTANTS
+        .iter()
+        .flat_map(|constant: &u32| U256::from(*constant).to_be_bytes::<32>())
+        .collect();
+    hash_function.hash(&encoded_constants)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{b256, keccak256};
+    use alloy_sol_types::SolValue;
+
+    #[test]
+    fn test_governing_constants() {
+        // Every constant is committed to in the order the verifier contract expects
+        assert_eq!(
+            GOVERNING_CONSTANTS,
+            [
+                BPS,
+                PRICE_DECIMALS,
+                MAX_BID_PRICE,
+                MAX_OFFER_PRICE,
+                MIN_ORDER_AMOUNT,
+                MAX_ORDERS,
+                INITIAL_COLLATERAL_RATIO,
+                SERVICING_FEE,
+                DAYS_IN_YEAR,
+                DAYS_IN_ACTUAL_YEAR,
+                MAX_DAY_COUNT,
+            ]
+        );
+        // Except for those derived from the governing constants, which are left out of the commitment
+        assert_eq!(
+            PRICE_UNITS_PER_BPS,
+            10u64.pow(PRICE_DECIMALS) * 100 / u64::from(BPS)
+        );
+    }
+
+    #[test]
+    fn test_constants_commitment() {
+        let commitment: B256 = constants_commitment(&|x: &[u8]| keccak256(x));
+
+        // Recreates the onchain process
+        assert_eq!(
+            commitment,
+            keccak256(GOVERNING_CONSTANTS.map(U256::from).abi_encode())
+        );
+        // Changing any governing constant changes the commitment the verifier contract expects
+        assert_eq!(
+            commitment,
+            b256!("5fee44ef1889ec3e0de54b8c8a7a96aacaac886f9d67e0ebd28837b9aa7e448e")
+        );
+    }
+}
@@ -1,8 +1,12 @@
-use alloy_primitives::B256;
+use crate::precompiles::HashFunction;
+use alloc::{collections::BTreeMap, vec::Vec};
+use alloy_primitives::{Address, B256, U256};
 use alloy_sol_types::{sol, SolValue};
+use core::{error, fmt};
+use serde::{Deserialize, Serialize};
 
 sol! {
-    #[derive(PartialEq, Eq, Debug)]
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
     struct ExitLeafTokenWithdrawal {
         /// The recipient of the withdrawal
         address recipient;
@@ -12,7 +16,7 @@ sol! {
         uint256 amount;
     }
 
-    #[derive(PartialEq, Eq, Debug)]
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
     struct ExitLeafRepoTokenWithdrawal {
         /// The recipient of the withdrawal
         address recipient;
@@ -20,7 +24,7 @@ sol! {
         uint256 amount;
     }
 
-    #[derive(PartialEq, Eq, Debug)]
+    #[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
     struct ExitLeafRepurchaseObligation {
         /// The debtor of the repurchase obligation
         address debtor;
@@ -32,7 +36,11 @@ sol! {
 }
 
 /// Represents different types of exit operations in the system.
-#[derive(PartialEq, Eq, Debug)]
+///
+/// Serializes as the fields of the leaf alongside a `type` field naming the variant, so that off-chain indexers can
+/// decode the exit leaves without replaying the auction.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
+#[serde(tag = "type")]
 pub enum ExitLeaf {
     /// Represents a token withdrawal operation.
     TokenWithdrawal(ExitLeafTokenWithdrawal),
@@ -55,19 +63,94 @@ impl ExitLeaf {
     /// # Returns
     ///
     /// A 32-byte hash (`B256`) of the `ExitLeaf`.
-    pub fn hash<F: Fn(&[u8]) -> B256>(&self, hash_function: &F) -> B256 {
+    pub fn hash<F: HashFunction + ?Sized>(&self, hash_function: &F) -> B256 {
         match self {
-            ExitLeaf::TokenWithdrawal(withdrawal) => hash_function(&withdrawal.abi_encode_packed()),
+            ExitLeaf::TokenWithdrawal(withdrawal) => {
+                hash_function.hash(&withdrawal.abi_encode_packed())
+            }
             ExitLeaf::RepoTokenWithdrawal(withdrawal) => {
-                hash_function(&withdrawal.abi_encode_packed())
+                hash_function.hash(&withdrawal.abi_encode_packed())
             }
             ExitLeaf::RepurchaseObligation(obligation) => {
-                hash_function(&obligation.abi_encode_packed())
+                hash_function.hash(&obligation.abi_encode_packed())
             }
         }
     }
+
+    /// Returns the amount carried by the `ExitLeaf`: the amount of a withdrawal, or the repurchase amount of an
+    /// obligation. The collateral of an obligation is not included.
+    pub fn amount(&self) -> U256 {
+        match self {
+            ExitLeaf::TokenWithdrawal(withdrawal) => withdrawal.amount,
+            ExitLeaf::RepoTokenWithdrawal(withdrawal) => withdrawal.amount,
+            ExitLeaf::RepurchaseObligation(obligation) => obligation.repurchaseAmount,
+        }
+    }
+
+    /// Returns the token withdrawn by the `ExitLeaf`, if it names one.
+    ///
+    /// Only token withdrawals do: repo token withdrawals are denominated in the repo token of the auction, which the
+    /// leaf does not name, and obligations are owed rather than withdrawn.
+    pub fn token(&self) -> Option<Address> {
+        match self {
+            ExitLeaf::TokenWithdrawal(withdrawal) => Some(withdrawal.token),
+            _ => None,
+        }
+    }
+
+    /// Returns the address the `ExitLeaf` belongs to: the recipient of a withdrawal, or the debtor of an obligation.
+    pub fn recipient(&self) -> Address {
+        match self {
+            ExitLeaf::TokenWithdrawal(withdrawal) => withdrawal.recipient,
+            ExitLeaf::RepoTokenWithdrawal(withdrawal) => withdrawal.recipient,
+            ExitLeaf::RepurchaseObligation(obligation) => obligation.debtor,
+        }
+    }
+}
+
+impl fmt::Display for ExitLeaf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExitLeaf::TokenWithdrawal(withdrawal) => write!(
+                f,
+                "TokenWithdrawal(recipient: {}, token: {}, amount: {})",
+                withdrawal.recipient, withdrawal.token, withdrawal.amount
+            ),
+            ExitLeaf::RepoTokenWithdrawal(withdrawal) => write!(
+                f,
+                "RepoTokenWithdrawal(recipient: {}, amount: {})",
+                withdrawal.recipient, withdrawal.amount
+            ),
+            ExitLeaf::RepurchaseObligation(obligation) => write!(
+                f,
+                "RepurchaseObligation(debtor: {}, repurchaseAmount: {}, collateralAmount: {})",
+                obligation.debtor, obligation.repurchaseAmount, obligation.collateralAmount
+            ),
+        }
+    }
+}
+
+/// Error returned by `hash_exit_root_checked` when two exit leaves hash to the same value.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct DuplicateLeafError {
+    /// The index of the first of the colliding leaves.
+    pub first_index: usize,
+    /// The index of the second of the colliding leaves.
+    pub second_index: usize,
+}
+
+impl fmt::Display for DuplicateLeafError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the exit leaves at indices {} and {} have the same hash",
+            self.first_index, self.second_index
+        )
+    }
 }
 
+impl error::Error for DuplicateLeafError {}
+
 /// Defines a lean incremental Merkle tree.
 pub trait ExitTree {
     /// Computes the root of a lean incremental Merkle tree from a list of leaves.
@@ -78,6 +161,8 @@ pub trait ExitTree {
     /// The tree's depth dynamically adjusts to the count of leaves, enhancing efficiency
     /// by minimizing the number of hash computations.
     /// For a better understanding, refer to the [visual explanation](https://hackmd.io/@vplasencia/S1whLBN16).
+    /// Both the leaves and the nodes are hashed with `hash_function`, so a zk-friendlier hash such as `poseidon_bn254`,
+    /// behind the `poseidon` feature, can be used when the root is verified by a circuit.
     ///
     /// # Arguments
     ///
@@ -87,42 +172,266 @@ pub trait ExitTree {
     /// # Returns
     ///
     /// A 32-byte array representing the root of the Merkle tree. If the input `leaves` is empty, it returns a zero byte array.
-    fn hash_exit_root<F: Fn(&[u8]) -> B256>(&self, hash_function: &F) -> B256;
+    fn hash_exit_root<F: HashFunction + ?Sized>(&self, hash_function: &F) -> B256;
+
+    /// Computes the root of a lean incremental Merkle tree from a list of leaves, rejecting duplicate leaves.
+    ///
+    /// Behaves like `hash_exit_root`, but first checks that no two leaves hash to the same value, as
+    /// identical exit leaves would break double-withdrawal protections onchain. This check is opt-in
+    /// so that the regular path does not pay for it.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - A slice of `SolValue` elements representing the leaves of the tree.
+    /// * `hash_function` - A function that computes a 32-byte hash from a byte slice.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing either the root of the Merkle tree or a `DuplicateLeafError` identifying the
+    /// first pair of colliding leaves.
+    fn hash_exit_root_checked<F: HashFunction + ?Sized>(
+        &self,
+        hash_function: &F,
+    ) -> Result<B256, DuplicateLeafError>;
+
+    /// Returns the dimensions of the tree built by `hash_exit_root`, so that proof verification can be bounded.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The exit leaves of the tree.
+    ///
+    /// # Returns
+    ///
+    /// A tuple with the depth of the tree, `ceil(log2(n))` for `n` leaves and zero when there are none, and the
+    /// number of leaves `n`.
+    fn exit_tree_dimensions(&self) -> (usize, usize);
+
+    /// Adds up the amounts withdrawn from each token, as given by `ExitLeaf::token` and `ExitLeaf::amount`.
+    ///
+    /// Leaves that do not name a token, namely repo token withdrawals and repurchase obligations, are excluded.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The exit leaves to add up.
+    ///
+    /// # Returns
+    ///
+    /// A map from each token to the total amount withdrawn from it, saturating on overflow.
+    fn totals_by_token(&self) -> BTreeMap<Address, U256>;
+
+    /// Groups the leaves by the address they belong to, as given by `ExitLeaf::recipient`.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The exit leaves to group.
+    ///
+    /// # Returns
+    ///
+    /// A map from each address to its exit leaves, kept in the same relative order as in `self`.
+    fn group_by_recipient(&self) -> BTreeMap<Address, Vec<&ExitLeaf>>;
 }
 
 impl ExitTree for ExitLeaves {
     // TODO: functionality to dump the tree leaves and tree nodes to a json file, for data availability
-    fn hash_exit_root<F: Fn(&[u8]) -> B256>(&self, hash_function: &F) -> B256 {
-        if self.is_empty() {
-            return B256::ZERO;
-        }
-
+    fn hash_exit_root<F: HashFunction + ?Sized>(&self, hash_function: &F) -> B256 {
         // TODO: Optimize so the hash of each leaf is obtained on the first time around of the while loop
         // Get the hash of each leaf
-        let mut current_level: Vec<B256> = self
+        let leaf_hashes: Vec<B256> = self
             .iter()
             .map(|leaf: &ExitLeaf| leaf.hash(hash_function))
             .collect();
 
-        // Hash the leaves in pairs or keep the leaf if there's no pair until we get the root
-        while current_level.len() > 1 {
-            current_level = current_level
-                .chunks(2)
-                .map(|chunk: &[B256]| {
-                    if chunk.len() == 2 {
-                        let input: Vec<u8> = [&chunk[0][..], &chunk[1][..]].concat();
-                        hash_function(&input)
-                    } else {
-                        chunk[0]
-                    }
-                })
-                .collect();
+        hash_root_from_leaf_hashes(leaf_hashes, hash_function)
+    }
+
+    fn hash_exit_root_checked<F: HashFunction + ?Sized>(
+        &self,
+        hash_function: &F,
+    ) -> Result<B256, DuplicateLeafError> {
+        let leaf_hashes: Vec<B256> = self
+            .iter()
+            .map(|leaf: &ExitLeaf| leaf.hash(hash_function))
+            .collect();
+
+        // Check for colliding leaf hashes
+        let mut seen: BTreeMap<B256, usize> = BTreeMap::new();
+        for (index, leaf_hash) in leaf_hashes.iter().enumerate() {
+            if let Some(first_index) = seen.insert(*leaf_hash, index) {
+                return Err(DuplicateLeafError {
+                    first_index,
+                    second_index: index,
+                });
+            }
+        }
+
+        Ok(hash_root_from_leaf_hashes(leaf_hashes, hash_function))
+    }
+
+    fn exit_tree_dimensions(&self) -> (usize, usize) {
+        // Each level halves the nodes rounding up, so the depth is the exponent of the next power of two
+        let depth: usize = self.len().next_power_of_two().trailing_zeros() as usize;
+
+        (depth, self.len())
+    }
+
+    fn totals_by_token(&self) -> BTreeMap<Address, U256> {
+        let mut totals: BTreeMap<Address, U256> = BTreeMap::new();
+        for exit_leaf in self.iter() {
+            if let Some(token) = exit_leaf.token() {
+                let total: &mut U256 = totals.entry(token).or_default();
+                *total = total.saturating_add(exit_leaf.amount());
+            }
+        }
+
+        totals
+    }
+
+    fn group_by_recipient(&self) -> BTreeMap<Address, Vec<&ExitLeaf>> {
+        let mut groups: BTreeMap<Address, Vec<&ExitLeaf>> = BTreeMap::new();
+        for exit_leaf in self.iter() {
+            groups
+                .entry(exit_leaf.recipient())
+                .or_default()
+                .push(exit_leaf);
+        }
+
+        groups
+    }
+}
+
+/// Builds the exit tree one leaf at a time, so that its root can be read at any point.
+///
+/// Only the frontier of the lean incremental Merkle tree is kept: at each level, the node still waiting for its right
+/// counterpart. Pushing a leaf merges it with every pending node it completes, and the root folds the frontier the same
+/// way `hash_exit_root` lifts a node lacking a right counterpart. Leaves and nodes are hashed with `hash_function`, so
+/// the root matches `hash_exit_root` with the same hash function.
+pub struct ExitTreeBuilder<'a, F: HashFunction + ?Sized> {
+    /// The hash function the leaves and nodes are hashed with.
+    hash_function: &'a F,
+    /// The pending node at each level, if any.
+    frontier: Vec<Option<B256>>,
+    /// The number of leaves pushed so far.
+    size: usize,
+}
+
+impl<'a, F: HashFunction + ?Sized> ExitTreeBuilder<'a, F> {
+    /// Creates a new, empty `ExitTreeBuilder`.
+    ///
+    /// # Arguments
+    ///
+    /// * `hash_function` - A function that computes a 32-byte hash from a byte slice.
+    pub fn new(hash_function: &'a F) -> Self {
+        Self {
+            hash_function,
+            frontier: Vec::new(),
+            size: 0,
+        }
+    }
+
+    /// Hashes the `leaf` and inserts it into the tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `leaf` - The exit leaf to insert.
+    pub fn push(&mut self, leaf: &ExitLeaf) {
+        let mut node: B256 = leaf.hash(self.hash_function);
+
+        // Merge the node with the pending left counterpart of every level it completes
+        let mut level: usize = 0;
+        while let Some(Some(left)) = self.frontier.get(level) {
+            node = self.hash_pair(left, &node);
+            self.frontier[level] = None;
+            level += 1;
         }
+        if level == self.frontier.len() {
+            self.frontier.push(Some(node));
+        } else {
+            self.frontier[level] = Some(node);
+        }
+
+        self.size += 1;
+    }
+
+    /// Returns the root of the tree, or a zero `B256` value if no leaf was pushed.
+    pub fn root(&self) -> B256 {
+        // Pending nodes at lower levels are the rightmost subtrees, lifted until they meet their left counterpart
+        let mut root: Option<B256> = None;
+        for node in self.frontier.iter().flatten() {
+            root = Some(match root {
+                Some(right) => self.hash_pair(node, &right),
+                None => *node,
+            });
+        }
+
+        root.unwrap_or(B256::ZERO)
+    }
+
+    /// Returns the number of leaves pushed.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Hashes a pair of nodes together.
+    fn hash_pair(&self, left: &B256, right: &B256) -> B256 {
+        let input: Vec<u8> = [&left[..], &right[..]].concat();
+        self.hash_function.hash(&input)
+    }
+}
+
+/// A destination for the exit leaves produced when converting the auction results.
+pub trait ExitLeafSink {
+    /// Takes the next exit leaf.
+    ///
+    /// # Arguments
+    ///
+    /// * `exit_leaf` - The exit leaf produced.
+    fn push(&mut self, exit_leaf: ExitLeaf);
+}
 
-        current_level[0]
+impl ExitLeafSink for ExitLeaves {
+    fn push(&mut self, exit_leaf: ExitLeaf) {
+        Vec::push(self, exit_leaf);
     }
 }
 
+impl<F: HashFunction + ?Sized> ExitLeafSink for ExitTreeBuilder<'_, F> {
+    fn push(&mut self, exit_leaf: ExitLeaf) {
+        ExitTreeBuilder::push(self, &exit_leaf);
+    }
+}
+
+/// Computes the root of a lean incremental Merkle tree from the already hashed leaves.
+///
+/// # Arguments
+///
+/// * `leaf_hashes` - The hashes of the leaves of the tree.
+/// * `hash_function` - A function that computes a 32-byte hash from a byte slice.
+fn hash_root_from_leaf_hashes<F: HashFunction + ?Sized>(
+    mut current_level: Vec<B256>,
+    hash_function: &F,
+) -> B256 {
+    if current_level.is_empty() {
+        return B256::ZERO;
+    }
+
+    // Hash the leaves in pairs or keep the leaf if there's no pair until we get the root
+    while current_level.len() > 1 {
+        current_level = current_level
+            .chunks(2)
+            .map(|chunk: &[B256]| {
+                if chunk.len() == 2 {
+                    let input: Vec<u8> = [&chunk[0][..], &chunk[1][..]].concat();
+                    hash_function.hash(&input)
+                } else {
+                    chunk[0]
+                }
+            })
+            .collect();
+    }
+
+    current_level[0]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -153,10 +462,332 @@ mod tests {
         // Testing with `sp1_keccak256`
         let sp1_output: B256 = exit_leaves.hash_exit_root(&sp1_keccak256);
         assert_eq!(sp1_output, expected_output);
+    }
+
+    #[test]
+    #[cfg(feature = "poseidon")]
+    fn test_hash_exit_root_poseidon() {
+        use crate::precompiles::poseidon_bn254;
+        use alloy_primitives::b256;
+
+        let exit_leaves: ExitLeaves = (1..=5u8)
+            .map(|i| {
+                ExitLeaf::TokenWithdrawal(ExitLeafTokenWithdrawal {
+                    recipient: Address::repeat_byte(i),
+                    token: Address::repeat_byte(0xff - i),
+                    amount: U256::from(i) * U256::from(1_000),
+                })
+            })
+            .collect();
+
+        // The Poseidon root is stable, and distinct from the Keccak root of the same leaves
+        let poseidon_root: B256 = exit_leaves.hash_exit_root(&poseidon_bn254);
+        assert_eq!(
+            poseidon_root,
+            b256!("231b612cc6fe3b872b3bceb44de8bfbefe27384e3d78e77f82bb1c43442292a6")
+        );
+        assert_eq!(
+            exit_leaves.hash_exit_root_checked(&poseidon_bn254),
+            Ok(poseidon_root)
+        );
+        assert_ne!(poseidon_root, exit_leaves.hash_exit_root(&sp1_keccak256));
+
+        // Nodes are hashed with Poseidon as well
+        let leaf_hashes: Vec<B256> = exit_leaves
+            .iter()
+            .map(|exit_leaf| exit_leaf.hash(&poseidon_bn254))
+            .collect();
+        let hash_pair = |left: B256, right: B256| poseidon_bn254(&[&left[..], &right[..]].concat());
+        let expected_root: B256 = hash_pair(
+            hash_pair(
+                hash_pair(leaf_hashes[0], leaf_hashes[1]),
+                hash_pair(leaf_hashes[2], leaf_hashes[3]),
+            ),
+            leaf_hashes[4],
+        );
+        assert_eq!(poseidon_root, expected_root);
+    }
+
+    #[test]
+    fn test_exit_tree_builder() {
+        for num_leaves in 0..34 {
+            let exit_leaves: ExitLeaves = (0..num_leaves).map(|_| rand::random()).collect();
+            let leaf_hashes: Vec<B256> = exit_leaves
+                .iter()
+                .map(|leaf: &ExitLeaf| leaf.hash(&sp1_keccak256))
+                .collect();
+            let expected_root: B256 = exit_leaves.hash_exit_root(&sp1_keccak256);
+
+            let mut exit_tree_builder: ExitTreeBuilder<_> = ExitTreeBuilder::new(&sp1_keccak256);
+            assert_eq!(exit_tree_builder.root(), B256::ZERO);
+
+            // After every push, the root matches the one of the leaves pushed so far
+            for (index, exit_leaf) in exit_leaves.iter().enumerate() {
+                exit_tree_builder.push(exit_leaf);
+
+                assert_eq!(exit_tree_builder.size(), index + 1);
+                assert_eq!(
+                    exit_tree_builder.root(),
+                    hash_root_from_leaf_hashes(leaf_hashes[..=index].to_vec(), &sp1_keccak256)
+                );
+            }
+            assert_eq!(exit_tree_builder.root(), expected_root);
+        }
+    }
+
+    #[test]
+    fn test_hash_exit_root_checked() {
+        let mut exit_leaves: ExitLeaves = (0..11).map(|_| rand::random()).collect();
+
+        // Distinct leaves produce the same root as the unchecked variant
+        let expected_output: B256 = exit_leaves.hash_exit_root(&sp1_keccak256);
+        assert_eq!(
+            exit_leaves.hash_exit_root_checked(&sp1_keccak256),
+            Ok(expected_output)
+        );
+
+        // Two identical leaves make the checked variant error
+        let recipient: Address = Address::random();
+        let token: Address = Address::random();
+        let amount: U256 = U256::from(rand::random::<u128>());
+        exit_leaves.push(ExitLeaf::TokenWithdrawal(ExitLeafTokenWithdrawal {
+            recipient,
+            token,
+            amount,
+        }));
+        exit_leaves.push(ExitLeaf::TokenWithdrawal(ExitLeafTokenWithdrawal {
+            recipient,
+            token,
+            amount,
+        }));
+        assert_eq!(
+            exit_leaves.hash_exit_root_checked(&sp1_keccak256),
+            Err(DuplicateLeafError {
+                first_index: 11,
+                second_index: 12
+            })
+        );
+
+        // While the unchecked variant still produces a root
+        assert_ne!(exit_leaves.hash_exit_root(&sp1_keccak256), B256::ZERO);
+    }
+
+    #[test]
+    fn test_exit_tree_dimensions() {
+        for (num_leaves, expected_depth) in [(0, 0), (1, 0), (2, 1), (3, 2), (8, 3), (11, 4)] {
+            let exit_leaves: ExitLeaves = (0..num_leaves).map(|_| rand::random()).collect();
+            assert_eq!(
+                exit_leaves.exit_tree_dimensions(),
+                (expected_depth, num_leaves)
+            );
+
+            // Matches the depth of the lean incremental Merkle tree over the same leaves
+            let leaves: Vec<B256> = exit_leaves
+                .iter()
+                .map(|exit_leaf| exit_leaf.hash(&|x: &[u8]| keccak256(x)))
+                .collect();
+            assert_eq!(
+                LeanIncrementalMerkleTree::new(&leaves).depth(),
+                expected_depth
+            );
+        }
+    }
+
+    #[test]
+    fn test_totals_by_token() {
+        let purchase_token: Address = Address::random();
+        let collateral_token: Address = Address::random();
+        let exit_leaves: ExitLeaves = vec![
+            ExitLeaf::TokenWithdrawal(ExitLeafTokenWithdrawal {
+                recipient: Address::random(),
+                token: purchase_token,
+                amount: U256::from(1_000),
+            }),
+            ExitLeaf::TokenWithdrawal(ExitLeafTokenWithdrawal {
+                recipient: Address::random(),
+                token: collateral_token,
+                amount: U256::from(2_000),
+            }),
+            ExitLeaf::TokenWithdrawal(ExitLeafTokenWithdrawal {
+                recipient: Address::random(),
+                token: purchase_token,
+                amount: U256::from(500),
+            }),
+            ExitLeaf::RepoTokenWithdrawal(ExitLeafRepoTokenWithdrawal {
+                recipient: Address::random(),
+                amount: U256::from(1_500),
+            }),
+            ExitLeaf::RepurchaseObligation(ExitLeafRepurchaseObligation {
+                debtor: Address::random(),
+                repurchaseAmount: U256::from(1_515),
+                collateralAmount: U256::from(3_000),
+            }),
+        ];
+
+        // Amounts and tokens of each kind of leaf
+        let amounts: Vec<U256> = exit_leaves.iter().map(ExitLeaf::amount).collect();
+        assert_eq!(amounts, [1_000, 2_000, 500, 1_500, 1_515].map(U256::from));
+        let tokens: Vec<Option<Address>> = exit_leaves.iter().map(ExitLeaf::token).collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Some(purchase_token),
+                Some(collateral_token),
+                Some(purchase_token),
+                None,
+                None
+            ]
+        );
+
+        // Only token withdrawals add up to the totals
+        assert_eq!(
+            exit_leaves.totals_by_token(),
+            BTreeMap::from([
+                (purchase_token, U256::from(1_500)),
+                (collateral_token, U256::from(2_000)),
+            ])
+        );
+        assert!(ExitLeaves::new().totals_by_token().is_empty());
+    }
+
+    #[test]
+    fn test_exit_leaf_json() {
+        for exit_leaf in [
+            ExitLeaf::TokenWithdrawal(ExitLeafTokenWithdrawal {
+                recipient: Address::random(),
+                token: Address::random(),
+                amount: U256::from(rand::random::<u128>()),
+            }),
+            ExitLeaf::RepoTokenWithdrawal(ExitLeafRepoTokenWithdrawal {
+                recipient: Address::random(),
+                amount: U256::from(rand::random::<u128>()),
+            }),
+            ExitLeaf::RepurchaseObligation(ExitLeafRepurchaseObligation {
+                debtor: Address::random(),
+                repurchaseAmount: U256::from(rand::random::<u128>()),
+                collateralAmount: U256::from(rand::random::<u128>()),
+            }),
+        ] {
+            let json: String = serde_json::to_string(&exit_leaf).unwrap();
+            let deserialized: ExitLeaf = serde_json::from_str(&json).unwrap();
+            assert_eq!(deserialized, exit_leaf);
+        }
+    }
+
+    #[test]
+    fn test_exit_leaf_json_tags() {
+        let address: Address = Address::with_last_byte(0x11);
+        let amount: U256 = U256::from(1_000);
+
+        // Indexers rely on these names, so they must not change
+        assert_eq!(
+            serde_json::to_value(ExitLeaf::TokenWithdrawal(ExitLeafTokenWithdrawal {
+                recipient: address,
+                token: address,
+                amount,
+            }))
+            .unwrap(),
+            serde_json::json!({
+                "type": "TokenWithdrawal",
+                "recipient": address,
+                "token": address,
+                "amount": amount,
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(ExitLeaf::RepoTokenWithdrawal(ExitLeafRepoTokenWithdrawal {
+                recipient: address,
+                amount,
+            }))
+            .unwrap(),
+            serde_json::json!({
+                "type": "RepoTokenWithdrawal",
+                "recipient": address,
+                "amount": amount,
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(ExitLeaf::RepurchaseObligation(
+                ExitLeafRepurchaseObligation {
+                    debtor: address,
+                    repurchaseAmount: amount,
+                    collateralAmount: amount,
+                }
+            ))
+            .unwrap(),
+            serde_json::json!({
+                "type": "RepurchaseObligation",
+                "debtor": address,
+                "repurchaseAmount": amount,
+                "collateralAmount": amount,
+            })
+        );
+    }
+
+    #[test]
+    fn test_exit_leaf_display() {
+        let recipient: Address = Address::with_last_byte(0x11);
+        let token: Address = Address::with_last_byte(0x22);
+
+        assert_eq!(
+            ExitLeaf::TokenWithdrawal(ExitLeafTokenWithdrawal {
+                recipient,
+                token,
+                amount: U256::from(1_000),
+            })
+            .to_string(),
+            format!(
+                "TokenWithdrawal(recipient: {}, token: {}, amount: 1000)",
+                recipient, token
+            )
+        );
+        assert_eq!(
+            ExitLeaf::RepurchaseObligation(ExitLeafRepurchaseObligation {
+                debtor: recipient,
+                repurchaseAmount: U256::from(1_010),
+                collateralAmount: U256::from(2_000),
+            })
+            .to_string(),
+            format!(
+                "RepurchaseObligation(debtor: {}, repurchaseAmount: 1010, collateralAmount: 2000)",
+                recipient
+            )
+        );
+    }
+
+    #[test]
+    fn test_group_by_recipient() {
+        let recipient_a: Address = Address::random();
+        let recipient_b: Address = Address::random();
+        let exit_leaves: ExitLeaves = vec![
+            ExitLeaf::TokenWithdrawal(ExitLeafTokenWithdrawal {
+                recipient: recipient_a,
+                token: Address::random(),
+                amount: U256::from(1),
+            }),
+            ExitLeaf::RepurchaseObligation(ExitLeafRepurchaseObligation {
+                debtor: recipient_b,
+                repurchaseAmount: U256::from(2),
+                collateralAmount: U256::from(3),
+            }),
+            ExitLeaf::RepoTokenWithdrawal(ExitLeafRepoTokenWithdrawal {
+                recipient: recipient_a,
+                amount: U256::from(4),
+            }),
+            ExitLeaf::TokenWithdrawal(ExitLeafTokenWithdrawal {
+                recipient: recipient_a,
+                token: Address::random(),
+                amount: U256::from(5),
+            }),
+        ];
 
-        // TODO: Test with risc0_keccak256 once implemented
-        //let risc0_output = hash_exit_root(&risc0_keccak256, &exit_root);
-        //assert_eq!(risc0_output, expected_output);
+        let groups: BTreeMap<Address, Vec<&ExitLeaf>> = exit_leaves.group_by_recipient();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(
+            groups[&recipient_a],
+            vec![&exit_leaves[0], &exit_leaves[2], &exit_leaves[3]]
+        );
+        assert_eq!(groups[&recipient_b], vec![&exit_leaves[1]]);
     }
 
     // HELPER FUNCTIONS
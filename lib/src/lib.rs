@@ -1,5 +1,10 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
 pub mod allocations;
 pub mod auction;
+pub mod auction_builder;
 pub mod auction_parameters;
 pub mod constants;
 pub mod exit_tree;
@@ -10,13 +15,20 @@ pub mod utils;
 use allocations::AuctionResults;
 use alloy_primitives::{Address, B256};
 use alloy_sol_types::sol;
-use auction::{compute_clearing_price, AssignableOrders};
-use auction_parameters::{AuctionParameters, HashableStruct};
-use exit_tree::{ExitLeaves, ExitTree};
+use auction::{report::AuctionReport, ClearingOutcome};
+use auction_parameters::{AuctionParameters, HashableStruct, ParamError};
+use constants::{constants_commitment, MAX_ORDERS};
+use core::{error, fmt};
+use exit_tree::{ExitLeaves, ExitTree, ExitTreeBuilder};
 use orders::{
-    bids::{BidReveals, BidSubmissions, Bids, ValidatedBids},
-    offers::{OfferReveals, OfferSubmissions, Offers, ValidatedOffers},
-    ChainableSubmissions, PlacedOrders, ValidatedOrders,
+    bids::{BidReveals, BidSubmissions, Bids},
+    offers::{OfferReveals, OfferSubmissions, Offers},
+    ChainableSubmissions, OrderSubmissions,
+};
+use precompiles::HashFunction;
+use utils::{
+    track_cycles, BID_HASH_CHAIN_CYCLES, CLEARING_CYCLES, EXIT_TREE_HASHING_CYCLES,
+    OFFER_HASH_CHAIN_CYCLES,
 };
 
 sol! {
@@ -30,8 +42,107 @@ sol! {
         bytes32 accOffersHash;
         /// Hashed together auction parameters
         bytes32 auctionParametersHash;
+        /// Hashed together constants that govern how the auction is run
+        bytes32 constantsCommitment;
         /// The root of the auction results tree
         bytes32 auctionResultRoot;
+        /// The clearing price as a percentage with 9 decimal places, or zero if the market did not clear
+        uint256 clearingPrice;
+        /// The total amount that was lent, or zero if the market did not clear
+        uint256 clearedVolume;
+    }
+}
+
+impl PublicValuesStruct {
+    /// Creates the public values committed by a proof of the auction run by `prover_address`.
+    ///
+    /// # Arguments
+    ///
+    /// * `prover_address` - The address of the prover.
+    /// * `auction_output` - The values computed by `run_auction`.
+    pub fn new(prover_address: Address, auction_output: AuctionOutput) -> Self {
+        let (
+            acc_bids_hash,
+            acc_offers_hash,
+            auction_parameters_hash,
+            auction_result_root,
+            clearing_outcome,
+            constants_commitment,
+        ) = auction_output;
+        PublicValuesStruct {
+            proverAddress: prover_address,
+            accBidsHash: acc_bids_hash,
+            accOffersHash: acc_offers_hash,
+            auctionParametersHash: auction_parameters_hash,
+            constantsCommitment: constants_commitment,
+            auctionResultRoot: auction_result_root,
+            clearingPrice: clearing_outcome.price(),
+            clearedVolume: clearing_outcome.volume(),
+        }
+    }
+}
+
+/// The values computed by `run_auction`: the bids hash chain, the offers hash chain, the auction parameters hash, the
+/// auction result root, whether the market cleared and the commitment to the governing constants, which together with
+/// the prover address make up the public values.
+pub type AuctionOutput = (B256, B256, B256, B256, ClearingOutcome, B256);
+
+/// Reasons for which `run_auction` fails to compute the public values.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum AuctionError {
+    /// The auction parameters do not pass `AuctionParameters::validate`.
+    InvalidAuctionParameters(ParamError),
+    /// More than `MAX_ORDERS` bids would be placed at once.
+    TooManyBids,
+    /// More than `MAX_ORDERS` offers would be placed at once.
+    TooManyOffers,
+    /// The amounts being cleared, or the repurchase prices resulting from them, do not fit in 256 bits.
+    ArithmeticOverflow,
+}
+
+impl fmt::Display for AuctionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuctionError::InvalidAuctionParameters(error) => {
+                write!(f, "invalid auction parameters: {}", error)
+            }
+            AuctionError::TooManyBids => {
+                write!(
+                    f,
+                    "bid submissions exceed the maximum of {} orders",
+                    MAX_ORDERS
+                )
+            }
+            AuctionError::TooManyOffers => {
+                write!(
+                    f,
+                    "offer submissions exceed the maximum of {} orders",
+                    MAX_ORDERS
+                )
+            }
+            AuctionError::ArithmeticOverflow => write!(f, "auction amounts overflow"),
+        }
+    }
+}
+
+impl error::Error for AuctionError {}
+
+impl From<ParamError> for AuctionError {
+    fn from(error: ParamError) -> Self {
+        AuctionError::InvalidAuctionParameters(error)
+    }
+}
+
+impl fmt::Display for PublicValuesStruct {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "proverAddress: {}", self.proverAddress)?;
+        writeln!(f, "accBidsHash: {}", self.accBidsHash)?;
+        writeln!(f, "accOffersHash: {}", self.accOffersHash)?;
+        writeln!(f, "auctionParametersHash: {}", self.auctionParametersHash)?;
+        writeln!(f, "constantsCommitment: {}", self.constantsCommitment)?;
+        writeln!(f, "auctionResultRoot: {}", self.auctionResultRoot)?;
+        writeln!(f, "clearingPrice: {}", self.clearingPrice)?;
+        write!(f, "clearedVolume: {}", self.clearedVolume)
     }
 }
 
@@ -51,8 +162,64 @@ sol! {
 ///
 /// # Returns
 ///
-/// Returns a `PublicValuesStruct` containing the computed hashes and auction result root.
-pub fn run_auction<F: Fn(&[u8]) -> B256>(
+/// Returns the `AuctionOutput` containing the computed hashes and auction result root, or the `AuctionError` the
+/// inputs were rejected with.
+pub fn run_auction<F: HashFunction + ?Sized>(
+    hash_function: &F,
+    prover_address: &Address,
+    bid_submissions: &BidSubmissions,
+    offer_submissions: &OfferSubmissions,
+    bid_reveals: &BidReveals,
+    offer_reveals: &OfferReveals,
+    auction_parameters: &AuctionParameters,
+) -> Result<AuctionOutput, AuctionError> {
+    let (acc_bids_hash, acc_offers_hash, tokens_hash, auction_results, clearing_outcome) =
+        clear_auction(
+            hash_function,
+            prover_address,
+            bid_submissions,
+            offer_submissions,
+            bid_reveals,
+            offer_reveals,
+            auction_parameters,
+        )?;
+
+    // Hash the auction results into the auction result root as they are converted, without keeping the exit leaves
+    let auction_result_root: B256 = track_cycles(EXIT_TREE_HASHING_CYCLES, || {
+        let mut exit_tree: ExitTreeBuilder<F> = ExitTreeBuilder::new(hash_function);
+        auction_results.into_exit_leaves(auction_parameters, &mut exit_tree);
+        exit_tree.root()
+    });
+
+    Ok((
+        acc_bids_hash,
+        acc_offers_hash,
+        tokens_hash,
+        auction_result_root,
+        clearing_outcome,
+        constants_commitment(hash_function),
+    ))
+}
+
+/// Executes the auction process and computes the public values, also returning the exit leaves.
+///
+/// Behaves exactly like `run_auction`, but hands back the `ExitLeaves` the auction result root was
+/// computed from, so that callers outside the zkVM can enumerate the resulting allocations.
+///
+/// # Arguments
+///
+/// * `hash_function` - A function that computes a 32-byte hash from a byte slice.
+/// * `bids` - A vector of bid submissions.
+/// * `offers` - A vector of offer submissions.
+/// * `revealed_bids` - A vector of revealed bid information.
+/// * `revealed_offers` - A vector of revealed offer information.
+/// * `auction_parameters` - A vector of token information for the assets involved in the auction.
+///
+/// # Returns
+///
+/// Returns the `AuctionOutput` as computed by `run_auction`, alongside the `ExitLeaves` that
+/// hash to its auction result root, or the `AuctionError` the inputs were rejected with.
+pub fn run_auction_with_results<F: HashFunction + ?Sized>(
     hash_function: &F,
     prover_address: &Address,
     bid_submissions: &BidSubmissions,
@@ -60,76 +227,770 @@ pub fn run_auction<F: Fn(&[u8]) -> B256>(
     bid_reveals: &BidReveals,
     offer_reveals: &OfferReveals,
     auction_parameters: &AuctionParameters,
-) -> (B256, B256, B256, B256) {
+) -> Result<(AuctionOutput, ExitLeaves), AuctionError> {
+    let (acc_bids_hash, acc_offers_hash, tokens_hash, auction_results, clearing_outcome) =
+        clear_auction(
+            hash_function,
+            prover_address,
+            bid_submissions,
+            offer_submissions,
+            bid_reveals,
+            offer_reveals,
+            auction_parameters,
+        )?;
+
+    // Add all auction results to exit leaves, and compute the auction result root
+    let (exit_leaves, auction_result_root) = track_cycles(EXIT_TREE_HASHING_CYCLES, || {
+        let mut exit_leaves: ExitLeaves = ExitLeaves::new();
+        auction_results.into_exit_leaves(auction_parameters, &mut exit_leaves);
+        let auction_result_root: B256 = exit_leaves.hash_exit_root(hash_function);
+        (exit_leaves, auction_result_root)
+    });
+
+    // Create and return the PublicValuesStruct alongside the exit leaves
+    Ok((
+        (
+            acc_bids_hash,
+            acc_offers_hash,
+            tokens_hash,
+            auction_result_root,
+            clearing_outcome,
+            constants_commitment(hash_function),
+        ),
+        exit_leaves,
+    ))
+}
+
+/// Validates the inputs, computes the hash chains of the orders and the hash of the auction parameters, and clears
+/// the auction, leaving the auction results to be hashed into the auction result root.
+///
+/// # Arguments
+///
+/// * `hash_function` - A function that computes a 32-byte hash from a byte slice.
+/// * `prover_address` - The address of the prover.
+/// * `bid_submissions` - A vector of bid submissions.
+/// * `offer_submissions` - A vector of offer submissions.
+/// * `bid_reveals` - A vector of revealed bid information.
+/// * `offer_reveals` - A vector of revealed offer information.
+/// * `auction_parameters` - The token information for the assets involved in the auction.
+///
+/// # Returns
+///
+/// The bids hash chain, the offers hash chain, the auction parameters hash, the auction results and whether the
+/// market cleared, or the `AuctionError` the inputs were rejected with.
+fn clear_auction<F: HashFunction + ?Sized>(
+    hash_function: &F,
+    prover_address: &Address,
+    bid_submissions: &BidSubmissions,
+    offer_submissions: &OfferSubmissions,
+    bid_reveals: &BidReveals,
+    offer_reveals: &OfferReveals,
+    auction_parameters: &AuctionParameters,
+) -> Result<(B256, B256, B256, AuctionResults, ClearingOutcome), AuctionError> {
+    let (acc_bids_hash, acc_offers_hash, bids, offers) = place_orders(
+        hash_function,
+        bid_submissions,
+        offer_submissions,
+        bid_reveals,
+        offer_reveals,
+        auction_parameters,
+    )?;
+
+    // Compute the hash of the information of the auction_parameters involved in the auction
+    let tokens_hash: B256 = auction_parameters.hash(hash_function);
+
+    // Validate, clear and assign the orders
+    let (auction_results, clearing_outcome): (AuctionResults, ClearingOutcome) =
+        track_cycles(CLEARING_CYCLES, || {
+            auction::run(prover_address, bids, offers, auction_parameters)
+        })?;
+
+    Ok((
+        acc_bids_hash,
+        acc_offers_hash,
+        tokens_hash,
+        auction_results,
+        clearing_outcome,
+    ))
+}
+
+/// Reports whether every order of the auction was filled, partially filled or refunded.
+///
+/// Takes the same inputs as `run_auction`, but nothing besides the order reveals is hashed, so this is meant to be
+/// run outside the zkVM by auction participants checking on their orders.
+///
+/// # Arguments
+///
+/// * `hash_function` - A function that computes a 32-byte hash from a byte slice.
+/// * `bid_submissions` - A vector of bid submissions.
+/// * `offer_submissions` - A vector of offer submissions.
+/// * `bid_reveals` - A vector of revealed bid information.
+/// * `offer_reveals` - A vector of revealed offer information.
+/// * `auction_parameters` - The token information for the assets involved in the auction.
+///
+/// # Returns
+///
+/// Returns the `AuctionReport` with the outcome of every placed bid and offer, keyed by order key, or the
+/// `AuctionError` that `run_auction` would fail with.
+pub fn report_auction<F: HashFunction + ?Sized>(
+    hash_function: &F,
+    bid_submissions: &BidSubmissions,
+    offer_submissions: &OfferSubmissions,
+    bid_reveals: &BidReveals,
+    offer_reveals: &OfferReveals,
+    auction_parameters: &AuctionParameters,
+) -> Result<AuctionReport, AuctionError> {
+    let (_, _, bids, offers) = place_orders(
+        hash_function,
+        bid_submissions,
+        offer_submissions,
+        bid_reveals,
+        offer_reveals,
+        auction_parameters,
+    )?;
+
+    auction::report::report(bids, offers, auction_parameters)
+}
+
+/// Validates the inputs and computes the hash chains of the orders, placing every submitted order and applying its
+/// reveal.
+///
+/// # Arguments
+///
+/// * `hash_function` - A function that computes a 32-byte hash from a byte slice.
+/// * `bid_submissions` - A vector of bid submissions.
+/// * `offer_submissions` - A vector of offer submissions.
+/// * `bid_reveals` - A vector of revealed bid information.
+/// * `offer_reveals` - A vector of revealed offer information.
+/// * `auction_parameters` - The token information for the assets involved in the auction.
+///
+/// # Returns
+///
+/// The bids hash chain, the offers hash chain and the placed bids and offers, or the `AuctionError` the inputs were
+/// rejected with.
+fn place_orders<F: HashFunction + ?Sized>(
+    hash_function: &F,
+    bid_submissions: &BidSubmissions,
+    offer_submissions: &OfferSubmissions,
+    bid_reveals: &BidReveals,
+    offer_reveals: &OfferReveals,
+    auction_parameters: &AuctionParameters,
+) -> Result<(B256, B256, Bids, Offers), AuctionError> {
+    validate_inputs(bid_submissions, offer_submissions, auction_parameters)?;
+
     // Compute the hash chain for the bids
     let mut bids: Bids = Bids::new();
-    let mut acc_bids_hash: B256 = bid_submissions.hash_chain(hash_function, B256::ZERO, &mut bids);
-    acc_bids_hash = bid_reveals.hash_chain(hash_function, acc_bids_hash, &mut bids);
+    let acc_bids_hash: B256 = track_cycles(BID_HASH_CHAIN_CYCLES, || {
+        let acc_bids_hash: B256 = bid_submissions.hash_chain(hash_function, B256::ZERO, &mut bids);
+        bid_reveals.hash_chain(hash_function, acc_bids_hash, &mut bids)
+    });
 
     // Compute the hash chain for the offers
     let mut offers: Offers = Offers::new();
-    let mut acc_offers_hash: B256 =
-        offer_submissions.hash_chain(hash_function, B256::ZERO, &mut offers);
-    acc_offers_hash = offer_reveals.hash_chain(hash_function, acc_offers_hash, &mut offers);
+    let acc_offers_hash: B256 = track_cycles(OFFER_HASH_CHAIN_CYCLES, || {
+        let acc_offers_hash: B256 =
+            offer_submissions.hash_chain(hash_function, B256::ZERO, &mut offers);
+        offer_reveals.hash_chain(hash_function, acc_offers_hash, &mut offers)
+    });
 
-    // Compute the hash of the information of the auction_parameters involved in the auction
-    let tokens_hash: B256 = auction_parameters.hash(hash_function);
+    Ok((acc_bids_hash, acc_offers_hash, bids, offers))
+}
 
-    // Define the auction results
-    let mut auction_results: AuctionResults = AuctionResults::new(prover_address);
-
-    // Get validated bids and offers
-    let mut validated_bids: ValidatedBids =
-        bids.into_validated_orders(auction_parameters, &mut auction_results.bidder_allocations);
-    let mut validated_offers: ValidatedOffers =
-        offers.into_validated_orders(auction_parameters, &mut auction_results.offeror_allocations);
-
-    // Sort validated bids by *ascending* price. Orders right on the price edge will be partially filled.
-    validated_bids.sort_orders();
-    // Sort validated offers by *ascending* price. Orders right on the price edge will be partially filled.
-    validated_offers.sort_orders();
-
-    // Calculate a clearing price and assign bids and offers only if both bids and offers exist and market intersects
-    if !validated_bids.is_empty()
-        && !validated_offers.is_empty()
-        && validated_bids.last().unwrap().bid_price_revealed
-            >= validated_offers.first().unwrap().offer_price_revealed
-    {
-        let (clearing_price, max_assignable) =
-            compute_clearing_price(&validated_bids, &validated_offers);
+/// Rejects the auction inputs that would otherwise make the auction panic.
+///
+/// # Arguments
+///
+/// * `bid_submissions` - A vector of bid submissions.
+/// * `offer_submissions` - A vector of offer submissions.
+/// * `auction_parameters` - The token information for the assets involved in the auction.
+fn validate_inputs(
+    bid_submissions: &BidSubmissions,
+    offer_submissions: &OfferSubmissions,
+    auction_parameters: &AuctionParameters,
+) -> Result<(), AuctionError> {
+    // Parameters are checked when built through `AuctionParameters::try_new`, this catches any built otherwise
+    auction_parameters.validate()?;
+
+    // Building the orders mappings panics past `MAX_ORDERS`, so reject those inputs beforehand
+    if bid_submissions.peak_order_count() > MAX_ORDERS as usize {
+        return Err(AuctionError::TooManyBids);
+    }
+    if offer_submissions.peak_order_count() > MAX_ORDERS as usize {
+        return Err(AuctionError::TooManyOffers);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        allocations::{
+            bidder_allocations::BidderAllocations, offeror_allocations::OfferorAllocations,
+        },
+        auction::compute_clearing_price,
+        auction_builder::{AuctionBuilder, AuctionInputs},
+        auction_parameters::tests::random_auction_parameters,
+        constants::{MAX_BID_PRICE, MAX_OFFER_PRICE},
+        orders::{
+            bids::{
+                recompute_bid_hash_chain,
+                tests::{random_bid_submission, valid_random_bid_submission},
+                BidReveal, ValidatedBids,
+            },
+            offers::{
+                recompute_offer_hash_chain,
+                tests::{random_offer_submission, valid_random_offer_submission},
+                OfferReveal, ValidatedOffers,
+            },
+            PlacedOrders, ValidatedOrders,
+        },
+        utils::{bps_to_internal, get_key},
+    };
+    use alloy_primitives::{keccak256, U256};
+    use rand::seq::SliceRandom;
+
+    #[test]
+    fn test_run_auction_with_results() {
+        let prover_address: Address = Address::random();
+        let (bid_submissions, offer_submissions, bid_reveals, _) = random_auction_inputs(42);
+        // Offers are left unrevealed, so all orders get unlocked into the exit leaves
+        let offer_reveals: OfferReveals = OfferReveals::new();
+        let auction_parameters: AuctionParameters = random_auction_parameters();
 
-        // Assign bids and offers
-        validated_bids.assign(
-            &max_assignable,
-            &clearing_price,
-            &auction_parameters.dayCount,
-            &mut auction_results.bidder_allocations,
+        let public_values: AuctionOutput = run_auction(
+            &|x: &[u8]| keccak256(x),
+            &prover_address,
+            &bid_submissions,
+            &offer_submissions,
+            &bid_reveals,
+            &offer_reveals,
+            &auction_parameters,
+        )
+        .unwrap();
+        let (public_values_with_results, exit_leaves) = run_auction_with_results(
+            &|x: &[u8]| keccak256(x),
+            &prover_address,
+            &bid_submissions,
+            &offer_submissions,
+            &bid_reveals,
+            &offer_reveals,
+            &auction_parameters,
+        )
+        .unwrap();
+
+        // Public values match, and the exit leaves hash to the same auction result root
+        assert_eq!(public_values, public_values_with_results);
+        assert_eq!(
+            exit_leaves.hash_exit_root(&|x: &[u8]| keccak256(x)),
+            public_values.3
         );
-        validated_offers.assign(
-            &max_assignable,
-            &clearing_price,
-            &auction_parameters.dayCount,
-            &mut auction_results.offeror_allocations,
+        // Without revealed offers the market does not clear
+        assert_eq!(public_values.4, ClearingOutcome::NoClear);
+        // The governing constants are committed to whatever the inputs
+        assert_eq!(
+            public_values.5,
+            constants_commitment(&|x: &[u8]| keccak256(x))
         );
-    } else {
-        // Dump all validated bids and offers to their corresponding allocations
-        validated_bids.unlock_outstanding_orders(&mut auction_results.bidder_allocations);
-        validated_offers.unlock_outstanding_orders(&mut auction_results.offeror_allocations);
     }
 
-    // Define the exit leaves
-    let mut exit_leaves: ExitLeaves = ExitLeaves::new();
-    // Add all auction results to exit leaves
-    auction_results.into_exit_leaves(auction_parameters, &mut exit_leaves);
+    #[test]
+    fn test_recompute_hash_chains() {
+        let (bid_submissions, offer_submissions, bid_reveals, offer_reveals) =
+            random_auction_inputs(42);
 
-    // Compute the auction result root
-    let auction_result_root: B256 = exit_leaves.hash_exit_root(hash_function);
+        let public_values: AuctionOutput = run_auction(
+            &|x: &[u8]| keccak256(x),
+            &Address::random(),
+            &bid_submissions,
+            &offer_submissions,
+            &bid_reveals,
+            &offer_reveals,
+            &random_auction_parameters(),
+        )
+        .unwrap();
 
-    // Create and return the PublicValuesStruct
-    (
-        acc_bids_hash,
-        acc_offers_hash,
-        tokens_hash,
-        auction_result_root,
-    )
+        assert_eq!(
+            recompute_bid_hash_chain(&bid_submissions, &bid_reveals, B256::ZERO, &|x: &[u8]| {
+                keccak256(x)
+            }),
+            public_values.0
+        );
+        assert_eq!(
+            recompute_offer_hash_chain(
+                &offer_submissions,
+                &offer_reveals,
+                B256::ZERO,
+                &|x: &[u8]| keccak256(x)
+            ),
+            public_values.1
+        );
+    }
+
+    #[test]
+    fn test_run_auction_with_hash_function_trait_object() {
+        let prover_address: Address = Address::random();
+        let (bid_submissions, offer_submissions, bid_reveals, _) = random_auction_inputs(42);
+        let offer_reveals: OfferReveals = OfferReveals::new();
+        let auction_parameters: AuctionParameters = random_auction_parameters();
+
+        // The hasher is picked at runtime, and matches a plain function
+        let hash_function: &dyn HashFunction = &precompiles::Keccak256;
+        assert_eq!(
+            run_auction(
+                hash_function,
+                &prover_address,
+                &bid_submissions,
+                &offer_submissions,
+                &bid_reveals,
+                &offer_reveals,
+                &auction_parameters,
+            ),
+            run_auction(
+                &precompiles::sp1_keccak256,
+                &prover_address,
+                &bid_submissions,
+                &offer_submissions,
+                &bid_reveals,
+                &offer_reveals,
+                &auction_parameters,
+            )
+        );
+    }
+
+    #[test]
+    fn test_run_auction_invalid_auction_parameters() {
+        let (bid_submissions, offer_submissions, bid_reveals, offer_reveals) =
+            random_auction_inputs(2);
+        let mut auction_parameters: AuctionParameters = random_auction_parameters();
+        auction_parameters.dayCount = U256::ZERO;
+
+        let error: AuctionError = run_auction(
+            &|x: &[u8]| keccak256(x),
+            &Address::random(),
+            &bid_submissions,
+            &offer_submissions,
+            &bid_reveals,
+            &offer_reveals,
+            &auction_parameters,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error,
+            AuctionError::InvalidAuctionParameters(ParamError::InvalidDayCount)
+        );
+        assert!(error
+            .to_string()
+            .starts_with("invalid auction parameters: day count must be between 1 and"));
+    }
+
+    #[test]
+    fn test_run_auction_too_many_orders() {
+        let (bid_submissions, offer_submissions, _, _) = random_auction_inputs(2);
+        let too_many_bids: BidSubmissions =
+            (0..=MAX_ORDERS).map(|_| random_bid_submission()).collect();
+        let too_many_offers: OfferSubmissions = (0..=MAX_ORDERS)
+            .map(|_| random_offer_submission())
+            .collect();
+
+        assert_eq!(
+            run_auction(
+                &|x: &[u8]| keccak256(x),
+                &Address::random(),
+                &too_many_bids,
+                &offer_submissions,
+                &BidReveals::new(),
+                &OfferReveals::new(),
+                &random_auction_parameters(),
+            ),
+            Err(AuctionError::TooManyBids)
+        );
+        assert_eq!(
+            run_auction(
+                &|x: &[u8]| keccak256(x),
+                &Address::random(),
+                &bid_submissions,
+                &too_many_offers,
+                &BidReveals::new(),
+                &OfferReveals::new(),
+                &random_auction_parameters(),
+            ),
+            Err(AuctionError::TooManyOffers)
+        );
+    }
+
+    #[test]
+    fn test_run_auction_arithmetic_overflow() {
+        let mut auction_parameters: AuctionParameters = random_auction_parameters();
+        auction_parameters.purchasePrice = U256::from(1);
+        auction_parameters.collateralPrice = U256::from(1);
+
+        // The offered amounts add up past `U256::MAX` while both offers cross the bid
+        let inputs: AuctionInputs = AuctionBuilder::new(auction_parameters)
+            .add_bid(
+                bps_to_internal(&U256::from(1_000)),
+                U256::from(1_000),
+                U256::from(2_000),
+            )
+            .add_offer(bps_to_internal(&U256::from(500)), U256::MAX)
+            .add_offer(bps_to_internal(&U256::from(500)), U256::from(1_000))
+            .build(&|x: &[u8]| keccak256(x));
+
+        assert_eq!(
+            run_auction(
+                &|x: &[u8]| keccak256(x),
+                &Address::random(),
+                &inputs.bid_submissions,
+                &inputs.offer_submissions,
+                &inputs.bid_reveals,
+                &inputs.offer_reveals,
+                &inputs.auction_parameters,
+            ),
+            Err(AuctionError::ArithmeticOverflow)
+        );
+    }
+
+    #[test]
+    fn test_public_values_struct_clearing_price() {
+        let mut auction_parameters: AuctionParameters = random_auction_parameters();
+        auction_parameters.purchasePrice = U256::from(1);
+        auction_parameters.collateralPrice = U256::from(1);
+        let inputs: AuctionInputs = AuctionBuilder::new(auction_parameters)
+            .add_bid(
+                bps_to_internal(&U256::from(1_000)),
+                U256::from(1_000),
+                U256::from(2_000),
+            )
+            .add_bid(
+                bps_to_internal(&U256::from(800)),
+                U256::from(1_000),
+                U256::from(2_000),
+            )
+            .add_bid(
+                bps_to_internal(&U256::from(100)),
+                U256::from(1_000),
+                U256::from(2_000),
+            )
+            .add_offer(bps_to_internal(&U256::from(500)), U256::from(1_000))
+            .add_offer(bps_to_internal(&U256::from(600)), U256::from(1_000))
+            .add_offer(bps_to_internal(&U256::from(1_500)), U256::from(1_000))
+            .build(&|x: &[u8]| keccak256(x));
+        let prover_address: Address = Address::random();
+
+        let public_values: PublicValuesStruct = PublicValuesStruct::new(
+            prover_address,
+            run_auction(
+                &|x: &[u8]| keccak256(x),
+                &prover_address,
+                &inputs.bid_submissions,
+                &inputs.offer_submissions,
+                &inputs.bid_reveals,
+                &inputs.offer_reveals,
+                &inputs.auction_parameters,
+            )
+            .unwrap(),
+        );
+
+        // Clear the same orders by hand
+        let mut bids: Bids = Bids::new();
+        let acc_bids_hash: B256 =
+            inputs
+                .bid_submissions
+                .hash_chain(&|x: &[u8]| keccak256(x), B256::ZERO, &mut bids);
+        inputs
+            .bid_reveals
+            .hash_chain(&|x: &[u8]| keccak256(x), acc_bids_hash, &mut bids);
+        let mut offers: Offers = Offers::new();
+        let acc_offers_hash: B256 =
+            inputs
+                .offer_submissions
+                .hash_chain(&|x: &[u8]| keccak256(x), B256::ZERO, &mut offers);
+        inputs
+            .offer_reveals
+            .hash_chain(&|x: &[u8]| keccak256(x), acc_offers_hash, &mut offers);
+        let mut validated_bids: ValidatedBids =
+            bids.into_validated_orders(&inputs.auction_parameters, &mut BidderAllocations::new());
+        let mut validated_offers: ValidatedOffers = offers
+            .into_validated_orders(&inputs.auction_parameters, &mut OfferorAllocations::new());
+        validated_bids.sort_orders();
+        validated_offers.sort_orders();
+        let (clearing_price, max_assignable) =
+            compute_clearing_price(&validated_bids, &validated_offers);
+
+        // The two most competitive bids and offers cross, and nothing caps what bids take
+        assert_eq!(public_values.clearingPrice, clearing_price);
+        assert_eq!(public_values.clearedVolume, max_assignable);
+        assert_eq!(public_values.clearedVolume, U256::from(2_000));
+    }
+
+    #[test]
+    fn test_run_auction_submission_order_invariant() {
+        let mut auction_parameters: AuctionParameters = random_auction_parameters();
+        auction_parameters.purchasePrice = U256::from(1);
+        auction_parameters.collateralPrice = U256::from(1);
+
+        // Few distinct prices and amounts, so that sorting has plenty of ties to break
+        let mut auction_builder: AuctionBuilder = AuctionBuilder::new(auction_parameters);
+        for i in 0..12u64 {
+            auction_builder = auction_builder
+                .add_bid(
+                    bps_to_internal(&U256::from(800 + 100 * (i % 3))),
+                    U256::from(1_000 * (1 + i % 2)),
+                    U256::from(4_000),
+                )
+                .add_offer(
+                    bps_to_internal(&U256::from(500 + 100 * (i % 3))),
+                    U256::from(1_500 * (1 + i % 2)),
+                );
+        }
+        let mut inputs: AuctionInputs = auction_builder.build(&|x: &[u8]| keccak256(x));
+        let run = |inputs: &AuctionInputs| {
+            run_auction(
+                &|x: &[u8]| keccak256(x),
+                &Address::ZERO,
+                &inputs.bid_submissions,
+                &inputs.offer_submissions,
+                &inputs.bid_reveals,
+                &inputs.offer_reveals,
+                &inputs.auction_parameters,
+            )
+            .unwrap()
+        };
+        let (_, _, _, auction_result_root, clearing_outcome, _) = run(&inputs);
+        assert!(matches!(clearing_outcome, ClearingOutcome::Cleared { .. }));
+
+        // Shuffling the submissions and reveals changes the hash chains, but not the results
+        let mut rng = rand::thread_rng();
+        for _ in 0..10 {
+            inputs.bid_submissions.shuffle(&mut rng);
+            inputs.offer_submissions.shuffle(&mut rng);
+            inputs.bid_reveals.shuffle(&mut rng);
+            inputs.offer_reveals.shuffle(&mut rng);
+
+            let (_, _, _, shuffled_auction_result_root, shuffled_clearing_outcome, _) =
+                run(&inputs);
+            assert_eq!(shuffled_auction_result_root, auction_result_root);
+            assert_eq!(shuffled_clearing_outcome, clearing_outcome);
+        }
+    }
+
+    #[test]
+    fn test_run_auction_no_clear_order_invariant() {
+        let mut auction_parameters: AuctionParameters = random_auction_parameters();
+        auction_parameters.purchasePrice = U256::from(1);
+        auction_parameters.collateralPrice = U256::from(1);
+
+        // Every bid is below every offer, so the market does not clear and all orders are refunded
+        let mut auction_builder: AuctionBuilder = AuctionBuilder::new(auction_parameters);
+        for i in 0..12u64 {
+            auction_builder = auction_builder
+                .add_bid(
+                    bps_to_internal(&U256::from(100 + 100 * (i % 3))),
+                    U256::from(1_000 * (1 + i % 2)),
+                    U256::from(4_000),
+                )
+                .add_offer(
+                    bps_to_internal(&U256::from(1_000 + 100 * (i % 3))),
+                    U256::from(1_500 * (1 + i % 2)),
+                );
+        }
+        let mut inputs: AuctionInputs = auction_builder.build(&|x: &[u8]| keccak256(x));
+        // Some orders are never revealed, and get refunded as invalid orders
+        inputs.bid_reveals.truncate(9);
+        inputs.offer_reveals.truncate(9);
+        let run = |inputs: &AuctionInputs| {
+            run_auction(
+                &|x: &[u8]| keccak256(x),
+                &Address::ZERO,
+                &inputs.bid_submissions,
+                &inputs.offer_submissions,
+                &inputs.bid_reveals,
+                &inputs.offer_reveals,
+                &inputs.auction_parameters,
+            )
+            .unwrap()
+        };
+        let (_, _, _, auction_result_root, clearing_outcome, _) = run(&inputs);
+        assert_eq!(clearing_outcome, ClearingOutcome::NoClear);
+
+        // Shuffling the submissions and reveals leaves the refund leaves in the same order
+        let mut rng = rand::thread_rng();
+        for _ in 0..10 {
+            inputs.bid_submissions.shuffle(&mut rng);
+            inputs.offer_submissions.shuffle(&mut rng);
+            inputs.bid_reveals.shuffle(&mut rng);
+            inputs.offer_reveals.shuffle(&mut rng);
+
+            let (_, _, _, shuffled_auction_result_root, shuffled_clearing_outcome, _) =
+                run(&inputs);
+            assert_eq!(shuffled_auction_result_root, auction_result_root);
+            assert_eq!(shuffled_clearing_outcome, ClearingOutcome::NoClear);
+        }
+    }
+
+    #[test]
+    fn test_public_values_struct_display() {
+        let public_values: PublicValuesStruct = PublicValuesStruct {
+            proverAddress: Address::random(),
+            accBidsHash: B256::random(),
+            accOffersHash: B256::random(),
+            auctionParametersHash: B256::random(),
+            constantsCommitment: B256::random(),
+            auctionResultRoot: B256::random(),
+            clearingPrice: U256::from(rand::random::<u64>()),
+            clearedVolume: U256::from(rand::random::<u64>()),
+        };
+        let summary: String = public_values.to_string();
+
+        // Every field is labeled, one per line
+        assert_eq!(summary.lines().count(), 8);
+        for (label, value) in [
+            ("proverAddress", public_values.proverAddress.to_string()),
+            ("accBidsHash", public_values.accBidsHash.to_string()),
+            ("accOffersHash", public_values.accOffersHash.to_string()),
+            (
+                "auctionParametersHash",
+                public_values.auctionParametersHash.to_string(),
+            ),
+            (
+                "constantsCommitment",
+                public_values.constantsCommitment.to_string(),
+            ),
+            (
+                "auctionResultRoot",
+                public_values.auctionResultRoot.to_string(),
+            ),
+            ("clearingPrice", public_values.clearingPrice.to_string()),
+            ("clearedVolume", public_values.clearedVolume.to_string()),
+        ] {
+            assert!(summary.contains(&format!("{}: {}", label, value)));
+        }
+    }
+
+    // HELPER FUNCTIONS
+    /// Creates `num_orders` random bid and offer submissions alongside their matching reveals.
+    pub fn random_auction_inputs(
+        num_orders: usize,
+    ) -> (BidSubmissions, OfferSubmissions, BidReveals, OfferReveals) {
+        let mut bid_reveals: BidReveals = BidReveals::new();
+        let bid_submissions: BidSubmissions = (0..num_orders)
+            .map(|_| {
+                let price: U256 = U256::from(rand::random::<u32>() % MAX_BID_PRICE);
+                let nonce: U256 = U256::from(rand::random::<u128>());
+                let bid_submission = valid_random_bid_submission(&price, &nonce);
+                bid_reveals.push(BidReveal {
+                    orderId: get_key(&bid_submission.bidder, &bid_submission.id).into(),
+                    price,
+                    nonce,
+                });
+                bid_submission
+            })
+            .collect();
+
+        let mut offer_reveals: OfferReveals = OfferReveals::new();
+        let offer_submissions: OfferSubmissions = (0..num_orders)
+            .map(|_| {
+                let price: U256 = U256::from(rand::random::<u32>() % MAX_OFFER_PRICE);
+                let nonce: U256 = U256::from(rand::random::<u128>());
+                let offer_submission = valid_random_offer_submission(&price, &nonce);
+                offer_reveals.push(OfferReveal {
+                    orderId: get_key(&offer_submission.offeror, &offer_submission.id).into(),
+                    price,
+                    nonce,
+                });
+                offer_submission
+            })
+            .collect();
+
+        (
+            bid_submissions,
+            offer_submissions,
+            bid_reveals,
+            offer_reveals,
+        )
+    }
+}
+
+/// Runs an auction end to end with the `std` feature disabled, through `alloc` and `core` paths only. Only built by
+/// `cargo test --no-default-features`: the test harness itself still links `std`, so this checks the behavior of the
+/// alloc-only build rather than that it is `no_std` compatible, which building the zkVM program does.
+#[cfg(all(test, not(feature = "std")))]
+mod alloc_only_tests {
+    use super::*;
+    use crate::{
+        auction_builder::{AuctionBuilder, AuctionInputs},
+        auction_parameters::tests::random_auction_parameters,
+        exit_tree::ExitTreeBuilder,
+        utils::bps_to_internal,
+    };
+    use alloc::{
+        format,
+        string::{String, ToString},
+        vec::Vec,
+    };
+    use alloy_primitives::{keccak256, U256};
+
+    #[test]
+    fn test_run_auction_alloc_only() {
+        let mut auction_parameters: AuctionParameters = random_auction_parameters();
+        auction_parameters.purchasePrice = U256::from(1);
+        auction_parameters.collateralPrice = U256::from(1);
+        let inputs: AuctionInputs = AuctionBuilder::new(auction_parameters)
+            .add_bid(
+                bps_to_internal(&U256::from(1_000)),
+                U256::from(1_000),
+                U256::from(2_000),
+            )
+            .add_offer(bps_to_internal(&U256::from(500)), U256::from(1_000))
+            .build(&|x: &[u8]| keccak256(x));
+        let prover_address: Address = Address::random();
+
+        let (auction_output, exit_leaves) = run_auction_with_results(
+            &|x: &[u8]| keccak256(x),
+            &prover_address,
+            &inputs.bid_submissions,
+            &inputs.offer_submissions,
+            &inputs.bid_reveals,
+            &inputs.offer_reveals,
+            &inputs.auction_parameters,
+        )
+        .unwrap();
+        assert_eq!(
+            run_auction(
+                &|x: &[u8]| keccak256(x),
+                &prover_address,
+                &inputs.bid_submissions,
+                &inputs.offer_submissions,
+                &inputs.bid_reveals,
+                &inputs.offer_reveals,
+                &inputs.auction_parameters,
+            ),
+            Ok(auction_output)
+        );
+        assert!(matches!(auction_output.4, ClearingOutcome::Cleared { .. }));
+
+        // The exit leaves hash to the auction result root when pushed one at a time
+        let mut exit_tree: ExitTreeBuilder<_> = ExitTreeBuilder::new(&|x: &[u8]| keccak256(x));
+        for exit_leaf in exit_leaves.iter() {
+            exit_tree.push(exit_leaf);
+        }
+        assert_eq!(exit_tree.size(), exit_leaves.len());
+        assert_eq!(exit_tree.root(), auction_output.3);
+
+        // Values are formatted through `core::fmt`
+        let lines: Vec<String> = PublicValuesStruct::new(prover_address, auction_output)
+            .to_string()
+            .lines()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(lines.len(), 8);
+        assert_eq!(
+            AuctionError::TooManyBids.to_string(),
+            format!(
+                "bid submissions exceed the maximum of {} orders",
+                MAX_ORDERS
+            )
+        );
+    }
 }
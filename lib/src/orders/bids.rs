@@ -1,15 +1,25 @@
-use super::{ChainableSubmissions, Order, PlacedOrders, ValidatedOrders};
+use super::{
+    remove_orders_by_address, ChainableSubmissions, Order, OrderInvalidReason, OrderReveals,
+    OrderSubmissions, PlacedOrders, RevealResult, ValidatedOrders,
+};
 use crate::{
     allocations::bidder_allocations::BidderAllocation,
     auction_parameters::AuctionParameters,
-    constants::{BPS, INITIAL_COLLATERAL_RATIO, MAX_BID_PRICE},
+    constants::{BPS, INITIAL_COLLATERAL_RATIO, MAX_BID_PRICE, MAX_ORDERS, MIN_ORDER_AMOUNT},
     exit_tree::ExitLeafTokenWithdrawal,
-    utils::{add_to_hash_chain, get_key, get_price_hash},
+    precompiles::HashFunction,
+    utils::{
+        add_to_hash_chain, bps_to_internal, fold_hash_chain, get_key, get_price_hash,
+        order_key_from_u256, verify_price_reveal,
+    },
+};
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    vec::Vec,
 };
 use alloy_primitives::{aliases::U96, Address, B256, U256};
 use alloy_sol_types::sol;
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
 
 /// Represents a bid to borrow an amount of money for a specific interest rate backed by collateral.
 #[derive(PartialEq, Eq, Debug, Clone)]
@@ -46,8 +56,8 @@ impl Order for Bid {
             bid_price_revealed: U256::ZERO,
             amount: bid_submission.amount,
             collateral_amount: bid_submission.collateralAmount,
-            is_rollover: false,
-            rollover_pair_off_term_repo_servicer: Address::ZERO,
+            is_rollover: bid_submission.isRollover,
+            rollover_pair_off_term_repo_servicer: bid_submission.rolloverPairOffTermRepoServicer,
             is_revealed: false,
         }
     }
@@ -56,36 +66,59 @@ impl Order for Bid {
         self.amount = bid_submission.amount;
         self.collateral_amount = bid_submission.collateralAmount;
         self.bid_price_hash = bid_submission.bidPriceHash;
+        self.is_rollover = bid_submission.isRollover;
+        self.rollover_pair_off_term_repo_servicer = bid_submission.rolloverPairOffTermRepoServicer;
     }
 
-    fn update_from_order_reveal<F: Fn(&[u8]) -> B256>(
+    fn update_from_order_reveal<F: HashFunction + ?Sized>(
         &mut self,
         hash_function: &F,
         bid_reveal: &BidReveal,
     ) {
-        if get_price_hash(hash_function, &bid_reveal.price, &bid_reveal.nonce)
-            == self.bid_price_hash
-            && bid_reveal.price <= U256::from(MAX_BID_PRICE)
+        // Once revealed, a later matching reveal cannot overwrite the price
+        if !self.is_revealed
+            && verify_price_reveal(
+                hash_function,
+                &bid_reveal.price,
+                &bid_reveal.nonce,
+                &self.bid_price_hash,
+                MAX_BID_PRICE,
+            )
         {
             self.bid_price_revealed = bid_reveal.price;
             self.is_revealed = true;
         }
     }
 
-    fn is_valid(&self, tokens: &AuctionParameters) -> bool {
+    fn validate(&self, tokens: &AuctionParameters) -> Result<(), OrderInvalidReason> {
+        if !self.is_revealed {
+            return Err(OrderInvalidReason::NotRevealed);
+        }
+
+        if self.amount < U256::from(MIN_ORDER_AMOUNT) {
+            return Err(OrderInvalidReason::BelowMinimumAmount);
+        }
+
         // Calculate the value of collateral and purchase amount
         // If one operation overflows, the bid is invalid
-        let (collateral_value, of1) = self
+        let minimum_collateral_side: U256 = self
+            .amount
+            .checked_mul(tokens.purchasePrice)
+            .and_then(|purchase_value| {
+                purchase_value.checked_mul(U256::from(INITIAL_COLLATERAL_RATIO))
+            })
+            .ok_or(OrderInvalidReason::AmountOverflow)?;
+        let collateral_side: U256 = self
             .collateral_amount
-            .overflowing_mul(tokens.collateralPrice);
-        let (purchase_value, of2) = self.amount.overflowing_mul(tokens.purchasePrice);
-        let (minimum_collateral_side, of3) =
-            purchase_value.overflowing_mul(U256::from(INITIAL_COLLATERAL_RATIO));
-        let (collateral_side, of4) = collateral_value.overflowing_mul(U256::from(BPS));
+            .checked_mul(tokens.collateralPrice)
+            .and_then(|collateral_value| collateral_value.checked_mul(U256::from(BPS)))
+            .ok_or(OrderInvalidReason::CollateralOverflow)?;
+
+        if collateral_side < minimum_collateral_side {
+            return Err(OrderInvalidReason::Undercollateralized);
+        }
 
-        self.is_revealed
-            && collateral_side >= minimum_collateral_side
-            && (!of1 && !of2 && !of3 && !of4)
+        Ok(())
     }
 
     fn to_exit_leaf(&self, tokens: &AuctionParameters) -> ExitLeafTokenWithdrawal {
@@ -131,6 +164,38 @@ impl PlacedOrders for Bids {
                 .or_insert_with(|| Bid::from_order_submission(order_submission));
         }
     }
+
+    fn cancel_by_address(&mut self, address: &Address) -> usize {
+        remove_orders_by_address(self, address)
+    }
+
+    fn total_amount(&self) -> U256 {
+        self.values().fold(U256::ZERO, |acc: U256, bid: &Bid| {
+            acc.saturating_add(bid.amount)
+        })
+    }
+
+    fn count_revealed(&self) -> usize {
+        self.values().filter(|bid: &&Bid| bid.is_revealed).count()
+    }
+}
+
+/// Trait for orders collections that lock collateral.
+pub trait CollateralizedOrders {
+    /// Returns the sum of the collateral locked by all orders, saturating on overflow.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The orders collection.
+    fn total_collateral(&self) -> U256;
+}
+
+impl CollateralizedOrders for Bids {
+    fn total_collateral(&self) -> U256 {
+        self.values().fold(U256::ZERO, |acc: U256, bid: &Bid| {
+            acc.saturating_add(bid.collateral_amount)
+        })
+    }
 }
 
 /// A collection of all validated bids.
@@ -141,8 +206,16 @@ impl ValidatedOrders for ValidatedBids {
     type Allocation = BidderAllocation;
 
     /// Inversely sorts bids from least competitive to most competitive, such that the last item in the list is the most competitive bid
+    ///
+    /// Bids are ordered by ascending revealed price. Bids at the same price are ordered by descending amount, and then
+    /// by ascending key, so that the resulting order is total and does not depend on the input order.
     fn sort_orders(&mut self) {
-        self.sort_by(|a: &Bid, b: &Bid| a.bid_price_revealed.cmp(&b.bid_price_revealed));
+        self.sort_by(|a: &Bid, b: &Bid| {
+            a.bid_price_revealed
+                .cmp(&b.bid_price_revealed)
+                .then_with(|| b.amount.cmp(&a.amount))
+                .then_with(|| get_key(&a.bidder, &a.id).cmp(&get_key(&b.bidder, &b.id)))
+        });
     }
 }
 
@@ -160,6 +233,10 @@ sol! {
         uint256 amount;
         /// The amount of collateral tokens that were locked onchain
         uint256 collateralAmount;
+        /// Whether the bid rolls over an existing loan
+        bool isRollover;
+        /// The term repo servicer of the loan being rolled over, zero if not a rollover
+        address rolloverPairOffTermRepoServicer;
     }
 }
 
@@ -168,27 +245,76 @@ pub type BidSubmissions = Vec<BidSubmission>;
 
 impl ChainableSubmissions for BidSubmissions {
     type T = Bid;
+    type Item = BidSubmission;
     /// # Behavior
     ///
     /// - If a bid with the same key already exists, it updates the amount, collateral amount, and bid price hash.
     /// - If no bid exists for the key, it creates a new `Bid` instance with the provided details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if more than `MAX_ORDERS` bids would be placed at once.
     fn hash_chain<F>(&self, hash_function: &F, start_value: B256, bids: &mut Bids) -> B256
     where
-        F: Fn(&[u8]) -> B256,
+        F: HashFunction + ?Sized,
     {
-        self.iter()
+        Self::hash_chain_iter(self.iter(), hash_function, start_value, bids)
+    }
+
+    fn hash_chain_iter<'a, F, I>(
+        items: I,
+        hash_function: &F,
+        start_value: B256,
+        bids: &mut Bids,
+    ) -> B256
+    where
+        F: HashFunction + ?Sized,
+        I: IntoIterator<Item = &'a BidSubmission>,
+    {
+        items
+            .into_iter()
             .fold(start_value, |acc: B256, bid_submission: &BidSubmission| {
                 bids.save_or_update_order(bid_submission);
+                assert!(
+                    bids.len() <= MAX_ORDERS as usize,
+                    "Bid submissions exceed the maximum of {} orders",
+                    MAX_ORDERS
+                );
                 add_to_hash_chain(hash_function, bid_submission, &acc)
             })
     }
 }
 
+impl OrderSubmissions for BidSubmissions {
+    fn keys(&self) -> Vec<B256> {
+        self.iter()
+            .map(|bid_submission: &BidSubmission| {
+                get_key(&bid_submission.bidder, &bid_submission.id)
+            })
+            .collect()
+    }
+
+    fn peak_order_count(&self) -> usize {
+        let mut placed: BTreeSet<B256> = BTreeSet::new();
+        let mut peak: usize = 0;
+        for (key, bid_submission) in self.keys().into_iter().zip(self.iter()) {
+            if bid_submission.collateralAmount.is_zero() {
+                placed.remove(&key);
+            } else {
+                placed.insert(key);
+                peak = peak.max(placed.len());
+            }
+        }
+
+        peak
+    }
+}
+
 sol! {
     /// A `BidReveal` represents the bid reveal process that was carried out onchain
     #[derive(Serialize, Deserialize)]
     struct BidReveal {
-        /// The ID of the bid that was revealed
+        /// The key of the bid that was revealed, as given by `get_key(bidder, id)`
         uint256 orderId;
         /// The price of the bid that was revealed
         uint256 price;
@@ -197,11 +323,23 @@ sol! {
     }
 }
 
+impl BidReveal {
+    /// Returns the key of the revealed bid within the `Bids` mapping.
+    ///
+    /// The `orderId` is expected to be the concatenation of the bidder's address and the bid ID, as
+    /// computed by `get_key`. A reveal whose `orderId` does not follow this layout will not match any
+    /// stored bid.
+    pub fn key(&self) -> B256 {
+        self.orderId.into()
+    }
+}
+
 /// Represents the history of all bid reveals made onchain.
 pub type BidReveals = Vec<BidReveal>;
 
 impl ChainableSubmissions for BidReveals {
     type T = Bid;
+    type Item = BidReveal;
     /// # Behavior
     ///
     /// - If a bid with the matching `orderId` is found and the calculated price hash
@@ -209,26 +347,126 @@ impl ChainableSubmissions for BidReveals {
     ///   - Updates the `bid_price_revealed` with the revealed price.
     ///   - Sets `is_revealed` to `true`.
     /// - If no matching bid is found or the price hash doesn't match, no changes are made.
+    ///
+    /// Reveals are matched against stored bids through `order_key_from_u256`, so an `orderId` that is not
+    /// `get_key(bidder, id)` leaves every bid untouched while still being added to the hash chain.
     fn hash_chain<F>(&self, hash_function: &F, start_value: B256, bids: &mut Bids) -> B256
     where
-        F: Fn(&[u8]) -> B256,
+        F: HashFunction + ?Sized,
     {
-        self.iter()
+        Self::hash_chain_iter(self.iter(), hash_function, start_value, bids)
+    }
+
+    fn hash_chain_iter<'a, F, I>(
+        items: I,
+        hash_function: &F,
+        start_value: B256,
+        bids: &mut Bids,
+    ) -> B256
+    where
+        F: HashFunction + ?Sized,
+        I: IntoIterator<Item = &'a BidReveal>,
+    {
+        items
+            .into_iter()
             .fold(start_value, |acc: B256, item: &BidReveal| {
                 // Set bid price if bid exists and was revealed properly
-                if let Some(bid) = bids.get_mut::<B256>(&item.orderId.into()) {
-                    bid.update_from_order_reveal(hash_function, item);
+                if let Ok(key) = order_key_from_u256(&item.orderId) {
+                    if let Some(bid) = bids.get_mut(&key) {
+                        bid.update_from_order_reveal(hash_function, item);
+                    }
                 }
                 add_to_hash_chain(hash_function, item, &acc)
             })
     }
 }
 
+impl OrderReveals for BidReveals {
+    fn keys(&self) -> Vec<B256> {
+        self.iter().map(BidReveal::key).collect()
+    }
+}
+
+/// Recomputes the bids hash chain over the bid submissions and then the bid reveals, the same way
+/// `run_auction` does, but without building the `Bids` mapping.
+///
+/// Meant for verifiers checking that the `accBidsHash` committed by a proof corresponds to a given history of
+/// bids.
+///
+/// # Arguments
+///
+/// * `bid_submissions` - The history of all bid submissions made onchain.
+/// * `bid_reveals` - The history of all bid reveals made onchain.
+/// * `start_value` - The initial 32-byte value to start the hash chain.
+/// * `hash_function` - A function that computes a 32-byte hash from a byte slice.
+///
+/// # Returns
+///
+/// The final `B256` hash of the bids hash chain.
+pub fn recompute_bid_hash_chain<F: HashFunction + ?Sized>(
+    bid_submissions: &BidSubmissions,
+    bid_reveals: &BidReveals,
+    start_value: B256,
+    hash_function: &F,
+) -> B256 {
+    let acc_bids_hash: B256 = fold_hash_chain(hash_function, bid_submissions, start_value);
+    fold_hash_chain(hash_function, bid_reveals, acc_bids_hash)
+}
+
+/// Checks every bid reveal against the bid it points to, reporting why each one would or would not reveal its price.
+///
+/// A read-only diagnostic meant for telling each bidder whether their reveal matched their commitment: the bids
+/// are left untouched and nothing is added to the hash chain. Every reveal is checked against the price hash
+/// submitted for its bid, whether or not an earlier reveal already revealed it.
+///
+/// # Arguments
+///
+/// * `submissions` - The placed bids, keyed by `get_key(bidder, id)`.
+/// * `reveals` - The history of all bid reveals made onchain.
+/// * `hash_function` - A function that computes a 32-byte hash from a byte slice.
+///
+/// # Returns
+///
+/// * `Vec<(B256, RevealResult)>` - The key every reveal points to alongside its result, in reveal order.
+pub fn verify_reveals<F: HashFunction + ?Sized>(
+    submissions: &Bids,
+    reveals: &BidReveals,
+    hash_function: &F,
+) -> Vec<(B256, RevealResult)> {
+    let max_price: U256 = bps_to_internal(&U256::from(MAX_BID_PRICE));
+    reveals
+        .iter()
+        .map(|reveal: &BidReveal| {
+            let key: B256 = reveal.key();
+            let bid: Option<&Bid> = order_key_from_u256(&reveal.orderId)
+                .ok()
+                .and_then(|key| submissions.get(&key));
+            let result: RevealResult = match bid {
+                None => RevealResult::NoSuchOrder,
+                Some(bid)
+                    if get_price_hash(hash_function, &reveal.price, &reveal.nonce)
+                        != bid.bid_price_hash =>
+                {
+                    RevealResult::HashMismatch
+                }
+                Some(_) if reveal.price > max_price => RevealResult::PriceOutOfBounds,
+                Some(_) => RevealResult::Matched,
+            };
+            (key, result)
+        })
+        .collect()
+}
+
 #[cfg(test)]
 pub mod tests {
     use crate::{
-        allocations::AuctionResults, auction_parameters::tests::random_auction_parameters,
-        utils::test::calculate_expected_hash_chain_output,
+        allocations::AuctionResults,
+        auction_parameters::tests::random_auction_parameters,
+        orders::{OrderLookup, RevealStats, SubmissionError},
+        utils::{
+            bps_to_internal, get_price_hash, internal_to_bps,
+            test::calculate_expected_hash_chain_output,
+        },
     };
 
     use super::*;
@@ -244,6 +482,16 @@ pub mod tests {
         assert_eq!(bid.bid_price_hash, bid_submission.bidPriceHash);
         assert_eq!(bid.amount, bid_submission.amount);
         assert_eq!(bid.collateral_amount, bid_submission.collateralAmount);
+        assert!(!bid.is_rollover);
+        assert_eq!(bid.rollover_pair_off_term_repo_servicer, Address::ZERO);
+
+        let rollover_bid_submission: BidSubmission = random_rollover_bid_submission();
+        let rollover_bid: Bid = Bid::from_order_submission(&rollover_bid_submission);
+        assert!(rollover_bid.is_rollover);
+        assert_eq!(
+            rollover_bid.rollover_pair_off_term_repo_servicer,
+            rollover_bid_submission.rolloverPairOffTermRepoServicer
+        );
     }
 
     #[test]
@@ -257,6 +505,14 @@ pub mod tests {
         assert_eq!(bid.amount, new_order_submission.amount);
         assert_eq!(bid.collateral_amount, new_order_submission.collateralAmount);
         assert_eq!(bid.bid_price_hash, new_order_submission.bidPriceHash);
+
+        let new_rollover_submission: BidSubmission = random_rollover_bid_submission();
+        bid.update_from_order_submission(&new_rollover_submission);
+        assert!(bid.is_rollover);
+        assert_eq!(
+            bid.rollover_pair_off_term_repo_servicer,
+            new_rollover_submission.rolloverPairOffTermRepoServicer
+        );
     }
 
     #[test]
@@ -267,7 +523,7 @@ pub mod tests {
         let bid_submission: BidSubmission = valid_random_bid_submission(&price, &nonce);
         let mut bid: Bid = Bid::from_order_submission(&bid_submission);
         bid.update_from_order_reveal(
-            &|x| keccak256(x),
+            &|x: &[u8]| keccak256(x),
             &BidReveal {
                 orderId: get_key(&bid_submission.bidder, &bid_submission.id).into(),
                 price,
@@ -291,7 +547,7 @@ pub mod tests {
         assert!(!bid.is_revealed);
 
         // Valid reveal with out of bounds price
-        let price: U256 = U256::from(MAX_BID_PRICE + 1);
+        let price: U256 = bps_to_internal(&U256::from(MAX_BID_PRICE)) + U256::from(1);
         let nonce: U256 = U256::from(rand::random::<u128>());
         let bid_submission: BidSubmission = valid_random_bid_submission(&price, &nonce);
         let mut bid: Bid = Bid::from_order_submission(&bid_submission);
@@ -307,6 +563,66 @@ pub mod tests {
         assert!(!bid.is_revealed);
     }
 
+    #[test]
+    fn test_bid_update_from_order_reveal_price_scale() {
+        let max_price: U256 = bps_to_internal(&U256::from(MAX_BID_PRICE));
+        let reveal = |price: U256| -> Bid {
+            let nonce: U256 = U256::from(rand::random::<u128>());
+            let bid_submission: BidSubmission = valid_random_bid_submission(&price, &nonce);
+            let mut bid: Bid = Bid::from_order_submission(&bid_submission);
+            bid.update_from_order_reveal(
+                &|x: &[u8]| keccak256(x),
+                &BidReveal {
+                    orderId: get_key(&bid_submission.bidder, &bid_submission.id).into(),
+                    price,
+                    nonce,
+                },
+            );
+            bid
+        };
+
+        // A price with 9 decimal places is compared against the maximum price in the same scale
+        for price in [max_price, max_price - U256::from(1), U256::from(1)] {
+            let bid: Bid = reveal(price);
+            assert!(bid.is_revealed);
+            assert_eq!(bid.bid_price_revealed, price);
+        }
+        assert_eq!(internal_to_bps(&max_price), U256::from(MAX_BID_PRICE));
+        assert_eq!(
+            internal_to_bps(&(max_price - U256::from(1))),
+            U256::from(MAX_BID_PRICE - 1)
+        );
+
+        // A maximum price that was scaled twice is out of bounds
+        let bid: Bid = reveal(bps_to_internal(&max_price));
+        assert!(!bid.is_revealed);
+        assert_eq!(bid.bid_price_revealed, U256::ZERO);
+    }
+
+    #[test]
+    fn test_bid_update_from_order_reveal_first_reveal_wins() {
+        // A hash function under which every price and nonce collide, so that any reveal matches
+        let colliding_hash_function = |_: &[u8]| B256::ZERO;
+        let mut bid: Bid = Bid::from_order_submission(&random_bid_submission());
+        bid.bid_price_hash = B256::ZERO;
+
+        let first_price: U256 = U256::from(rand::random::<u32>() % MAX_BID_PRICE);
+        let second_price: U256 = first_price + U256::from(1);
+        for price in [first_price, second_price] {
+            bid.update_from_order_reveal(
+                &colliding_hash_function,
+                &BidReveal {
+                    orderId: get_key(&bid.bidder, &bid.id).into(),
+                    price,
+                    nonce: U256::from(rand::random::<u128>()),
+                },
+            );
+        }
+
+        assert_eq!(bid.bid_price_revealed, first_price);
+        assert!(bid.is_revealed);
+    }
+
     #[test]
     fn test_bid_is_valid() {
         let tokens: AuctionParameters = random_auction_parameters();
@@ -324,6 +640,50 @@ pub mod tests {
         assert!(!undercollateralized_bid.is_valid(&tokens));
     }
 
+    #[test]
+    fn test_bid_validate() {
+        let tokens: AuctionParameters = random_auction_parameters();
+
+        let revealed_bid: Bid =
+            random_collateralized_revealed_bid(&tokens.purchasePrice, &tokens.collateralPrice);
+        assert_eq!(revealed_bid.validate(&tokens), Ok(()));
+
+        let non_revealed_bid: Bid =
+            random_collateralized_non_revealed_bid(&tokens.purchasePrice, &tokens.collateralPrice);
+        assert_eq!(
+            non_revealed_bid.validate(&tokens),
+            Err(OrderInvalidReason::NotRevealed)
+        );
+
+        let undercollateralized_bid: Bid =
+            random_undercollateralized_bid(&tokens.purchasePrice, &tokens.collateralPrice);
+        assert_eq!(
+            undercollateralized_bid.validate(&tokens),
+            Err(OrderInvalidReason::Undercollateralized)
+        );
+
+        let mut dust_bid: Bid = revealed_bid.clone();
+        dust_bid.amount = U256::from(MIN_ORDER_AMOUNT - 1);
+        assert_eq!(
+            dust_bid.validate(&tokens),
+            Err(OrderInvalidReason::BelowMinimumAmount)
+        );
+
+        let mut amount_overflow_bid: Bid = revealed_bid.clone();
+        amount_overflow_bid.amount = U256::MAX;
+        assert_eq!(
+            amount_overflow_bid.validate(&tokens),
+            Err(OrderInvalidReason::AmountOverflow)
+        );
+
+        let mut collateral_overflow_bid: Bid = revealed_bid.clone();
+        collateral_overflow_bid.collateral_amount = U256::MAX;
+        assert_eq!(
+            collateral_overflow_bid.validate(&tokens),
+            Err(OrderInvalidReason::CollateralOverflow)
+        );
+    }
+
     #[test]
     fn test_bid_to_exit_leaf() {
         let bid: Bid = random_revealed_bid();
@@ -371,6 +731,127 @@ pub mod tests {
         assert_eq!(bids.len(), 0);
     }
 
+    #[test]
+    fn test_bid_order_lookup() {
+        let mut bids: Bids = Bids::new();
+        let bid_submission: BidSubmission = random_bid_submission();
+        assert!(!bids.has_order(&bid_submission.bidder, &bid_submission.id));
+        assert_eq!(
+            bids.get_order(&bid_submission.bidder, &bid_submission.id),
+            None
+        );
+
+        // Looking up by address and ID matches looking up by the computed key
+        bids.save_or_update_order(&bid_submission);
+        let key: B256 = get_key(&bid_submission.bidder, &bid_submission.id);
+        assert!(bids.has_order(&bid_submission.bidder, &bid_submission.id));
+        assert_eq!(
+            bids.get_order(&bid_submission.bidder, &bid_submission.id),
+            bids.get(&key)
+        );
+
+        // Same address with a different ID is a different order
+        let other_id: U96 = bid_submission.id.wrapping_add(U96::from(1));
+        assert!(!bids.has_order(&bid_submission.bidder, &other_id));
+    }
+
+    #[test]
+    fn test_bids_aggregates() {
+        let mut bids: Bids = Bids::new();
+        assert_eq!(bids.total_amount(), U256::ZERO);
+        assert_eq!(bids.total_collateral(), U256::ZERO);
+        assert_eq!(bids.count_revealed(), 0);
+
+        let mut revealed_bid: Bid = random_revealed_bid();
+        revealed_bid.amount = U256::from(100);
+        revealed_bid.collateral_amount = U256::from(150);
+        let mut non_revealed_bid: Bid = Bid::from_order_submission(&random_bid_submission());
+        non_revealed_bid.amount = U256::from(200);
+        non_revealed_bid.collateral_amount = U256::from(250);
+        for bid in [revealed_bid, non_revealed_bid] {
+            bids.insert(get_key(&bid.bidder, &bid.id), bid);
+        }
+
+        assert_eq!(bids.total_amount(), U256::from(300));
+        assert_eq!(bids.total_collateral(), U256::from(400));
+        assert_eq!(bids.count_revealed(), 1);
+
+        // Totals saturate instead of overflowing
+        let mut max_bid: Bid = random_revealed_bid();
+        max_bid.amount = U256::MAX;
+        max_bid.collateral_amount = U256::MAX;
+        bids.insert(get_key(&max_bid.bidder, &max_bid.id), max_bid);
+
+        assert_eq!(bids.total_amount(), U256::MAX);
+        assert_eq!(bids.total_collateral(), U256::MAX);
+        assert_eq!(bids.count_revealed(), 2);
+    }
+
+    #[test]
+    fn test_cancel_bids_by_address() {
+        let bidder: Address = Address::with_last_byte(0x11);
+        // Right next to the bidder, so that its keys border the range of the bidder
+        let other_bidder: Address = Address::with_last_byte(0x12);
+        let mut bids: Bids = Bids::new();
+        for (address, id) in [
+            (bidder, U96::ZERO),
+            (bidder, U96::from(42)),
+            (bidder, U96::MAX),
+            (other_bidder, U96::ZERO),
+            (other_bidder, U96::MAX),
+        ] {
+            let mut bid_submission: BidSubmission = random_bid_submission();
+            bid_submission.bidder = address;
+            bid_submission.id = id;
+            bids.save_or_update_order(&bid_submission);
+        }
+
+        // Only the orders of the bidder are removed
+        assert_eq!(bids.cancel_by_address(&bidder), 3);
+        assert_eq!(bids.len(), 2);
+        assert!(bids.values().all(|bid| bid.bidder == other_bidder));
+
+        // Nothing is left to cancel
+        assert_eq!(bids.cancel_by_address(&bidder), 0);
+        assert_eq!(bids.len(), 2);
+    }
+
+    #[test]
+    fn test_orders_for() {
+        let bidder: Address = Address::with_last_byte(0x11);
+        // Right next to the bidder on both sides, so that their keys border the range of the bidder
+        let previous_bidder: Address = Address::with_last_byte(0x10);
+        let next_bidder: Address = Address::with_last_byte(0x12);
+        let mut bids: Bids = Bids::new();
+        for (address, id) in [
+            (previous_bidder, U96::MAX),
+            (bidder, U96::MAX),
+            (bidder, U96::ZERO),
+            (bidder, U96::from(42)),
+            (next_bidder, U96::ZERO),
+        ] {
+            let mut bid_submission: BidSubmission = random_bid_submission();
+            bid_submission.bidder = address;
+            bid_submission.id = id;
+            bids.save_or_update_order(&bid_submission);
+        }
+
+        // Exactly the orders of the bidder, sorted by ID
+        let bidder_bids: Vec<(&B256, &Bid)> = bids.orders_for(&bidder).collect();
+        assert_eq!(bidder_bids.len(), 3);
+        for ((key, bid), id) in bidder_bids
+            .into_iter()
+            .zip([U96::ZERO, U96::from(42), U96::MAX])
+        {
+            assert_eq!(*key, get_key(&bidder, &id));
+            assert_eq!(bid.bidder, bidder);
+            assert_eq!(bid.id, id);
+        }
+
+        // Nothing for an address without orders
+        assert_eq!(bids.orders_for(&Address::with_last_byte(0x13)).count(), 0);
+    }
+
     #[test]
     fn test_order_submissions_hash_chain() {
         // Random values
@@ -394,6 +875,217 @@ pub mod tests {
         assert_eq!(expected_bids, bids);
     }
 
+    #[test]
+    fn test_order_submissions_hash_chain_max_orders() {
+        let mut bid_submissions: BidSubmissions =
+            (0..MAX_ORDERS).map(|_| random_bid_submission()).collect();
+        // Updating an order already placed does not count towards the limit
+        let mut update_submission: BidSubmission = random_bid_submission();
+        update_submission.bidder = bid_submissions[0].bidder;
+        update_submission.id = bid_submissions[0].id;
+        bid_submissions.push(update_submission);
+
+        let mut bids: Bids = Bids::new();
+        bid_submissions.hash_chain(&|x: &[u8]| keccak256(x), B256::ZERO, &mut bids);
+        assert_eq!(bids.len(), MAX_ORDERS as usize);
+    }
+
+    #[test]
+    #[should_panic(expected = "Bid submissions exceed the maximum of")]
+    fn test_order_submissions_hash_chain_above_max_orders() {
+        let bid_submissions: BidSubmissions =
+            (0..=MAX_ORDERS).map(|_| random_bid_submission()).collect();
+
+        bid_submissions.hash_chain(&|x: &[u8]| keccak256(x), B256::ZERO, &mut Bids::new());
+    }
+
+    #[test]
+    fn test_order_submissions_hash_chain_iter() {
+        let hash_function = |x: &[u8]| keccak256(x);
+        let start_value: B256 = B256::random();
+        let bid_submissions: BidSubmissions = (0..100).map(|_| random_bid_submission()).collect();
+
+        let mut expected_bids: Bids = Bids::new();
+        let expected_output: B256 =
+            bid_submissions.hash_chain(&hash_function, start_value, &mut expected_bids);
+
+        // Streaming the submissions one by one yields the same chain and orders
+        let mut bids: Bids = Bids::new();
+        let output: B256 = BidSubmissions::hash_chain_iter(
+            bid_submissions.iter().take(100),
+            &hash_function,
+            start_value,
+            &mut bids,
+        );
+
+        assert_eq!(output, expected_output);
+        assert_eq!(bids, expected_bids);
+    }
+
+    #[test]
+    fn test_bid_reveals_hash_chain_iter() {
+        let hash_function = |x: &[u8]| keccak256(x);
+        let mut bid_reveals: BidReveals = BidReveals::new();
+        let bid_submissions: BidSubmissions = (0..1_000)
+            .map(|_| {
+                let price: U256 = U256::from(rand::random::<u32>() % MAX_BID_PRICE);
+                let nonce: U256 = U256::from(rand::random::<u128>());
+                let bid_submission: BidSubmission = valid_random_bid_submission(&price, &nonce);
+                bid_reveals.push(BidReveal {
+                    orderId: get_key(&bid_submission.bidder, &bid_submission.id).into(),
+                    price,
+                    nonce,
+                });
+                bid_submission
+            })
+            .collect();
+
+        let mut expected_bids: Bids = Bids::new();
+        let mut expected_output: B256 =
+            bid_submissions.hash_chain(&hash_function, B256::ZERO, &mut expected_bids);
+        expected_output =
+            bid_reveals.hash_chain(&hash_function, expected_output, &mut expected_bids);
+
+        // Reading the history page by page, as from a database cursor, yields the same chain and revealed orders
+        let mut bids: Bids = Bids::new();
+        let mut output: B256 = BidSubmissions::hash_chain_iter(
+            bid_submissions.chunks(64).flatten(),
+            &hash_function,
+            B256::ZERO,
+            &mut bids,
+        );
+        output = BidReveals::hash_chain_iter(
+            bid_reveals.chunks(64).flatten(),
+            &hash_function,
+            output,
+            &mut bids,
+        );
+
+        assert_eq!(output, expected_output);
+        assert_eq!(bids, expected_bids);
+        assert_eq!(bids.count_revealed(), 1_000);
+    }
+
+    #[test]
+    fn test_find_duplicate_keys() {
+        let mut bids_submissions: BidSubmissions =
+            (0..3).map(|_| random_bid_submission()).collect();
+        assert!(bids_submissions.find_duplicate_keys().is_empty());
+
+        // Resubmitting under the same key is reported once, no matter how many times it repeats
+        let mut duplicate_submission: BidSubmission = random_bid_submission();
+        duplicate_submission.bidder = bids_submissions[1].bidder;
+        duplicate_submission.id = bids_submissions[1].id;
+        bids_submissions.push(duplicate_submission.clone());
+        bids_submissions.push(duplicate_submission);
+
+        assert_eq!(
+            bids_submissions.find_duplicate_keys(),
+            vec![get_key(
+                &bids_submissions[1].bidder,
+                &bids_submissions[1].id
+            )]
+        );
+    }
+
+    #[test]
+    fn test_order_submissions_hash_chain_strict() {
+        let hash_function = |x: &[u8]| keccak256(x);
+        let start_value: B256 = B256::random();
+        let mut bids_submissions: BidSubmissions =
+            (0..3).map(|_| random_bid_submission()).collect();
+
+        // Updates under a repeated key hash exactly like the non strict chain
+        let mut update_submission: BidSubmission = random_bid_submission();
+        update_submission.bidder = bids_submissions[0].bidder;
+        update_submission.id = bids_submissions[0].id;
+        bids_submissions.push(update_submission);
+
+        let mut expected_bids: Bids = Bids::new();
+        let expected_output: B256 =
+            bids_submissions.hash_chain(&hash_function, start_value, &mut expected_bids);
+        let mut bids: Bids = Bids::new();
+        assert_eq!(
+            bids_submissions.hash_chain_strict(&hash_function, start_value, &mut bids),
+            Ok(expected_output)
+        );
+        assert_eq!(bids, expected_bids);
+
+        // A submission no onchain sender can have made is rejected without touching the orders
+        let mut zero_address_submission: BidSubmission = random_bid_submission();
+        zero_address_submission.bidder = Address::ZERO;
+        bids_submissions.push(zero_address_submission.clone());
+
+        let mut bids: Bids = Bids::new();
+        assert_eq!(
+            bids_submissions.hash_chain_strict(&hash_function, start_value, &mut bids),
+            Err(SubmissionError::ZeroAddressSender(get_key(
+                &Address::ZERO,
+                &zero_address_submission.id
+            )))
+        );
+        assert!(bids.is_empty());
+    }
+
+    #[test]
+    fn test_order_reveals_hash_chain_with_stats() {
+        let hash_function = |x: &[u8]| keccak256(x);
+
+        // Reveals keyed like their submissions all match a placed bid
+        let mut bid_reveals: BidReveals = BidReveals::new();
+        let bid_submissions: BidSubmissions = (0..42)
+            .map(|_| {
+                let price: U256 = U256::from(rand::random::<u32>() % MAX_BID_PRICE);
+                let nonce: U256 = U256::from(rand::random::<u128>());
+                let bid_submission: BidSubmission = valid_random_bid_submission(&price, &nonce);
+                bid_reveals.push(BidReveal {
+                    orderId: get_key(&bid_submission.bidder, &bid_submission.id).into(),
+                    price,
+                    nonce,
+                });
+                bid_submission
+            })
+            .collect();
+        let mut bids: Bids = Bids::new();
+        bid_submissions.hash_chain(&hash_function, B256::ZERO, &mut bids);
+        let mut expected_bids: Bids = bids.clone();
+        let expected_output: B256 =
+            bid_reveals.hash_chain(&hash_function, B256::ZERO, &mut expected_bids);
+
+        let (output, stats) =
+            bid_reveals.hash_chain_with_stats(&hash_function, B256::ZERO, &mut bids);
+        assert_eq!(output, expected_output);
+        assert_eq!(bids, expected_bids);
+        assert_eq!(
+            stats,
+            RevealStats {
+                total: 42,
+                matched: 42,
+            }
+        );
+
+        // Reveals with random order IDs match none
+        let bid_submissions: BidSubmissions = (0..42).map(|_| random_bid_submission()).collect();
+        let bid_reveals: BidReveals = (0..42)
+            .map(|_| BidReveal {
+                orderId: U256::from(rand::random::<u64>()),
+                price: U256::from(rand::random::<u128>()),
+                nonce: U256::from(rand::random::<u128>()),
+            })
+            .collect();
+        let mut bids: Bids = Bids::new();
+        bid_submissions.hash_chain(&hash_function, B256::ZERO, &mut bids);
+
+        let (_, stats) = bid_reveals.hash_chain_with_stats(&hash_function, B256::ZERO, &mut bids);
+        assert_eq!(
+            stats,
+            RevealStats {
+                total: 42,
+                matched: 0,
+            }
+        );
+    }
+
     #[test]
     fn test_order_reveals_hash_chain() {
         // Random values
@@ -432,6 +1124,127 @@ pub mod tests {
         assert_eq!(expected_bids, bids);
     }
 
+    #[test]
+    fn test_order_reveals_hash_chain_updates_submitted_bid() {
+        let price: U256 = U256::from(rand::random::<u32>() % MAX_BID_PRICE);
+        let nonce: U256 = U256::from(rand::random::<u128>());
+        let bid_submission: BidSubmission = valid_random_bid_submission(&price, &nonce);
+        let mut bids: Bids = Bids::new();
+        vec![bid_submission.clone()].hash_chain(&|x: &[u8]| keccak256(x), B256::ZERO, &mut bids);
+
+        // The reveal is keyed exactly like the submission
+        let bid_reveal: BidReveal = BidReveal {
+            orderId: get_key(&bid_submission.bidder, &bid_submission.id).into(),
+            price,
+            nonce,
+        };
+        assert_eq!(
+            bid_reveal.key(),
+            get_key(&bid_submission.bidder, &bid_submission.id)
+        );
+        vec![bid_reveal].hash_chain(&|x: &[u8]| keccak256(x), B256::ZERO, &mut bids);
+
+        // So it reveals the stored bid
+        let bid: &Bid = bids
+            .get(&get_key(&bid_submission.bidder, &bid_submission.id))
+            .unwrap();
+        assert!(bid.is_revealed);
+        assert_eq!(bid.bid_price_revealed, price);
+    }
+
+    #[test]
+    fn test_order_reveals_hash_chain_mismatched_key() {
+        let price: U256 = U256::from(rand::random::<u32>() % MAX_BID_PRICE);
+        let nonce: U256 = U256::from(rand::random::<u128>());
+        let bid_submission: BidSubmission = valid_random_bid_submission(&price, &nonce);
+        let mut bids: Bids = Bids::new();
+        bids.save_or_update_order(&bid_submission);
+
+        // The reveal uses the bid ID alone instead of the full `get_key(bidder, id)` key
+        let bid_reveals: BidReveals = vec![BidReveal {
+            orderId: U256::from(bid_submission.id),
+            price,
+            nonce,
+        }];
+        let start_value: B256 = B256::random();
+        let output: B256 = bid_reveals.hash_chain(&|x: &[u8]| keccak256(x), start_value, &mut bids);
+
+        // Hash chain still advances
+        assert_eq!(
+            output,
+            calculate_expected_hash_chain_output(&start_value, &bid_reveals)
+        );
+        // But the bid remains unrevealed
+        let bid: &Bid = bids
+            .get(&get_key(&bid_submission.bidder, &bid_submission.id))
+            .unwrap();
+        assert!(!bid.is_revealed);
+        assert_eq!(bid.bid_price_revealed, U256::ZERO);
+    }
+
+    #[test]
+    fn test_verify_reveals() {
+        let hash_function = |x: &[u8]| keccak256(x);
+        let price: U256 = bps_to_internal(&U256::from(MAX_BID_PRICE));
+        let out_of_bounds_price: U256 = price + U256::from(1);
+        let nonce: U256 = U256::from(rand::random::<u128>());
+        let bid_submission: BidSubmission = valid_random_bid_submission(&price, &nonce);
+        let out_of_bounds_submission: BidSubmission =
+            valid_random_bid_submission(&out_of_bounds_price, &nonce);
+        let mut bids: Bids = Bids::new();
+        bids.save_or_update_order(&bid_submission);
+        bids.save_or_update_order(&out_of_bounds_submission);
+        let bid_key: B256 = get_key(&bid_submission.bidder, &bid_submission.id);
+        let out_of_bounds_key: B256 = get_key(
+            &out_of_bounds_submission.bidder,
+            &out_of_bounds_submission.id,
+        );
+        let unknown_key: B256 = get_key(&Address::random(), &U96::from(rand::random::<u64>()));
+
+        let reveals: BidReveals = vec![
+            BidReveal {
+                orderId: bid_key.into(),
+                price,
+                nonce,
+            },
+            BidReveal {
+                orderId: unknown_key.into(),
+                price,
+                nonce,
+            },
+            BidReveal {
+                orderId: bid_key.into(),
+                price,
+                nonce: nonce + U256::from(1),
+            },
+            BidReveal {
+                orderId: out_of_bounds_key.into(),
+                price: out_of_bounds_price,
+                nonce,
+            },
+            // A key without the bidder address never matches a bid
+            BidReveal {
+                orderId: U256::from(bid_submission.id),
+                price,
+                nonce,
+            },
+        ];
+        let bids_before: Bids = bids.clone();
+
+        assert_eq!(
+            verify_reveals(&bids, &reveals, &hash_function),
+            vec![
+                (bid_key, RevealResult::Matched),
+                (unknown_key, RevealResult::NoSuchOrder),
+                (bid_key, RevealResult::HashMismatch),
+                (out_of_bounds_key, RevealResult::PriceOutOfBounds),
+                (reveals[4].key(), RevealResult::NoSuchOrder),
+            ]
+        );
+        // The bids are left untouched
+        assert_eq!(bids, bids_before);
+    }
+
     #[test]
     fn test_validated_bids_sort_orders() {
         let mut bids: ValidatedBids = vec![
@@ -444,6 +1257,36 @@ pub mod tests {
         assert!(bids[1].bid_price_revealed <= bids[2].bid_price_revealed);
     }
 
+    #[test]
+    fn test_validated_bids_sort_orders_tiebreak() {
+        // Equal price orders, with some of them sharing amounts too
+        let price: U256 = U256::from(rand::random::<u32>() % 1_000);
+        let bids: ValidatedBids = (0..24)
+            .map(|i| {
+                let mut bid: Bid = random_revealed_bid();
+                bid.bid_price_revealed = price;
+                bid.amount = U256::from(i % 4);
+                bid
+            })
+            .collect();
+
+        let mut sorted_bids: ValidatedBids = bids.clone();
+        sorted_bids.sort_orders();
+        for pair in sorted_bids.windows(2) {
+            assert!(
+                pair[0].amount > pair[1].amount
+                    || (pair[0].amount == pair[1].amount
+                        && get_key(&pair[0].bidder, &pair[0].id)
+                            < get_key(&pair[1].bidder, &pair[1].id))
+            );
+        }
+
+        // Any input order sorts to the same result
+        let mut reversed_bids: ValidatedBids = bids.into_iter().rev().collect();
+        reversed_bids.sort_orders();
+        assert_eq!(reversed_bids, sorted_bids);
+    }
+
     #[test]
     fn test_validated_bids_unlock_outstanding_orders() {
         let prover_address: Address = Address::random();
@@ -464,17 +1307,30 @@ pub mod tests {
             bidPriceHash: B256::random(),
             amount: U256::from(rand::random::<u128>()),
             collateralAmount: U256::from(rand::random::<u128>()),
+            isRollover: false,
+            rolloverPairOffTermRepoServicer: Address::ZERO,
+        }
+    }
+
+    /// Creates a new rollover BidSubmission with random values for testing purposes.
+    pub fn random_rollover_bid_submission() -> BidSubmission {
+        BidSubmission {
+            isRollover: true,
+            rolloverPairOffTermRepoServicer: Address::random(),
+            ..random_bid_submission()
         }
     }
 
     /// Creates a random BidSubmission with a valid bid price hash for the given price and nonce.
-    fn valid_random_bid_submission(price: &U256, nonce: &U256) -> BidSubmission {
+    pub fn valid_random_bid_submission(price: &U256, nonce: &U256) -> BidSubmission {
         BidSubmission {
             bidder: Address::random(),
             id: U96::from(rand::random::<u64>()),
-            bidPriceHash: get_price_hash(&|x| keccak256(x), price, nonce),
+            bidPriceHash: get_price_hash(&|x: &[u8]| keccak256(x), price, nonce),
             amount: U256::from(rand::random::<u128>()),
             collateralAmount: U256::from(rand::random::<u128>()),
+            isRollover: false,
+            rolloverPairOffTermRepoServicer: Address::ZERO,
         }
     }
 
@@ -2,14 +2,23 @@ pub mod bids;
 pub mod offers;
 
 use crate::{
-    allocations::Allocations, auction_parameters::AuctionParameters, exit_tree::ExitLeafTokenWithdrawal,
+    allocations::Allocations,
+    auction_parameters::AuctionParameters,
+    exit_tree::ExitLeafTokenWithdrawal,
+    precompiles::HashFunction,
+    utils::{get_key, get_key_range},
 };
-use alloy_primitives::B256;
-use std::collections::BTreeMap;
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    vec::Vec,
+};
+use alloy_primitives::{aliases::U96, Address, B256, U256};
+use core::{error, fmt, ops::RangeInclusive};
 
 /// Trait for types that represent onchain chainable orders.
 pub trait ChainableSubmissions {
     type T;
+    type Item;
     /// Computes an orders hash chain while updating the orders in the provided `orders` mapping with the revealed price information.
     ///
     /// # Arguments
@@ -25,7 +34,249 @@ pub trait ChainableSubmissions {
         orders: &mut BTreeMap<B256, Self::T>,
     ) -> B256
     where
-        F: Fn(&[u8]) -> B256;
+        F: HashFunction + ?Sized;
+
+    /// Computes an orders hash chain like `hash_chain`, but folding over any iterator of items, so that the whole
+    /// history does not need to be held in memory at once.
+    ///
+    /// # Arguments
+    ///
+    /// * `items` - The items placed onchain, in the order they were placed.
+    /// * `hash_function` - A function that computes a 32-byte hash from a byte slice.
+    /// * `start_value` - The initial 32-byte value to start the hash chain.
+    /// * `orders` - A mutable reference to the `T` BTreeMap where all orders will be updated.
+    fn hash_chain_iter<'a, F, I>(
+        items: I,
+        hash_function: &F,
+        start_value: B256,
+        orders: &mut BTreeMap<B256, Self::T>,
+    ) -> B256
+    where
+        F: HashFunction + ?Sized,
+        I: IntoIterator<Item = &'a Self::Item>,
+        Self::Item: 'a;
+}
+
+/// Trait for the history of order submissions made onchain, each keyed by `get_key(address, id)`.
+pub trait OrderSubmissions: ChainableSubmissions {
+    /// Returns the key of every submission, in submission order.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The order submissions.
+    fn keys(&self) -> Vec<B256>;
+
+    /// Returns the largest number of orders that are placed at once while processing the submissions in order, which
+    /// `hash_chain` requires to stay within `MAX_ORDERS`.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The order submissions.
+    fn peak_order_count(&self) -> usize;
+
+    /// Returns every key that is shared by more than one submission, each reported once in the order
+    /// its first repetition appears.
+    ///
+    /// Repeated keys are how orders are updated onchain, so this is meant for inspecting the inputs rather than
+    /// rejecting them.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The order submissions.
+    fn find_duplicate_keys(&self) -> Vec<B256> {
+        let mut seen: BTreeSet<B256> = BTreeSet::new();
+        let mut reported: BTreeSet<B256> = BTreeSet::new();
+
+        self.keys()
+            .into_iter()
+            .filter(|key: &B256| !seen.insert(*key) && reported.insert(*key))
+            .collect()
+    }
+
+    /// Computes the orders hash chain like `hash_chain`, but fails on any submission the contract cannot have
+    /// produced, instead of placing an order nobody can have submitted.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The order submissions.
+    /// * `hash_function` - A function that computes a 32-byte hash from a byte slice.
+    /// * `start_value` - The initial 32-byte value to start the hash chain.
+    /// * `orders` - A mutable reference to the BTreeMap where all orders will be saved.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<B256, SubmissionError>` - The hash chain output, or the reason the first invalid submission cannot
+    ///   have been made onchain. On error `orders` is left untouched.
+    fn hash_chain_strict<F>(
+        &self,
+        hash_function: &F,
+        start_value: B256,
+        orders: &mut BTreeMap<B256, Self::T>,
+    ) -> Result<B256, SubmissionError>
+    where
+        F: HashFunction + ?Sized,
+    {
+        let zero_address_keys: RangeInclusive<B256> = get_key_range(&Address::ZERO);
+        if let Some(key) = self
+            .keys()
+            .into_iter()
+            .find(|key: &B256| zero_address_keys.contains(key))
+        {
+            return Err(SubmissionError::ZeroAddressSender(key));
+        }
+
+        Ok(self.hash_chain(hash_function, start_value, orders))
+    }
+
+    /// Computes the orders hash chain like `hash_chain`, also counting how many orders each submission created,
+    /// updated or deleted, so that they can be reconciled against the events emitted onchain.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The order submissions.
+    /// * `hash_function` - A function that computes a 32-byte hash from a byte slice.
+    /// * `start_value` - The initial 32-byte value to start the hash chain.
+    /// * `orders` - A mutable reference to the BTreeMap where all orders will be saved.
+    /// * `stats` - The statistics the changes made to `orders` are added to.
+    ///
+    /// # Returns
+    ///
+    /// * `B256` - The hash chain output, the same as `hash_chain` would return.
+    fn hash_chain_with_order_stats<F>(
+        &self,
+        hash_function: &F,
+        start_value: B256,
+        orders: &mut BTreeMap<B256, Self::T>,
+        stats: &mut OrderStats,
+    ) -> B256
+    where
+        F: HashFunction + ?Sized,
+        for<'a> &'a Self: IntoIterator<Item = &'a Self::Item>,
+    {
+        self.keys()
+            .into_iter()
+            .zip(self)
+            .fold(start_value, |acc: B256, (key, item)| {
+                let existed: bool = orders.contains_key(&key);
+                let acc: B256 = Self::hash_chain_iter([item], hash_function, acc, orders);
+                stats.record(existed, orders.contains_key(&key));
+                acc
+            })
+    }
+}
+
+/// Reasons for which `hash_chain_strict` rejects a history of order submissions.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum SubmissionError {
+    /// The submission with this key was made by the zero address, which no onchain sender can be.
+    ZeroAddressSender(B256),
+}
+
+impl fmt::Display for SubmissionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SubmissionError::ZeroAddressSender(key) => {
+                write!(f, "order submission {} was made by the zero address", key)
+            }
+        }
+    }
+}
+
+impl error::Error for SubmissionError {}
+
+/// How many orders were created, updated or deleted by a batch of order submissions.
+#[derive(Default, PartialEq, Eq, Debug)]
+pub struct OrderStats {
+    /// The number of submissions that placed a new order.
+    pub created: usize,
+    /// The number of submissions that updated an existing order.
+    pub updated: usize,
+    /// The number of submissions that deleted an existing order.
+    pub deleted: usize,
+}
+
+impl OrderStats {
+    /// Records the change a submission made to an order.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The statistics being updated.
+    /// * `existed` - Whether the order existed before the submission.
+    /// * `exists` - Whether the order exists after the submission.
+    fn record(&mut self, existed: bool, exists: bool) {
+        match (existed, exists) {
+            (false, true) => self.created += 1,
+            (true, true) => self.updated += 1,
+            (true, false) => self.deleted += 1,
+            // A submission deleting an order that was never placed changes nothing
+            (false, false) => {}
+        }
+    }
+}
+
+/// How many order reveals matched an order that was placed onchain.
+#[derive(Default, PartialEq, Eq, Debug)]
+pub struct RevealStats {
+    /// The number of reveals made onchain.
+    pub total: usize,
+    /// The number of reveals whose key matched a placed order.
+    pub matched: usize,
+}
+
+/// The result of checking a single order reveal against the order it points to.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum RevealResult {
+    /// The reveal matches the price hash of the order, and its price is within bounds.
+    Matched,
+    /// No placed order has the key the reveal points to.
+    NoSuchOrder,
+    /// The revealed price and nonce do not hash to the price hash of the order.
+    HashMismatch,
+    /// The reveal matches the price hash of the order, but its price is above the maximum price.
+    PriceOutOfBounds,
+}
+
+/// Trait for the history of order reveals made onchain, each pointing to an order by its `get_key(address, id)` key.
+pub trait OrderReveals: ChainableSubmissions {
+    /// Returns the key of the order every reveal points to, in reveal order.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The order reveals.
+    fn keys(&self) -> Vec<B256>;
+
+    /// Computes the orders hash chain like `hash_chain`, also counting how many reveals matched a placed order.
+    ///
+    /// A reveal that matches no order is still added to the hash chain, but can never reveal a price, so a low
+    /// `matched` count points to inputs whose reveals are not keyed like their submissions.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The order reveals.
+    /// * `hash_function` - A function that computes a 32-byte hash from a byte slice.
+    /// * `start_value` - The initial 32-byte value to start the hash chain.
+    /// * `orders` - A mutable reference to the BTreeMap where all orders will be updated.
+    ///
+    /// # Returns
+    ///
+    /// * `(B256, RevealStats)` - The hash chain output, alongside the reveal statistics.
+    fn hash_chain_with_stats<F>(
+        &self,
+        hash_function: &F,
+        start_value: B256,
+        orders: &mut BTreeMap<B256, Self::T>,
+    ) -> (B256, RevealStats)
+    where
+        F: HashFunction + ?Sized,
+    {
+        let keys: Vec<B256> = self.keys();
+        let stats: RevealStats = RevealStats {
+            total: keys.len(),
+            matched: keys.iter().filter(|&key| orders.contains_key(key)).count(),
+        };
+
+        (self.hash_chain(hash_function, start_value, orders), stats)
+    }
 }
 
 /// Trait for placed orders mappings.
@@ -42,8 +293,38 @@ pub trait PlacedOrders: IntoIterator<Item = (B256, Self::Order)> + Sized {
     /// * `order_submission` - A reference to the `OrderSubmission` containing the order details.
     fn save_or_update_order(&mut self, order_submission: &Self::OrderSubmission);
 
+    /// Removes every order placed by an address from the orders collection.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - A mutable reference to the `Orders` collection (BTreeMap) to modify.
+    /// * `address` - The address whose orders are cancelled.
+    ///
+    /// # Returns
+    ///
+    /// The number of orders removed.
+    fn cancel_by_address(&mut self, address: &Address) -> usize;
+
+    /// Returns the sum of the amounts of all orders, saturating on overflow.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The orders collection.
+    fn total_amount(&self) -> U256;
+
+    /// Returns the number of orders whose price was properly revealed.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The orders collection.
+    fn count_revealed(&self) -> usize;
+
     /// Validates orders and returns a vector of valid orders, assigning invalid orders to the corresponding allocations.
     ///
+    /// Orders are visited in ascending order of key, that is by address and then by ID, regardless of the order they
+    /// were submitted in. `ValidatedOrders::sort_orders` falls back to the same key to break ties, so the auction
+    /// results only depend on the set of orders placed, not on how their submissions were interleaved.
+    ///
     /// # Arguments
     ///
     /// * `orders` - The orders mapping to validate.
@@ -68,6 +349,21 @@ pub trait PlacedOrders: IntoIterator<Item = (B256, Self::Order)> + Sized {
     }
 }
 
+/// The reasons why an order can be deemed invalid and be left out of auction matching.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum OrderInvalidReason {
+    /// The order price was never properly revealed.
+    NotRevealed,
+    /// The order amount, in raw purchase token units, is below `MIN_ORDER_AMOUNT`.
+    BelowMinimumAmount,
+    /// The collateral locked does not cover the initial collateral ratio.
+    Undercollateralized,
+    /// Computing the value of the order amount overflows.
+    AmountOverflow,
+    /// Computing the value of the locked collateral overflows.
+    CollateralOverflow,
+}
+
 /// Trait for orders.
 pub trait Order {
     type OrderSubmission;
@@ -88,26 +384,36 @@ pub trait Order {
     /// * `order_submission` - The new order submission.
     fn update_from_order_submission(&mut self, order_submission: &Self::OrderSubmission);
 
-    /// Updates the order with revealed information if the reveal is valid.
+    /// Updates the order with revealed information if the reveal is valid and the order was not revealed already.
     ///
     /// # Arguments
     ///
     /// * `self` - The order being updated.
     /// * `hash_function` - A function that computes a 32-byte hash from a byte slice.
     /// * `order_reveal` - The reveal information containing the price and nonce.
-    fn update_from_order_reveal<F: Fn(&[u8]) -> B256>(
+    fn update_from_order_reveal<F: HashFunction + ?Sized>(
         &mut self,
         hash_function: &F,
         order_reveal: &Self::OrderReveal,
     );
 
+    /// Checks whether the order can go to auction matching, returning the reason why not otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The order being checked.
+    /// * `tokens` - The tokens to check against.
+    fn validate(&self, tokens: &AuctionParameters) -> Result<(), OrderInvalidReason>;
+
     /// Returns true if the order is valid and can go to auction matching.
     ///
     /// # Arguments
     ///
     /// * `self` - The order being checked.
     /// * `tokens` - The tokens to check against.
-    fn is_valid(&self, tokens: &AuctionParameters) -> bool;
+    fn is_valid(&self, tokens: &AuctionParameters) -> bool {
+        self.validate(tokens).is_ok()
+    }
 
     /// Converts the order to an exit leaf.
     ///
@@ -121,6 +427,69 @@ pub trait Order {
 /// Type alias for orders mapping.
 pub type Orders<T> = BTreeMap<B256, T>;
 
+/// Trait for looking up placed orders by the address and ID they were placed with, instead of by their key.
+pub trait OrderLookup {
+    type Order;
+
+    /// Returns the order placed by `address` with the given `id`, if any.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The orders mapping.
+    /// * `address` - The address of the bidder or offeror.
+    /// * `id` - The ID of the order.
+    fn get_order(&self, address: &Address, id: &U96) -> Option<&Self::Order>;
+
+    /// Returns true if `address` placed an order with the given `id`.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The orders mapping.
+    /// * `address` - The address of the bidder or offeror.
+    /// * `id` - The ID of the order.
+    fn has_order(&self, address: &Address, id: &U96) -> bool {
+        self.get_order(address, id).is_some()
+    }
+
+    /// Returns every order placed by `address`, in ascending order of ID.
+    ///
+    /// Keys start with the address, so the orders of an address are contiguous and only their range is visited,
+    /// instead of every order.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The orders mapping.
+    /// * `address` - The address of the bidder or offeror.
+    fn orders_for(&self, address: &Address) -> impl Iterator<Item = (&B256, &Self::Order)>;
+}
+
+/// Removes every order placed by `address`, returning how many were removed.
+///
+/// # Arguments
+///
+/// * `orders` - The orders mapping to modify.
+/// * `address` - The address of the bidder or offeror.
+fn remove_orders_by_address<T>(orders: &mut Orders<T>, address: &Address) -> usize {
+    let keys: Vec<B256> = orders.orders_for(address).map(|(key, _)| *key).collect();
+    for key in keys.iter() {
+        orders.remove(key);
+    }
+
+    keys.len()
+}
+
+impl<T> OrderLookup for Orders<T> {
+    type Order = T;
+
+    fn get_order(&self, address: &Address, id: &U96) -> Option<&T> {
+        self.get(&get_key(address, id))
+    }
+
+    fn orders_for(&self, address: &Address) -> impl Iterator<Item = (&B256, &T)> {
+        self.range(get_key_range(address))
+    }
+}
+
 pub trait ValidatedOrders: IntoIterator<Item = Self::Order> + Sized {
     type Allocation;
     type Order: Order;
@@ -134,6 +503,9 @@ pub trait ValidatedOrders: IntoIterator<Item = Self::Order> + Sized {
 
     /// Dumps all outstanding validated orders into their corresponding allocations.
     ///
+    /// Allocations are kept in address-ordered maps and only add up order amounts, so the refund leaves end up in
+    /// the same canonical order whichever order the validated orders are unlocked in.
+    ///
     /// # Arguments
     ///
     /// * `self` - The validated orders.
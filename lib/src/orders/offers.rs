@@ -1,15 +1,24 @@
-use super::{ChainableSubmissions, Order, PlacedOrders, ValidatedOrders};
+use super::{
+    remove_orders_by_address, ChainableSubmissions, Order, OrderInvalidReason, OrderReveals,
+    OrderSubmissions, PlacedOrders, ValidatedOrders,
+};
 use crate::{
     allocations::offeror_allocations::OfferorAllocation,
     auction_parameters::AuctionParameters,
-    constants::MAX_OFFER_PRICE,
-    exit_tree::ExitLeafTokenWithdrawal,
-    utils::{add_to_hash_chain, get_key, get_price_hash},
+    constants::{MAX_OFFER_PRICE, MAX_ORDERS, MIN_ORDER_AMOUNT},
+    exit_tree::{ExitLeafRepoTokenWithdrawal, ExitLeafTokenWithdrawal},
+    precompiles::HashFunction,
+    utils::{
+        add_to_hash_chain, fold_hash_chain, get_key, order_key_from_u256, verify_price_reveal,
+    },
+};
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    vec::Vec,
 };
 use alloy_primitives::{aliases::U96, Address, B256, U256};
 use alloy_sol_types::sol;
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
 
 /// Represents an offer to lend an amount of money for a specific interest rate.
 #[derive(PartialEq, Eq, Debug, Clone)]
@@ -48,22 +57,36 @@ impl Order for Offer {
         self.offer_price_hash = offer_submission.offerPriceHash;
     }
 
-    fn update_from_order_reveal<F: Fn(&[u8]) -> B256>(
+    fn update_from_order_reveal<F: HashFunction + ?Sized>(
         &mut self,
         hash_function: &F,
         offer_reveal: &OfferReveal,
     ) {
-        if get_price_hash(hash_function, &offer_reveal.price, &offer_reveal.nonce)
-            == self.offer_price_hash
-            && offer_reveal.price <= U256::from(MAX_OFFER_PRICE)
+        // Once revealed, a later matching reveal cannot overwrite the price
+        if !self.is_revealed
+            && verify_price_reveal(
+                hash_function,
+                &offer_reveal.price,
+                &offer_reveal.nonce,
+                &self.offer_price_hash,
+                MAX_OFFER_PRICE,
+            )
         {
             self.offer_price_revealed = offer_reveal.price;
             self.is_revealed = true;
         }
     }
 
-    fn is_valid(&self, _tokens: &AuctionParameters) -> bool {
-        self.is_revealed
+    fn validate(&self, _tokens: &AuctionParameters) -> Result<(), OrderInvalidReason> {
+        if !self.is_revealed {
+            return Err(OrderInvalidReason::NotRevealed);
+        }
+
+        if self.amount < U256::from(MIN_ORDER_AMOUNT) {
+            return Err(OrderInvalidReason::BelowMinimumAmount);
+        }
+
+        Ok(())
     }
 
     fn to_exit_leaf(&self, tokens: &AuctionParameters) -> ExitLeafTokenWithdrawal {
@@ -75,6 +98,23 @@ impl Order for Offer {
     }
 }
 
+impl Offer {
+    /// Converts a filled offer into the withdrawal of the repo tokens it is owed.
+    ///
+    /// Allocations add up every offer placed by the same offeror, so this is meant for inspecting a single offer,
+    /// as when testing or previewing the auction offchain.
+    ///
+    /// # Arguments
+    ///
+    /// * `repurchase_amount` - The repurchase amount owed for the part of the offer that was assigned.
+    pub fn to_repo_exit_leaf(&self, repurchase_amount: &U256) -> ExitLeafRepoTokenWithdrawal {
+        ExitLeafRepoTokenWithdrawal {
+            recipient: self.offeror,
+            amount: *repurchase_amount,
+        }
+    }
+}
+
 /// A collection of all offers, indexed by their unique keys.
 ///
 /// # Key
@@ -108,6 +148,22 @@ impl PlacedOrders for Offers {
                 .or_insert_with(|| Offer::from_order_submission(order_submission));
         }
     }
+
+    fn cancel_by_address(&mut self, address: &Address) -> usize {
+        remove_orders_by_address(self, address)
+    }
+
+    fn total_amount(&self) -> U256 {
+        self.values().fold(U256::ZERO, |acc: U256, offer: &Offer| {
+            acc.saturating_add(offer.amount)
+        })
+    }
+
+    fn count_revealed(&self) -> usize {
+        self.values()
+            .filter(|offer: &&Offer| offer.is_revealed)
+            .count()
+    }
 }
 
 sol! {
@@ -130,29 +186,78 @@ pub type OfferSubmissions = Vec<OfferSubmission>;
 
 impl ChainableSubmissions for OfferSubmissions {
     type T = Offer;
+    type Item = OfferSubmission;
     /// # Behavior
     ///
     /// - If an offer with the same key already exists, it updates the amount and offer price hash.
     /// - If no offer exists for the key, it creates a new `Offer` instance with the provided details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if more than `MAX_ORDERS` offers would be placed at once.
     fn hash_chain<F>(&self, hash_function: &F, start_value: B256, offers: &mut Offers) -> B256
     where
-        F: Fn(&[u8]) -> B256,
+        F: HashFunction + ?Sized,
     {
-        self.iter().fold(
+        Self::hash_chain_iter(self.iter(), hash_function, start_value, offers)
+    }
+
+    fn hash_chain_iter<'a, F, I>(
+        items: I,
+        hash_function: &F,
+        start_value: B256,
+        offers: &mut Offers,
+    ) -> B256
+    where
+        F: HashFunction + ?Sized,
+        I: IntoIterator<Item = &'a OfferSubmission>,
+    {
+        items.into_iter().fold(
             start_value,
             |acc: B256, offer_submission: &OfferSubmission| {
                 offers.save_or_update_order(offer_submission);
+                assert!(
+                    offers.len() <= MAX_ORDERS as usize,
+                    "Offer submissions exceed the maximum of {} orders",
+                    MAX_ORDERS
+                );
                 add_to_hash_chain(hash_function, offer_submission, &acc)
             },
         )
     }
 }
 
+impl OrderSubmissions for OfferSubmissions {
+    fn keys(&self) -> Vec<B256> {
+        self.iter()
+            .map(|offer_submission: &OfferSubmission| {
+                get_key(&offer_submission.offeror, &offer_submission.id)
+            })
+            .collect()
+    }
+
+    /// Offer submissions with a zero amount remove the offer, lowering the count of placed offers.
+    fn peak_order_count(&self) -> usize {
+        let mut placed: BTreeSet<B256> = BTreeSet::new();
+        let mut peak: usize = 0;
+        for (key, offer_submission) in self.keys().into_iter().zip(self.iter()) {
+            if offer_submission.amount.is_zero() {
+                placed.remove(&key);
+            } else {
+                placed.insert(key);
+                peak = peak.max(placed.len());
+            }
+        }
+
+        peak
+    }
+}
+
 sol! {
     /// An `OfferReveal` represents the offer reveal process that was carried out onchain
     #[derive(Serialize, Deserialize)]
     struct OfferReveal {
-        /// The ID of the offer that was revealed
+        /// The key of the offer that was revealed, as given by `get_key(offeror, id)`
         uint256 orderId;
         /// The price of the offer that was revealed
         uint256 price;
@@ -161,11 +266,23 @@ sol! {
     }
 }
 
+impl OfferReveal {
+    /// Returns the key of the revealed offer within the `Offers` mapping.
+    ///
+    /// The `orderId` is expected to be the concatenation of the offeror's address and the offer ID, as
+    /// computed by `get_key`. A reveal whose `orderId` does not follow this layout will not match any
+    /// stored offer.
+    pub fn key(&self) -> B256 {
+        self.orderId.into()
+    }
+}
+
 /// Represents the history of all offer reveals made onchain.
 pub type OfferReveals = Vec<OfferReveal>;
 
 impl ChainableSubmissions for OfferReveals {
     type T = Offer;
+    type Item = OfferReveal;
     /// # Behavior
     ///
     /// - If a offer with the matching `orderId` is found and the calculated price hash
@@ -173,15 +290,34 @@ impl ChainableSubmissions for OfferReveals {
     ///   - Updates the `offer_price_revealed` with the revealed price.
     ///   - Sets `is_revealed` to `true`.
     /// - If no matching offer is found or the price hash doesn't match, no changes are made.
+    ///
+    /// Reveals are matched against stored offers through `order_key_from_u256`, so an `orderId` that is not
+    /// `get_key(offeror, id)` leaves every offer untouched while still being added to the hash chain.
     fn hash_chain<F>(&self, hash_function: &F, start_value: B256, offers: &mut Offers) -> B256
     where
-        F: Fn(&[u8]) -> B256,
+        F: HashFunction + ?Sized,
     {
-        self.iter()
+        Self::hash_chain_iter(self.iter(), hash_function, start_value, offers)
+    }
+
+    fn hash_chain_iter<'a, F, I>(
+        items: I,
+        hash_function: &F,
+        start_value: B256,
+        offers: &mut Offers,
+    ) -> B256
+    where
+        F: HashFunction + ?Sized,
+        I: IntoIterator<Item = &'a OfferReveal>,
+    {
+        items
+            .into_iter()
             .fold(start_value, |acc: B256, item: &OfferReveal| {
                 // Set offer price if it was revealed properly
-                if let Some(offer) = offers.get_mut::<B256>(&item.orderId.into()) {
-                    offer.update_from_order_reveal(hash_function, item);
+                if let Ok(key) = order_key_from_u256(&item.orderId) {
+                    if let Some(offer) = offers.get_mut(&key) {
+                        offer.update_from_order_reveal(hash_function, item);
+                    }
                 }
                 // Add value to hash chain
                 add_to_hash_chain(hash_function, item, &acc)
@@ -189,6 +325,38 @@ impl ChainableSubmissions for OfferReveals {
     }
 }
 
+impl OrderReveals for OfferReveals {
+    fn keys(&self) -> Vec<B256> {
+        self.iter().map(OfferReveal::key).collect()
+    }
+}
+
+/// Recomputes the offers hash chain over the offer submissions and then the offer reveals, the same way
+/// `run_auction` does, but without building the `Offers` mapping.
+///
+/// Meant for verifiers checking that the `accOffersHash` committed by a proof corresponds to a given history of
+/// offers.
+///
+/// # Arguments
+///
+/// * `offer_submissions` - The history of all offer submissions made onchain.
+/// * `offer_reveals` - The history of all offer reveals made onchain.
+/// * `start_value` - The initial 32-byte value to start the hash chain.
+/// * `hash_function` - A function that computes a 32-byte hash from a byte slice.
+///
+/// # Returns
+///
+/// The final `B256` hash of the offers hash chain.
+pub fn recompute_offer_hash_chain<F: HashFunction + ?Sized>(
+    offer_submissions: &OfferSubmissions,
+    offer_reveals: &OfferReveals,
+    start_value: B256,
+    hash_function: &F,
+) -> B256 {
+    let acc_offers_hash: B256 = fold_hash_chain(hash_function, offer_submissions, start_value);
+    fold_hash_chain(hash_function, offer_reveals, acc_offers_hash)
+}
+
 /// A collection of all validated offers.
 pub type ValidatedOffers = Vec<Offer>;
 
@@ -198,16 +366,29 @@ impl ValidatedOrders for ValidatedOffers {
 
     /// Sorts offers from most competitive to least competitive, such that the first item in the list is the most competitive offer
     /// This is done to follow Term Finance's logic for computing the clearing price later on
+    ///
+    /// Offers are ordered by ascending revealed price. Offers at the same price are ordered by descending amount, and
+    /// then by ascending key, so that the resulting order is total and does not depend on the input order.
     fn sort_orders(&mut self) {
-        self.sort_by(|a: &Offer, b: &Offer| a.offer_price_revealed.cmp(&b.offer_price_revealed));
+        self.sort_by(|a: &Offer, b: &Offer| {
+            a.offer_price_revealed
+                .cmp(&b.offer_price_revealed)
+                .then_with(|| b.amount.cmp(&a.amount))
+                .then_with(|| get_key(&a.offeror, &a.id).cmp(&get_key(&b.offeror, &b.id)))
+        });
     }
 }
 
 #[cfg(test)]
 pub mod tests {
     use crate::{
-        allocations::AuctionResults, auction_parameters::tests::random_auction_parameters,
-        utils::test::calculate_expected_hash_chain_output,
+        allocations::AuctionResults,
+        auction_parameters::tests::random_auction_parameters,
+        orders::{OrderLookup, OrderStats, RevealStats, SubmissionError},
+        utils::{
+            bps_to_internal, get_price_hash, internal_to_bps,
+            test::calculate_expected_hash_chain_output,
+        },
     };
 
     use super::*;
@@ -244,7 +425,7 @@ pub mod tests {
         let offer_submission: OfferSubmission = valid_random_offer_submission(&price, &nonce);
         let mut offer: Offer = Offer::from_order_submission(&offer_submission);
         offer.update_from_order_reveal(
-            &|x| keccak256(x),
+            &|x: &[u8]| keccak256(x),
             &OfferReveal {
                 orderId: get_key(&offer_submission.offeror, &offer_submission.id).into(),
                 price,
@@ -268,7 +449,7 @@ pub mod tests {
         assert!(!offer.is_revealed);
 
         // Invalid reveal with out of bounds price
-        let price: U256 = U256::from(MAX_OFFER_PRICE + 1);
+        let price: U256 = bps_to_internal(&U256::from(MAX_OFFER_PRICE)) + U256::from(1);
         let nonce: U256 = U256::from(rand::random::<u128>());
         let offer_submission: OfferSubmission = valid_random_offer_submission(&price, &nonce);
         let mut offer = Offer::from_order_submission(&offer_submission);
@@ -284,6 +465,66 @@ pub mod tests {
         assert!(!offer.is_revealed);
     }
 
+    #[test]
+    fn test_offer_update_from_order_reveal_price_scale() {
+        let max_price: U256 = bps_to_internal(&U256::from(MAX_OFFER_PRICE));
+        let reveal = |price: U256| -> Offer {
+            let nonce: U256 = U256::from(rand::random::<u128>());
+            let offer_submission: OfferSubmission = valid_random_offer_submission(&price, &nonce);
+            let mut offer: Offer = Offer::from_order_submission(&offer_submission);
+            offer.update_from_order_reveal(
+                &|x: &[u8]| keccak256(x),
+                &OfferReveal {
+                    orderId: get_key(&offer_submission.offeror, &offer_submission.id).into(),
+                    price,
+                    nonce,
+                },
+            );
+            offer
+        };
+
+        // A price with 9 decimal places is compared against the maximum price in the same scale
+        for price in [max_price, max_price - U256::from(1), U256::from(1)] {
+            let offer: Offer = reveal(price);
+            assert!(offer.is_revealed);
+            assert_eq!(offer.offer_price_revealed, price);
+        }
+        assert_eq!(internal_to_bps(&max_price), U256::from(MAX_OFFER_PRICE));
+        assert_eq!(
+            internal_to_bps(&(max_price - U256::from(1))),
+            U256::from(MAX_OFFER_PRICE - 1)
+        );
+
+        // A maximum price that was scaled twice is out of bounds
+        let offer: Offer = reveal(bps_to_internal(&max_price));
+        assert!(!offer.is_revealed);
+        assert_eq!(offer.offer_price_revealed, U256::ZERO);
+    }
+
+    #[test]
+    fn test_offer_update_from_order_reveal_first_reveal_wins() {
+        // A hash function under which every price and nonce collide, so that any reveal matches
+        let colliding_hash_function = |_: &[u8]| B256::ZERO;
+        let mut offer: Offer = Offer::from_order_submission(&random_offer_submission());
+        offer.offer_price_hash = B256::ZERO;
+
+        let first_price: U256 = U256::from(rand::random::<u32>() % MAX_OFFER_PRICE);
+        let second_price: U256 = first_price + U256::from(1);
+        for price in [first_price, second_price] {
+            offer.update_from_order_reveal(
+                &colliding_hash_function,
+                &OfferReveal {
+                    orderId: get_key(&offer.offeror, &offer.id).into(),
+                    price,
+                    nonce: U256::from(rand::random::<u128>()),
+                },
+            );
+        }
+
+        assert_eq!(offer.offer_price_revealed, first_price);
+        assert!(offer.is_revealed);
+    }
+
     #[test]
     fn test_offer_is_valid() {
         let mut offer: Offer = random_revealed_offer();
@@ -294,6 +535,25 @@ pub mod tests {
         assert!(!offer.is_valid(&random_auction_parameters()));
     }
 
+    #[test]
+    fn test_offer_validate() {
+        let mut offer: Offer = random_revealed_offer();
+        assert_eq!(offer.validate(&random_auction_parameters()), Ok(()));
+
+        offer.is_revealed = false;
+        assert_eq!(
+            offer.validate(&random_auction_parameters()),
+            Err(OrderInvalidReason::NotRevealed)
+        );
+
+        offer.is_revealed = true;
+        offer.amount = U256::from(MIN_ORDER_AMOUNT - 1);
+        assert_eq!(
+            offer.validate(&random_auction_parameters()),
+            Err(OrderInvalidReason::BelowMinimumAmount)
+        );
+    }
+
     #[test]
     fn test_offer_to_exit_leaf() {
         let offer: Offer = random_revealed_offer();
@@ -305,6 +565,16 @@ pub mod tests {
         assert_eq!(exit_leaf.amount, offer.amount);
     }
 
+    #[test]
+    fn test_offer_to_repo_exit_leaf() {
+        let offer: Offer = random_revealed_offer();
+        let repurchase_amount: U256 = offer.amount + U256::from(1);
+        let exit_leaf: ExitLeafRepoTokenWithdrawal = offer.to_repo_exit_leaf(&repurchase_amount);
+
+        assert_eq!(exit_leaf.recipient, offer.offeror);
+        assert_eq!(exit_leaf.amount, repurchase_amount);
+    }
+
     #[test]
     fn test_save_or_update_offer() {
         let mut offers: Offers = Offers::new();
@@ -342,6 +612,74 @@ pub mod tests {
         assert_eq!(offers.len(), 0);
     }
 
+    #[test]
+    fn test_offer_order_lookup() {
+        let mut offers: Offers = Offers::new();
+        let offer_submission: OfferSubmission = random_offer_submission();
+        assert!(!offers.has_order(&offer_submission.offeror, &offer_submission.id));
+        assert_eq!(
+            offers.get_order(&offer_submission.offeror, &offer_submission.id),
+            None
+        );
+
+        // Looking up by address and ID matches looking up by the computed key
+        offers.save_or_update_order(&offer_submission);
+        let key: B256 = get_key(&offer_submission.offeror, &offer_submission.id);
+        assert!(offers.has_order(&offer_submission.offeror, &offer_submission.id));
+        assert_eq!(
+            offers.get_order(&offer_submission.offeror, &offer_submission.id),
+            offers.get(&key)
+        );
+
+        // Same address with a different ID is a different order
+        let other_id: U96 = offer_submission.id.wrapping_add(U96::from(1));
+        assert!(!offers.has_order(&offer_submission.offeror, &other_id));
+    }
+
+    #[test]
+    fn test_offers_aggregates() {
+        let mut offers: Offers = Offers::new();
+        assert_eq!(offers.total_amount(), U256::ZERO);
+        assert_eq!(offers.count_revealed(), 0);
+
+        let mut revealed_offer: Offer = random_revealed_offer();
+        revealed_offer.amount = U256::from(100);
+        let mut non_revealed_offer: Offer =
+            Offer::from_order_submission(&random_offer_submission());
+        non_revealed_offer.amount = U256::from(200);
+        for offer in [revealed_offer, non_revealed_offer] {
+            offers.insert(get_key(&offer.offeror, &offer.id), offer);
+        }
+
+        assert_eq!(offers.total_amount(), U256::from(300));
+        assert_eq!(offers.count_revealed(), 1);
+
+        // Totals saturate instead of overflowing
+        let mut max_offer: Offer = random_revealed_offer();
+        max_offer.amount = U256::MAX;
+        offers.insert(get_key(&max_offer.offeror, &max_offer.id), max_offer);
+
+        assert_eq!(offers.total_amount(), U256::MAX);
+        assert_eq!(offers.count_revealed(), 2);
+    }
+
+    #[test]
+    fn test_cancel_offers_by_address() {
+        let offeror: Address = Address::random();
+        let other_offeror: Address = Address::random();
+        let mut offers: Offers = Offers::new();
+        for address in [offeror, other_offeror, offeror, other_offeror, offeror] {
+            let mut offer_submission: OfferSubmission = random_offer_submission();
+            offer_submission.offeror = address;
+            offers.save_or_update_order(&offer_submission);
+        }
+
+        // Only the orders of the offeror are removed
+        assert_eq!(offers.cancel_by_address(&offeror), 3);
+        assert_eq!(offers.len(), 2);
+        assert!(offers.values().all(|offer| offer.offeror == other_offeror));
+    }
+
     #[test]
     fn test_order_submissions_hash_chain() {
         // Random values
@@ -365,6 +703,216 @@ pub mod tests {
         assert_eq!(expected_offers, offers);
     }
 
+    #[test]
+    fn test_order_submissions_hash_chain_max_orders() {
+        let mut offer_submissions: OfferSubmissions =
+            (0..MAX_ORDERS).map(|_| random_offer_submission()).collect();
+        // Updating an order already placed does not count towards the limit
+        let mut update_submission: OfferSubmission = random_offer_submission();
+        update_submission.offeror = offer_submissions[0].offeror;
+        update_submission.id = offer_submissions[0].id;
+        offer_submissions.push(update_submission);
+
+        let mut offers: Offers = Offers::new();
+        offer_submissions.hash_chain(&|x: &[u8]| keccak256(x), B256::ZERO, &mut offers);
+        assert_eq!(offers.len(), MAX_ORDERS as usize);
+    }
+
+    #[test]
+    #[should_panic(expected = "Offer submissions exceed the maximum of")]
+    fn test_order_submissions_hash_chain_above_max_orders() {
+        let offer_submissions: OfferSubmissions = (0..=MAX_ORDERS)
+            .map(|_| random_offer_submission())
+            .collect();
+
+        offer_submissions.hash_chain(&|x: &[u8]| keccak256(x), B256::ZERO, &mut Offers::new());
+    }
+
+    #[test]
+    fn test_order_submissions_hash_chain_iter() {
+        let hash_function = |x: &[u8]| keccak256(x);
+        let start_value: B256 = B256::random();
+        let offer_submissions: OfferSubmissions =
+            (0..100).map(|_| random_offer_submission()).collect();
+
+        let mut expected_offers: Offers = Offers::new();
+        let expected_output: B256 =
+            offer_submissions.hash_chain(&hash_function, start_value, &mut expected_offers);
+
+        // Streaming the submissions one by one yields the same chain and orders
+        let mut offers: Offers = Offers::new();
+        let output: B256 = OfferSubmissions::hash_chain_iter(
+            offer_submissions.iter().take(100),
+            &hash_function,
+            start_value,
+            &mut offers,
+        );
+
+        assert_eq!(output, expected_output);
+        assert_eq!(offers, expected_offers);
+    }
+
+    #[test]
+    fn test_find_duplicate_keys() {
+        let mut offers_submissions: OfferSubmissions =
+            (0..3).map(|_| random_offer_submission()).collect();
+        assert!(offers_submissions.find_duplicate_keys().is_empty());
+
+        // Resubmitting under the same key is reported once, no matter how many times it repeats
+        let mut duplicate_submission: OfferSubmission = random_offer_submission();
+        duplicate_submission.offeror = offers_submissions[1].offeror;
+        duplicate_submission.id = offers_submissions[1].id;
+        offers_submissions.push(duplicate_submission.clone());
+        offers_submissions.push(duplicate_submission);
+
+        assert_eq!(
+            offers_submissions.find_duplicate_keys(),
+            vec![get_key(
+                &offers_submissions[1].offeror,
+                &offers_submissions[1].id
+            )]
+        );
+    }
+
+    #[test]
+    fn test_order_submissions_hash_chain_strict() {
+        let hash_function = |x: &[u8]| keccak256(x);
+        let start_value: B256 = B256::random();
+        let mut offers_submissions: OfferSubmissions =
+            (0..3).map(|_| random_offer_submission()).collect();
+
+        // Updates under a repeated key hash exactly like the non strict chain
+        let mut update_submission: OfferSubmission = random_offer_submission();
+        update_submission.offeror = offers_submissions[0].offeror;
+        update_submission.id = offers_submissions[0].id;
+        offers_submissions.push(update_submission);
+
+        let mut expected_offers: Offers = Offers::new();
+        let expected_output: B256 =
+            offers_submissions.hash_chain(&hash_function, start_value, &mut expected_offers);
+        let mut offers: Offers = Offers::new();
+        assert_eq!(
+            offers_submissions.hash_chain_strict(&hash_function, start_value, &mut offers),
+            Ok(expected_output)
+        );
+        assert_eq!(offers, expected_offers);
+
+        // A submission no onchain sender can have made is rejected without touching the orders
+        let mut zero_address_submission: OfferSubmission = random_offer_submission();
+        zero_address_submission.offeror = Address::ZERO;
+        offers_submissions.push(zero_address_submission.clone());
+
+        let mut offers: Offers = Offers::new();
+        assert_eq!(
+            offers_submissions.hash_chain_strict(&hash_function, start_value, &mut offers),
+            Err(SubmissionError::ZeroAddressSender(get_key(
+                &Address::ZERO,
+                &zero_address_submission.id
+            )))
+        );
+        assert!(offers.is_empty());
+    }
+
+    #[test]
+    fn test_hash_chain_with_order_stats() {
+        let hash_function = |x: &[u8]| keccak256(x);
+
+        // An offer is placed, updated, then cancelled by resubmitting it with a zero amount
+        let offer_submission: OfferSubmission = random_offer_submission();
+        let offer_submissions: OfferSubmissions = [1_000, 2_000, 0]
+            .into_iter()
+            .map(|amount| OfferSubmission {
+                amount: U256::from(amount),
+                ..offer_submission.clone()
+            })
+            .collect();
+
+        let mut offers: Offers = Offers::new();
+        let mut stats: OrderStats = OrderStats::default();
+        let output: B256 = offer_submissions.hash_chain_with_order_stats(
+            &hash_function,
+            B256::ZERO,
+            &mut offers,
+            &mut stats,
+        );
+
+        assert_eq!(
+            output,
+            offer_submissions.hash_chain(&hash_function, B256::ZERO, &mut Offers::new())
+        );
+        assert_eq!(
+            stats,
+            OrderStats {
+                created: 1,
+                updated: 1,
+                deleted: 1,
+            }
+        );
+        assert!(offers.is_empty());
+    }
+
+    #[test]
+    fn test_order_reveals_hash_chain_with_stats() {
+        let hash_function = |x: &[u8]| keccak256(x);
+
+        // Reveals keyed like their submissions all match a placed offer
+        let mut offer_reveals: OfferReveals = OfferReveals::new();
+        let offer_submissions: OfferSubmissions = (0..42)
+            .map(|_| {
+                let price: U256 = U256::from(rand::random::<u32>() % MAX_OFFER_PRICE);
+                let nonce: U256 = U256::from(rand::random::<u128>());
+                let offer_submission: OfferSubmission =
+                    valid_random_offer_submission(&price, &nonce);
+                offer_reveals.push(OfferReveal {
+                    orderId: get_key(&offer_submission.offeror, &offer_submission.id).into(),
+                    price,
+                    nonce,
+                });
+                offer_submission
+            })
+            .collect();
+        let mut offers: Offers = Offers::new();
+        offer_submissions.hash_chain(&hash_function, B256::ZERO, &mut offers);
+        let mut expected_offers: Offers = offers.clone();
+        let expected_output: B256 =
+            offer_reveals.hash_chain(&hash_function, B256::ZERO, &mut expected_offers);
+
+        let (output, stats) =
+            offer_reveals.hash_chain_with_stats(&hash_function, B256::ZERO, &mut offers);
+        assert_eq!(output, expected_output);
+        assert_eq!(offers, expected_offers);
+        assert_eq!(
+            stats,
+            RevealStats {
+                total: 42,
+                matched: 42,
+            }
+        );
+
+        // Reveals with random order IDs match none
+        let offer_submissions: OfferSubmissions =
+            (0..42).map(|_| random_offer_submission()).collect();
+        let offer_reveals: OfferReveals = (0..42)
+            .map(|_| OfferReveal {
+                orderId: U256::from(rand::random::<u64>()),
+                price: U256::from(rand::random::<u128>()),
+                nonce: U256::from(rand::random::<u128>()),
+            })
+            .collect();
+        let mut offers: Offers = Offers::new();
+        offer_submissions.hash_chain(&hash_function, B256::ZERO, &mut offers);
+
+        let (_, stats) =
+            offer_reveals.hash_chain_with_stats(&hash_function, B256::ZERO, &mut offers);
+        assert_eq!(
+            stats,
+            RevealStats {
+                total: 42,
+                matched: 0,
+            }
+        );
+    }
+
     #[test]
     fn test_order_reveals_hash_chain() {
         // Random values
@@ -416,6 +964,36 @@ pub mod tests {
         assert!(offers[1].offer_price_revealed <= offers[2].offer_price_revealed);
     }
 
+    #[test]
+    fn test_validated_offers_sort_orders_tiebreak() {
+        // Equal price orders, with some of them sharing amounts too
+        let price: U256 = U256::from(rand::random::<u32>() % 1_000);
+        let offers: ValidatedOffers = (0..24)
+            .map(|i| {
+                let mut offer: Offer = random_revealed_offer();
+                offer.offer_price_revealed = price;
+                offer.amount = U256::from(i % 4);
+                offer
+            })
+            .collect();
+
+        let mut sorted_offers: ValidatedOffers = offers.clone();
+        sorted_offers.sort_orders();
+        for pair in sorted_offers.windows(2) {
+            assert!(
+                pair[0].amount > pair[1].amount
+                    || (pair[0].amount == pair[1].amount
+                        && get_key(&pair[0].offeror, &pair[0].id)
+                            < get_key(&pair[1].offeror, &pair[1].id))
+            );
+        }
+
+        // Any input order sorts to the same result
+        let mut reversed_offers: ValidatedOffers = offers.into_iter().rev().collect();
+        reversed_offers.sort_orders();
+        assert_eq!(reversed_offers, sorted_offers);
+    }
+
     #[test]
     fn test_validated_offers_unlock_outstanding_orders() {
         let prover_address: Address = Address::random();
@@ -439,11 +1017,11 @@ pub mod tests {
     }
 
     /// Creates a random OfferSubmission with a valid offer price hash for the given price and nonce.
-    fn valid_random_offer_submission(price: &U256, nonce: &U256) -> OfferSubmission {
+    pub fn valid_random_offer_submission(price: &U256, nonce: &U256) -> OfferSubmission {
         OfferSubmission {
             offeror: Address::random(),
             id: U96::from(rand::random::<u64>()),
-            offerPriceHash: get_price_hash(&|x| keccak256(x), price, nonce),
+            offerPriceHash: get_price_hash(&|x: &[u8]| keccak256(x), price, nonce),
             amount: U256::from(rand::random::<u128>()),
         }
     }
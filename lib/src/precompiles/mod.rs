@@ -1,6 +1,51 @@
-use alloy_primitives::B256;
+#[cfg(feature = "poseidon")]
+use alloc::vec::Vec;
+use alloy_primitives::{keccak256, B256};
+#[cfg(feature = "poseidon")]
+use ark_bn254::Fr;
+#[cfg(feature = "poseidon")]
+use ark_ff::{BigInteger, PrimeField};
+#[cfg(feature = "poseidon")]
+use light_poseidon::{Poseidon, PoseidonHasher};
 use tiny_keccak::{Hasher, Keccak};
 
+/// The largest number of field elements hashed at once with the circom Poseidon parameters.
+#[cfg(feature = "poseidon")]
+const POSEIDON_MAX_INPUTS: usize = 12;
+/// The number of input bytes packed into each field element, so that every element stays below the BN254 modulus.
+#[cfg(feature = "poseidon")]
+const POSEIDON_BYTES_PER_ELEMENT: usize = 31;
+
+/// A function that computes a 32-byte hash from a byte slice.
+///
+/// Implemented for every `Fn(&[u8]) -> B256`, so plain functions like `sp1_keccak256` and closures can be passed
+/// wherever a `HashFunction` is expected. Implement it on a type to carry hasher specific configuration, or use
+/// `&dyn HashFunction` to select the hasher at runtime.
+pub trait HashFunction {
+    /// Computes the hash of the input bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - A slice of bytes to be hashed.
+    fn hash(&self, bytes: &[u8]) -> B256;
+}
+
+impl<F: Fn(&[u8]) -> B256> HashFunction for F {
+    fn hash(&self, bytes: &[u8]) -> B256 {
+        self(bytes)
+    }
+}
+
+/// Computes Keccak-256 hashes with `alloy_primitives::keccak256`, for use outside of a zkVM.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Keccak256;
+
+impl HashFunction for Keccak256 {
+    fn hash(&self, bytes: &[u8]) -> B256 {
+        keccak256(bytes)
+    }
+}
+
 /// Computes the Keccak-256 hash of the input bytes using [SP1's Keccak precompile](https://docs.succinct.xyz/writing-programs/precompiles.html).
 ///
 /// # Arguments
@@ -18,10 +63,65 @@ pub fn sp1_keccak256(bytes: &[u8]) -> B256 {
     output.into()
 }
 
+/// Computes the Poseidon hash of the input bytes over the BN254 scalar field, using the circom parameters.
+///
+/// Poseidon is far cheaper than Keccak-256 to prove in a circuit, so it can be used to hash the exit tree when its
+/// root is verified by one. The input bytes are packed into field elements as follows, and an onchain verifier must
+/// pack them the same way for the roots to match:
+///
+/// 1. The first element is the length of the input in bytes, so that inputs differing only by trailing zero bytes
+///    do not collide.
+/// 2. The input is split into chunks of 31 bytes, the last one possibly shorter, and each chunk is read as a
+///    big-endian integer.
+/// 3. The first 12 elements are hashed together, and each following group of up to 11 elements is hashed together
+///    with the previous hash, which goes first.
+///
+/// The pair of hashes combined into a tree node is 64 bytes long, so it is packed into 4 elements and hashed at once.
+///
+/// # Arguments
+///
+/// * `bytes` - A slice of bytes to be hashed.
+///
+/// # Returns
+///
+/// A 32-byte array containing the resulting field element, in big-endian order.
+#[cfg(feature = "poseidon")]
+pub fn poseidon_bn254(bytes: &[u8]) -> B256 {
+    let mut elements: Vec<Fr> = Vec::with_capacity(bytes.len() / POSEIDON_BYTES_PER_ELEMENT + 2);
+    elements.push(Fr::from(bytes.len() as u64));
+    elements.extend(
+        bytes
+            .chunks(POSEIDON_BYTES_PER_ELEMENT)
+            .map(Fr::from_be_bytes_mod_order),
+    );
+
+    let (first_elements, next_elements) =
+        elements.split_at(elements.len().min(POSEIDON_MAX_INPUTS));
+    let mut state: Fr = poseidon_hash_elements(first_elements);
+    for chunk in next_elements.chunks(POSEIDON_MAX_INPUTS - 1) {
+        state = poseidon_hash_elements(&[&[state], chunk].concat());
+    }
+
+    let mut output = [0u8; 32];
+    output.copy_from_slice(&state.into_bigint().to_bytes_be());
+    output.into()
+}
+
+/// Hashes between 1 and `POSEIDON_MAX_INPUTS` field elements with the circom Poseidon parameters.
+///
+/// # Arguments
+///
+/// * `elements` - The field elements to be hashed.
+#[cfg(feature = "poseidon")]
+fn poseidon_hash_elements(elements: &[Fr]) -> Fr {
+    Poseidon::<Fr>::new_circom(elements.len())
+        .and_then(|mut poseidon| poseidon.hash(elements))
+        .expect("The number of elements is supported by the circom parameters")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use alloy_primitives::keccak256;
 
     #[test]
     fn test_sp1_keccak256() {
@@ -31,4 +131,48 @@ mod tests {
         let output: B256 = sp1_keccak256(&input);
         assert_eq!(output, expected_output);
     }
+
+    #[test]
+    fn test_hash_function() {
+        let input: [u8; 32] = [1u8; 32];
+        let expected_output: B256 = keccak256(input);
+
+        // Plain functions, closures, and types implementing the trait are all hash functions
+        let hash_functions: [&dyn HashFunction; 3] =
+            [&sp1_keccak256, &|x: &[u8]| keccak256(x), &Keccak256];
+        for hash_function in hash_functions {
+            assert_eq!(hash_function.hash(&input), expected_output);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "poseidon")]
+    fn test_poseidon_bn254() {
+        // Matches the circom reference implementation
+        assert_eq!(
+            poseidon_hash_elements(&[Fr::from(1u64), Fr::from(2u64)])
+                .into_bigint()
+                .to_bytes_be(),
+            alloy_primitives::hex!(
+                "115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a"
+            )
+        );
+
+        // Inputs that only differ in trailing zero bytes, or that span several permutations, do not collide
+        let inputs: [&[u8]; 6] = [
+            &[],
+            &[0u8],
+            &[1u8; 31],
+            &[1u8; 32],
+            &[7u8; 400],
+            &[7u8; 401],
+        ];
+        for (i, input) in inputs.iter().enumerate() {
+            assert_eq!(poseidon_bn254(input), poseidon_bn254(input));
+            assert_ne!(poseidon_bn254(input), keccak256(input));
+            for other_input in inputs.iter().skip(i + 1) {
+                assert_ne!(poseidon_bn254(input), poseidon_bn254(other_input));
+            }
+        }
+    }
 }
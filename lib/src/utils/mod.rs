@@ -1,5 +1,10 @@
+#[cfg(feature = "std")]
+use crate::constants::PRICE_DECIMALS;
+use crate::{constants::PRICE_UNITS_PER_BPS, precompiles::HashFunction};
+use alloc::vec::Vec;
 use alloy_primitives::{aliases::U96, Address, B256, U256};
 use alloy_sol_types::SolValue;
+use core::{error, fmt, ops::RangeInclusive};
 
 /// Generates a unique identifying key for an order by combining an address and an ID.
 ///
@@ -18,6 +23,55 @@ pub fn get_key(address: &Address, id: &U96) -> B256 {
     B256::from(key)
 }
 
+/// Error returned by `order_key_from_u256` for an `orderId` that does not follow the `get_key` layout.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct OrderIdError(pub U256);
+
+impl fmt::Display for OrderIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "order ID {} does not follow the get_key layout, as it carries no address",
+            self.0
+        )
+    }
+}
+
+impl error::Error for OrderIdError {}
+
+/// Converts the `orderId` of an order reveal into the key of the order it reveals, checking that it follows the
+/// `get_key` layout.
+///
+/// Any 256-bit value splits into a 20-byte address followed by a 12-byte ID, so the layout is only broken by an
+/// `orderId` whose address bytes are all zero, such as a bare order ID that was not combined with its address.
+///
+/// # Arguments
+///
+/// * `id` - The `orderId` of the reveal.
+///
+/// # Returns
+///
+/// * `Result<B256, OrderIdError>` - The key of the order, or an error if the `orderId` does not carry an address.
+pub fn order_key_from_u256(id: &U256) -> Result<B256, OrderIdError> {
+    let key: B256 = B256::from(*id);
+    if key[0..20] == [0u8; 20] {
+        return Err(OrderIdError(*id));
+    }
+
+    Ok(key)
+}
+
+/// Returns the range spanning the keys of every order placed by an address.
+///
+/// Keys start with the address, so the orders of an address are contiguous in a `BTreeMap` keyed by `get_key`.
+///
+/// # Arguments
+///
+/// * `address` - The Ethereum address of the bidder or offeror.
+pub fn get_key_range(address: &Address) -> RangeInclusive<B256> {
+    get_key(address, &U96::ZERO)..=get_key(address, &U96::MAX)
+}
+
 /// Calculates the price hash by hashing together the revealed price and nonce.
 ///
 /// # Arguments
@@ -28,8 +82,12 @@ pub fn get_key(address: &Address, id: &U96) -> B256 {
 /// # Returns
 ///
 /// A `B256` value representing the price hash, which is the Keccak-256 hash of the price and nonce.
-pub fn get_price_hash<F: Fn(&[u8]) -> B256>(hash_function: &F, price: &U256, nonce: &U256) -> B256 {
-    hash_function(
+pub fn get_price_hash<F: HashFunction + ?Sized>(
+    hash_function: &F,
+    price: &U256,
+    nonce: &U256,
+) -> B256 {
+    hash_function.hash(
         &[
             &price.to_be_bytes::<32>()[..],
             &nonce.to_be_bytes::<32>()[..],
@@ -38,6 +96,102 @@ pub fn get_price_hash<F: Fn(&[u8]) -> B256>(hash_function: &F, price: &U256, non
     )
 }
 
+/// Checks whether a revealed price and nonce are the preimage of a price hash, and the price is within bounds.
+///
+/// Does not touch any order, so a reveal can be checked before it is submitted onchain.
+///
+/// # Arguments
+///
+/// * `hash_function` - A function that computes a 32-byte hash from a byte slice.
+/// * `price` - The price being revealed, as a percentage with 9 decimal places.
+/// * `nonce` - The nonce the price was hashed with.
+/// * `expected_hash` - The price hash that was submitted.
+/// * `max_price_bps` - The maximum price that can be revealed, in basis points.
+///
+/// # Returns
+///
+/// `true` if the price hash matches and the price does not exceed `max_price_bps`.
+pub fn verify_price_reveal<F: HashFunction + ?Sized>(
+    hash_function: &F,
+    price: &U256,
+    nonce: &U256,
+    expected_hash: &B256,
+    max_price_bps: u32,
+) -> bool {
+    let hash_matches: bool = if cfg!(feature = "constant-time") {
+        ct_eq(&get_price_hash(hash_function, price, nonce), expected_hash)
+    } else {
+        get_price_hash(hash_function, price, nonce) == *expected_hash
+    };
+
+    hash_matches && *price <= bps_to_internal(&U256::from(max_price_bps))
+}
+
+/// Compares two hashes in constant time.
+///
+/// Unlike `==`, every byte is compared no matter where the first difference is, so the time taken does not leak how
+/// much of a price hash a guessed reveal got right. Reveals are compared with it when the `constant-time` feature is
+/// enabled, which is meant for reveals validated outside of a zkVM, where there is no timing to observe.
+///
+/// # Arguments
+///
+/// * `a` - The first hash.
+/// * `b` - The second hash.
+///
+/// # Returns
+///
+/// `true` if both hashes are equal.
+pub fn ct_eq(a: &B256, b: &B256) -> bool {
+    let difference: u8 = a
+        .iter()
+        .zip(b.iter())
+        .fold(0u8, |difference, (x, y)| difference | (x ^ y));
+
+    // Keeps the compiler from turning the fold back into an early exit
+    core::hint::black_box(difference) == 0
+}
+
+/// Converts a price in basis points into the internal price representation, a percentage with 9 decimal places.
+///
+/// # Arguments
+///
+/// * `bps` - The price in basis points.
+pub fn bps_to_internal(bps: &U256) -> U256 {
+    bps * U256::from(PRICE_UNITS_PER_BPS)
+}
+
+/// Converts an internal price, a percentage with 9 decimal places, into basis points, rounding down.
+///
+/// # Arguments
+///
+/// * `price` - The internal price.
+pub fn internal_to_bps(price: &U256) -> U256 {
+    price / U256::from(PRICE_UNITS_PER_BPS)
+}
+
+/// Converts a price in basis points into the internal price representation, a percentage with `PRICE_DECIMALS`
+/// decimal places.
+///
+/// # Arguments
+///
+/// * `bps` - The price in basis points.
+pub fn price_from_bps(bps: u32) -> U256 {
+    bps_to_internal(&U256::from(bps))
+}
+
+/// Converts an internal price into a percentage, so that `price_from_bps(250)` becomes `2.5`.
+///
+/// The conversion is lossy, so the result is only meant for display and must never be fed back into the auction.
+/// Converting into floating point requires `std`.
+///
+/// # Arguments
+///
+/// * `price` - The internal price.
+#[cfg(feature = "std")]
+pub fn price_to_percentage(price: &U256) -> f64 {
+    f64::from(price) / 10f64.powi(PRICE_DECIMALS as i32)
+}
+
 /// Adds an item to a hash chain by combining it with the previous accumulator value.
 ///
 /// # Arguments
@@ -56,16 +210,73 @@ pub fn get_price_hash<F: Fn(&[u8]) -> B256>(hash_function: &F, price: &U256, non
 /// * `S`: The type of the item being added to the hash chain, which must implement `SolValue`.
 pub fn add_to_hash_chain<F, S>(hash_function: &F, item: &S, acc: &B256) -> B256
 where
-    F: Fn(&[u8]) -> B256,
+    F: HashFunction + ?Sized,
     S: SolValue,
 {
     let encoded_item: Vec<u8> = item.abi_encode_packed();
     let input: Vec<u8> = [&acc[..], &encoded_item].concat();
-    hash_function(&input)
+    hash_function.hash(&input)
+}
+
+/// Folds every item into a hash chain, in order, starting from `start_value`.
+///
+/// # Arguments
+///
+/// * `hash_function` - A function that computes a 32-byte hash from a byte slice.
+/// * `items` - The items to be added to the hash chain, in the order they were placed onchain.
+/// * `start_value` - The initial 32-byte value to start the hash chain.
+///
+/// # Returns
+///
+/// The final `B256` hash of the chain, or `start_value` if there are no items.
+pub fn fold_hash_chain<'a, F, S, I>(hash_function: &F, items: I, start_value: B256) -> B256
+where
+    F: HashFunction + ?Sized,
+    S: SolValue + 'a,
+    I: IntoIterator<Item = &'a S>,
+{
+    items.into_iter().fold(start_value, |acc: B256, item: &S| {
+        add_to_hash_chain(hash_function, item, &acc)
+    })
+}
+
+/// Cycle tracker label for computing the bids hash chain.
+pub const BID_HASH_CHAIN_CYCLES: &str = "bid_hash_chain";
+/// Cycle tracker label for computing the offers hash chain.
+pub const OFFER_HASH_CHAIN_CYCLES: &str = "offer_hash_chain";
+/// Cycle tracker label for validating, clearing and assigning the orders.
+pub const CLEARING_CYCLES: &str = "clearing";
+/// Cycle tracker label for building and hashing the exit tree.
+pub const EXIT_TREE_HASHING_CYCLES: &str = "exit_tree_hashing";
+
+/// Runs `f` inside an [SP1 cycle tracker](https://docs.succinct.xyz/writing-programs/cycle-tracking.html) region,
+/// so that the cycles it takes are reported under `label` when executing the program.
+///
+/// The annotations are only emitted inside the zkVM, elsewhere this simply calls `f`.
+///
+/// # Arguments
+///
+/// * `label` - The name the cycles are reported under.
+/// * `f` - The step being tracked.
+///
+/// # Returns
+///
+/// The output of `f`.
+pub fn track_cycles<T>(label: &str, f: impl FnOnce() -> T) -> T {
+    #[cfg(all(target_os = "zkvm", feature = "std"))]
+    println!("cycle-tracker-report-start: {}", label);
+    let output: T = f();
+    #[cfg(all(target_os = "zkvm", feature = "std"))]
+    println!("cycle-tracker-report-end: {}", label);
+    #[cfg(not(all(target_os = "zkvm", feature = "std")))]
+    let _ = label;
+
+    output
 }
 
 // TEST HELPER FUNCTIONS
 pub mod test {
+    use alloc::vec::Vec;
     use alloy_primitives::{keccak256, B256};
     use alloy_sol_types::{sol, SolValue};
 
@@ -88,3 +299,150 @@ pub mod test {
         expected_output
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "std")]
+    use crate::constants::MAX_OFFER_PRICE;
+    use crate::constants::{BPS, MAX_BID_PRICE, PRICE_DECIMALS};
+    use alloy_primitives::keccak256;
+
+    #[test]
+    fn test_order_key_from_u256() {
+        // A key built by `get_key` converts back into itself
+        let key: B256 = get_key(&Address::random(), &U96::from(rand::random::<u64>()));
+        assert_eq!(order_key_from_u256(&key.into()), Ok(key));
+
+        // A bare order ID carries no address
+        let id: U96 = U96::from(rand::random::<u64>());
+        assert_eq!(
+            order_key_from_u256(&U256::from(id)),
+            Err(OrderIdError(U256::from(id)))
+        );
+        assert_eq!(
+            order_key_from_u256(&U256::ZERO),
+            Err(OrderIdError(U256::ZERO))
+        );
+    }
+
+    #[test]
+    fn test_ct_eq() {
+        let hash: B256 = B256::random();
+        assert!(ct_eq(&hash, &hash));
+        assert!(ct_eq(&B256::ZERO, &B256::ZERO));
+
+        // Agrees with `==` wherever the hashes differ
+        for index in 0..32 {
+            let mut other_hash: B256 = hash;
+            other_hash[index] ^= 1 << (index % 8);
+            assert_eq!(ct_eq(&hash, &other_hash), hash == other_hash);
+            assert!(!ct_eq(&hash, &other_hash));
+        }
+        let other_hash: B256 = B256::random();
+        assert_eq!(ct_eq(&hash, &other_hash), hash == other_hash);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_price_to_percentage() {
+        for (bps, percentage) in [
+            (0, 0.0),
+            (1, 0.01),
+            (50, 0.5),
+            (100, 1.0),
+            (250, 2.5),
+            (10_000, 100.0),
+        ] {
+            assert_eq!(price_to_percentage(&price_from_bps(bps)), percentage);
+        }
+
+        // The highest prices accepted are 10,000%
+        assert_eq!(
+            price_to_percentage(&price_from_bps(MAX_BID_PRICE)),
+            10_000.0
+        );
+        assert_eq!(
+            price_to_percentage(&price_from_bps(MAX_OFFER_PRICE)),
+            10_000.0
+        );
+    }
+
+    #[test]
+    fn test_price_decimals() {
+        // A percentage is a hundred basis points
+        assert_eq!(
+            U256::from(PRICE_UNITS_PER_BPS) * U256::from(BPS),
+            U256::from(100) * U256::from(10).pow(U256::from(PRICE_DECIMALS))
+        );
+
+        for (bps, price) in [
+            (0, 0u64),
+            (1, 10_000_000),
+            (50, 500_000_000),
+            (100, 1_000_000_000),
+            (250, 2_500_000_000),
+            (10_000, 100_000_000_000),
+        ] {
+            assert_eq!(price_from_bps(bps), U256::from(price));
+            assert_eq!(price_from_bps(bps), bps_to_internal(&U256::from(bps)));
+            assert_eq!(internal_to_bps(&price_from_bps(bps)), U256::from(bps));
+        }
+
+        let price: U256 = price_from_bps(MAX_BID_PRICE);
+        let nonce: U256 = U256::from(rand::random::<u128>());
+        let hash: B256 = get_price_hash(&|x: &[u8]| keccak256(x), &price, &nonce);
+        assert!(verify_price_reveal(
+            &|x: &[u8]| keccak256(x),
+            &price,
+            &nonce,
+            &hash,
+            MAX_BID_PRICE
+        ));
+        let price: U256 = price_from_bps(MAX_BID_PRICE + 1);
+        let hash: B256 = get_price_hash(&|x: &[u8]| keccak256(x), &price, &nonce);
+        assert!(!verify_price_reveal(
+            &|x: &[u8]| keccak256(x),
+            &price,
+            &nonce,
+            &hash,
+            MAX_BID_PRICE
+        ));
+    }
+
+    #[test]
+    fn test_verify_price_reveal() {
+        let price: U256 = bps_to_internal(&U256::from(rand::random::<u32>() % MAX_BID_PRICE));
+        let nonce: U256 = U256::from(rand::random::<u128>());
+        let price_hash: B256 = get_price_hash(&|x: &[u8]| keccak256(x), &price, &nonce);
+
+        // Matching preimage
+        assert!(verify_price_reveal(
+            &|x: &[u8]| keccak256(x),
+            &price,
+            &nonce,
+            &price_hash,
+            MAX_BID_PRICE
+        ));
+
+        // Wrong nonce
+        assert!(!verify_price_reveal(
+            &|x: &[u8]| keccak256(x),
+            &price,
+            &(nonce + U256::from(1)),
+            &price_hash,
+            MAX_BID_PRICE
+        ));
+
+        // Matching preimage with an out of bounds price
+        let price: U256 = bps_to_internal(&U256::from(MAX_BID_PRICE)) + U256::from(1);
+        let price_hash: B256 = get_price_hash(&|x: &[u8]| keccak256(x), &price, &nonce);
+        assert!(!verify_price_reveal(
+            &|x: &[u8]| keccak256(x),
+            &price,
+            &nonce,
+            &price_hash,
+            MAX_BID_PRICE
+        ));
+    }
+}
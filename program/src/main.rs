@@ -14,7 +14,7 @@ use zkauction_lib::{
         offers::{OfferReveals, OfferSubmissions},
     },
     precompiles::sp1_keccak256,
-    run_auction, PublicValuesStruct,
+    run_auction, AuctionOutput, PublicValuesStruct,
 };
 
 /// The main function of the program, reads the auction inputs, computes the auction results commitment,
@@ -34,7 +34,7 @@ pub fn main() {
     let tokens: AuctionParameters = sp1_zkvm::io::read::<AuctionParameters>();
 
     // Compute public values encoding the auction and its results
-    let (acc_bids_hash, acc_offers_hash, token_prices_hash, auction_result_root) = run_auction(
+    let auction_output: AuctionOutput = run_auction(
         &sp1_keccak256,
         &prover_address,
         &bid_submissions,
@@ -42,16 +42,12 @@ pub fn main() {
         &bid_reveals,
         &offer_reveals,
         &tokens,
-    );
+    )
+    .expect("Failed to run the auction");
 
     // Encode the public values of the program.
-    let bytes = PublicValuesStruct::abi_encode(&PublicValuesStruct {
-        proverAddress: prover_address,
-        accBidsHash: acc_bids_hash,
-        accOffersHash: acc_offers_hash,
-        auctionParametersHash: token_prices_hash,
-        auctionResultRoot: auction_result_root,
-    });
+    let bytes =
+        PublicValuesStruct::abi_encode(&PublicValuesStruct::new(prover_address, auction_output));
 
     // Commit to the public values of the program. The final proof will have a commitment to all the
     // bytes that were committed to.
@@ -9,17 +9,33 @@
 //! ```shell
 //! RUST_LOG=info cargo run --release -- --prove
 //! ```
+//! Adding `--profile` to `--execute` also prints the cycles taken by each step of the auction as JSON, and adding
+//! `--report` prints whether every order was filled, partially filled or refunded. Adding `--system plonk` or
+//! `--system groth16` to `--prove` generates an EVM compatible proof instead, and saves it as a contracts fixture.
 
 use alloy_primitives::keccak256;
 use alloy_sol_types::SolType;
 use clap::Parser;
-use sp1_sdk::{ProverClient, SP1Stdin};
-use zkauction_lib::PublicValuesStruct;
+use serde::Serialize;
+use sp1_sdk::{ExecutionReport, ProverClient, SP1ProofWithPublicValues, SP1Stdin};
+use zkauction_lib::{
+    auction::report::AuctionReport,
+    exit_tree::ExitLeaf,
+    utils::{
+        BID_HASH_CHAIN_CYCLES, CLEARING_CYCLES, EXIT_TREE_HASHING_CYCLES, OFFER_HASH_CHAIN_CYCLES,
+    },
+    PublicValuesStruct,
+};
+
+use fixture::{build_fixture, save_fixture, ProofSystem, SP1zkAuctionProofFixture};
 
 // Adjust this path based on the actual location of input.rs
 #[path = "../lib/input.rs"]
 mod input;
 
+#[path = "../lib/fixture.rs"]
+mod fixture;
+
 /// The ELF (executable and linkable format) file for the Succinct RISC-V zkVM.
 pub const ZK_AUCTION_ELF: &[u8] = include_bytes!("../../../elf/riscv32im-succinct-zkvm-elf");
 
@@ -33,8 +49,47 @@ struct Args {
     #[clap(long)]
     prove: bool,
 
+    #[clap(long)]
+    profile: bool,
+
+    #[clap(long)]
+    report: bool,
+
+    /// The proof system of the EVM compatible proof generated alongside `--prove`.
+    #[clap(long, value_enum)]
+    system: Option<ProofSystem>,
+
     #[clap(long, default_value = "20")]
     n: u32,
+
+    /// The number of bids, and of offers, placed in the generated auction.
+    #[clap(long, default_value_t = input::DEFAULT_NUM_ORDERS)]
+    num_orders: usize,
+}
+
+/// The number of cycles taken by each major step of the auction.
+#[derive(Serialize, Debug)]
+struct CycleProfile {
+    bid_hash_chain: u64,
+    offer_hash_chain: u64,
+    clearing: u64,
+    exit_tree_hashing: u64,
+    total: u64,
+}
+
+impl CycleProfile {
+    /// Reads the cycle tracker regions reported by `run_auction` out of an execution report.
+    fn from_report(report: &ExecutionReport) -> Self {
+        let cycles = |label: &str| report.cycle_tracker.get(label).copied().unwrap_or_default();
+
+        Self {
+            bid_hash_chain: cycles(BID_HASH_CHAIN_CYCLES),
+            offer_hash_chain: cycles(OFFER_HASH_CHAIN_CYCLES),
+            clearing: cycles(CLEARING_CYCLES),
+            exit_tree_hashing: cycles(EXIT_TREE_HASHING_CYCLES),
+            total: report.total_instruction_count(),
+        }
+    }
 }
 
 fn main() {
@@ -49,13 +104,28 @@ fn main() {
         std::process::exit(1);
     }
 
+    if args.profile && !args.execute {
+        eprintln!("Error: --profile can only be used alongside --execute");
+        std::process::exit(1);
+    }
+
+    if args.report && !args.execute {
+        eprintln!("Error: --report can only be used alongside --execute");
+        std::process::exit(1);
+    }
+
+    if args.system.is_some() && !args.prove {
+        eprintln!("Error: --system can only be used alongside --prove");
+        std::process::exit(1);
+    }
+
     // Setup the prover client.
     let client: ProverClient = ProverClient::new();
 
     // Setup the inputs.
     let mut stdin: SP1Stdin = SP1Stdin::new();
     let (_prover_address, bid_submissions, offer_submissions, bid_reveals, offer_reveals, tokens) =
-        input::set_inputs(&mut stdin);
+        input::set_inputs(&mut stdin, args.num_orders);
 
     if args.execute {
         // Execute the program
@@ -65,25 +135,29 @@ fn main() {
         // Read the output.
         let decoded: PublicValuesStruct =
             PublicValuesStruct::abi_decode(output.as_slice(), true).unwrap();
+        println!("{}", decoded);
         let PublicValuesStruct {
             proverAddress: prover_address,
             accBidsHash: acc_bids_hash,
             accOffersHash: acc_offers_hash,
             auctionParametersHash: token_prices_hash,
+            constantsCommitment: constants_commitment,
             auctionResultRoot: auction_result_root,
+            clearingPrice: clearing_price,
+            clearedVolume: cleared_volume,
         } = decoded;
-        println!("proverAddress: {}", prover_address);
-        println!("accBidsHash: {}", acc_bids_hash);
-        println!("accOffersHash: {}", acc_offers_hash);
-        println!("auctionParametersHash: {}", token_prices_hash);
-        println!("auctionResultRoot: {}", auction_result_root);
 
         let (
-            expected_acc_bids_hash,
-            expected_acc_offers_hash,
-            expected_token_prices_hash,
-            expected_auction_result_root,
-        ) = zkauction_lib::run_auction(
+            (
+                expected_acc_bids_hash,
+                expected_acc_offers_hash,
+                expected_token_prices_hash,
+                expected_auction_result_root,
+                clearing_outcome,
+                expected_constants_commitment,
+            ),
+            exit_leaves,
+        ) = zkauction_lib::run_auction_with_results(
             &|x: &[u8]| keccak256(x),
             &prover_address,
             &bid_submissions,
@@ -91,29 +165,105 @@ fn main() {
             &bid_reveals,
             &offer_reveals,
             &tokens,
-        );
+        )
+        .expect("Failed to run the auction");
         assert_eq!(acc_bids_hash, expected_acc_bids_hash);
         assert_eq!(acc_offers_hash, expected_acc_offers_hash);
         assert_eq!(token_prices_hash, expected_token_prices_hash);
         assert_eq!(auction_result_root, expected_auction_result_root);
+        assert_eq!(constants_commitment, expected_constants_commitment);
+        assert_eq!(clearing_price, clearing_outcome.price());
+        assert_eq!(cleared_volume, clearing_outcome.volume());
         println!("Values are correct!");
+        println!("Clearing outcome: {:?}", clearing_outcome);
+
+        // The generated auction clears, so some bids must have been assigned
+        assert!(
+            exit_leaves
+                .iter()
+                .any(|exit_leaf| matches!(exit_leaf, ExitLeaf::RepurchaseObligation(_))),
+            "The auction did not assign any orders"
+        );
 
         // Record the number of cycles executed.
         println!("Number of cycles: {}", report.total_instruction_count());
+
+        if args.profile {
+            let cycle_profile: CycleProfile = CycleProfile::from_report(&report);
+            println!("{}", serde_json::to_string_pretty(&cycle_profile).unwrap());
+        }
+
+        if args.report {
+            let auction_report: AuctionReport = zkauction_lib::report_auction(
+                &|x: &[u8]| keccak256(x),
+                &bid_submissions,
+                &offer_submissions,
+                &bid_reveals,
+                &offer_reveals,
+                &tokens,
+            )
+            .expect("Failed to run the auction");
+            println!("{}", serde_json::to_string_pretty(&auction_report).unwrap());
+        }
     } else {
         // Setup the program for proving.
         let (pk, vk) = client.setup(ZK_AUCTION_ELF);
 
         // Generate the proof
-        let proof: sp1_sdk::SP1ProofWithPublicValues = client
-            .prove(&pk, stdin)
-            .run()
-            .expect("failed to generate proof");
+        let proof: SP1ProofWithPublicValues = match args.system {
+            None => client.prove(&pk, stdin).run(),
+            Some(ProofSystem::Plonk) => client.prove(&pk, stdin).plonk().run(),
+            Some(ProofSystem::Groth16) => client.prove(&pk, stdin).groth16().run(),
+        }
+        .expect("failed to generate proof");
 
         println!("Successfully generated proof!");
 
         // Verify the proof.
         client.verify(&proof, &vk).expect("failed to verify proof");
         println!("Successfully verified proof!");
+
+        // EVM compatible proofs can be verified onchain, so they are saved as a fixture
+        if let Some(system) = args.system {
+            let fixture: SP1zkAuctionProofFixture = build_fixture(&proof, &vk);
+            save_fixture(&fixture, system);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zkauction_lib::orders::{bids::BidReveals, offers::OfferReveals};
+
+    /// Executes the program, so it needs the ELF to be built and is only run on demand with `cargo test -- --ignored`.
+    #[test]
+    #[ignore]
+    fn test_execute_without_reveals_is_cheaper() {
+        let client: ProverClient = ProverClient::new();
+        let mut stdin: SP1Stdin = SP1Stdin::new();
+        let (prover_address, bid_submissions, offer_submissions, _, _, tokens) =
+            input::set_inputs(&mut stdin, 100);
+        let (_, report) = client.execute(ZK_AUCTION_ELF, stdin).run().unwrap();
+
+        // The same orders are placed, but none of them is revealed
+        let mut stdin_without_reveals: SP1Stdin = SP1Stdin::new();
+        stdin_without_reveals.write(&prover_address);
+        stdin_without_reveals.write(&bid_submissions);
+        stdin_without_reveals.write(&offer_submissions);
+        stdin_without_reveals.write(&BidReveals::new());
+        stdin_without_reveals.write(&OfferReveals::new());
+        stdin_without_reveals.write(&tokens);
+        let (_, report_without_reveals) = client
+            .execute(ZK_AUCTION_ELF, stdin_without_reveals)
+            .run()
+            .unwrap();
+
+        // Every order is invalid, so nothing is sorted nor cleared
+        let cycle_profile: CycleProfile = CycleProfile::from_report(&report);
+        let cycle_profile_without_reveals: CycleProfile =
+            CycleProfile::from_report(&report_without_reveals);
+        assert!(cycle_profile_without_reveals.clearing < cycle_profile.clearing);
+        assert!(cycle_profile_without_reveals.total < cycle_profile.total);
     }
 }
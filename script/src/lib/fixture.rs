@@ -0,0 +1,180 @@
+use alloy_sol_types::SolType;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use sp1_sdk::{HashableKey, SP1ProofWithPublicValues, SP1VerifyingKey};
+use std::path::PathBuf;
+use zkauction_lib::PublicValuesStruct;
+
+/// Enum representing the available proof systems
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug)]
+pub enum ProofSystem {
+    Plonk,
+    Groth16,
+}
+
+/// A fixture that can be used to test the verification of SP1 zkVM proofs inside Solidity.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SP1zkAuctionProofFixture {
+    pub prover_address: String,
+    pub acc_bids_hash: String,
+    pub acc_offers_hash: String,
+    pub token_prices_hash: String,
+    pub constants_commitment: String,
+    pub auction_result_root: String,
+    pub clearing_price: String,
+    pub cleared_volume: String,
+    pub vkey: String,
+    pub public_values: String,
+    pub proof: String,
+}
+
+/// Builds the fixture for an EVM compatible proof.
+///
+/// # Arguments
+///
+/// * `proof` - The proof, generated with either of the `ProofSystem`s.
+/// * `vk` - The verification key of the program.
+pub fn build_fixture(
+    proof: &SP1ProofWithPublicValues,
+    vk: &SP1VerifyingKey,
+) -> SP1zkAuctionProofFixture {
+    fixture_from_parts(proof.public_values.as_slice(), vk.bytes32(), &proof.bytes())
+}
+
+/// Builds a fixture out of the ABI encoded public values, the verification key and the proof bytes.
+///
+/// # Arguments
+///
+/// * `public_values` - The ABI encoded `PublicValuesStruct` committed by the program.
+/// * `vkey` - The verification key of the program, as a hex string.
+/// * `proof_bytes` - The proof, as verified onchain.
+fn fixture_from_parts(
+    public_values: &[u8],
+    vkey: String,
+    proof_bytes: &[u8],
+) -> SP1zkAuctionProofFixture {
+    let PublicValuesStruct {
+        proverAddress,
+        accBidsHash,
+        accOffersHash,
+        auctionParametersHash,
+        constantsCommitment,
+        auctionResultRoot,
+        clearingPrice,
+        clearedVolume,
+    } = PublicValuesStruct::abi_decode(public_values, false).unwrap();
+
+    SP1zkAuctionProofFixture {
+        prover_address: proverAddress.to_string(),
+        acc_bids_hash: accBidsHash.to_string(),
+        acc_offers_hash: accOffersHash.to_string(),
+        token_prices_hash: auctionParametersHash.to_string(),
+        constants_commitment: constantsCommitment.to_string(),
+        auction_result_root: auctionResultRoot.to_string(),
+        clearing_price: clearingPrice.to_string(),
+        cleared_volume: clearedVolume.to_string(),
+        vkey,
+        public_values: format!("0x{}", hex::encode(public_values)),
+        proof: format!("0x{}", hex::encode(proof_bytes)),
+    }
+}
+
+/// Prints the fixture and saves it to the contracts fixtures, named after the proof system it was generated with.
+///
+/// # Arguments
+///
+/// * `fixture` - The fixture to save.
+/// * `system` - The proof system the proof of the fixture was generated with.
+pub fn save_fixture(fixture: &SP1zkAuctionProofFixture, system: ProofSystem) {
+    // The verification key is used to verify that the proof corresponds to the execution of the
+    // program on the given input.
+    //
+    // Note that the verification key stays the same regardless of the input.
+    println!("Verification Key: {}", fixture.vkey);
+
+    // The public values are the values which are publicly committed to by the zkVM.
+    //
+    // If you need to expose the inputs or outputs of your program, you should commit them in
+    // the public values.
+    println!("Public Values: {}", fixture.public_values);
+
+    // The proof proves to the verifier that the program was executed with some inputs that led to
+    // the give public values.
+    println!("Proof Bytes: {}", fixture.proof);
+
+    // Save the fixture to a file.
+    let fixture_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../contracts/src/fixtures");
+    std::fs::create_dir_all(&fixture_path).expect("failed to create fixture path");
+    std::fs::write(
+        fixture_path.join(format!("{:?}-fixture.json", system).to_lowercase()),
+        serde_json::to_string_pretty(fixture).unwrap(),
+    )
+    .expect("failed to write fixture");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{Address, B256, U256};
+
+    #[test]
+    fn test_fixture_from_parts() {
+        let public_values: PublicValuesStruct = PublicValuesStruct {
+            proverAddress: Address::random(),
+            accBidsHash: B256::random(),
+            accOffersHash: B256::random(),
+            auctionParametersHash: B256::random(),
+            constantsCommitment: B256::random(),
+            auctionResultRoot: B256::random(),
+            clearingPrice: U256::from(rand::random::<u64>()),
+            clearedVolume: U256::from(rand::random::<u64>()),
+        };
+        let encoded_public_values: Vec<u8> = PublicValuesStruct::abi_encode(&public_values);
+        // A mock proof, whose bytes are never verified here
+        let proof_bytes: Vec<u8> = (0..260).map(|_| rand::random::<u8>()).collect();
+        let vkey: String = B256::random().to_string();
+
+        let fixture: SP1zkAuctionProofFixture =
+            fixture_from_parts(&encoded_public_values, vkey.clone(), &proof_bytes);
+
+        // Every hash is a 0x prefixed 32 byte hex string
+        for hash in [
+            &fixture.acc_bids_hash,
+            &fixture.acc_offers_hash,
+            &fixture.token_prices_hash,
+            &fixture.constants_commitment,
+            &fixture.auction_result_root,
+            &fixture.vkey,
+        ] {
+            assert_eq!(hash.len(), 66);
+            assert_eq!(
+                hex::decode(hash.strip_prefix("0x").unwrap()).unwrap().len(),
+                32
+            );
+        }
+        assert_eq!(fixture.vkey, vkey);
+        assert_eq!(
+            fixture.prover_address.parse::<Address>().unwrap(),
+            public_values.proverAddress
+        );
+        assert_eq!(
+            fixture.clearing_price,
+            public_values.clearingPrice.to_string()
+        );
+        assert_eq!(
+            fixture.cleared_volume,
+            public_values.clearedVolume.to_string()
+        );
+
+        // The public values and the proof round trip through their hex encoding
+        assert_eq!(
+            hex::decode(fixture.public_values.strip_prefix("0x").unwrap()).unwrap(),
+            encoded_public_values
+        );
+        assert_eq!(
+            hex::decode(fixture.proof.strip_prefix("0x").unwrap()).unwrap(),
+            proof_bytes
+        );
+    }
+}
@@ -1,16 +1,32 @@
-use alloy_primitives::{aliases::U96, Address, B256, U256};
+use alloy_primitives::{keccak256, Address, U256};
 use sp1_sdk::SP1Stdin;
 use zkauction_lib::{
-    auction_parameters::AuctionParameters,
+    auction_builder::{AuctionBuilder, AuctionInputs},
+    auction_parameters::{AuctionMode, AuctionParameters, DayCountConvention},
+    constants::{MAX_DAY_COUNT, MIN_ORDER_AMOUNT},
     orders::{
-        bids::{BidReveal, BidReveals, BidSubmission, BidSubmissions},
-        offers::{OfferReveal, OfferReveals, OfferSubmission, OfferSubmissions},
+        bids::{BidReveals, BidSubmissions},
+        offers::{OfferReveals, OfferSubmissions},
     },
+    utils::bps_to_internal,
 };
 
-/// Reads the provided auction inputs and sets them in the provided stdin.
+/// The number of bids, and of offers, placed by default.
+pub const DEFAULT_NUM_ORDERS: usize = 1000;
+
+/// Generates an auction that clears and sets its inputs in the provided stdin.
+///
+/// Every order is submitted with the hash of its price and then revealed. Bid prices range from 1% to 20% and offer
+/// prices from 0.5% to 15%, so the most competitive orders cross and get assigned, while every bid locks twice the
+/// value of its amount as collateral.
+///
+/// # Arguments
+///
+/// * `stdin` - The stdin the inputs are written to.
+/// * `num_orders` - The number of bids, and of offers, placed.
 pub fn set_inputs(
     stdin: &mut SP1Stdin,
+    num_orders: usize,
 ) -> (
     Address,
     BidSubmissions,
@@ -19,48 +35,43 @@ pub fn set_inputs(
     OfferReveals,
     AuctionParameters,
 ) {
-    let num_offers: i32 = 1000;
-
     let prover_address = Address::random();
-    let bid_submissions: BidSubmissions = (0..num_offers)
-        .map(|_| BidSubmission {
-            bidder: Address::random(),
-            id: U96::from(rand::random::<u64>()),
-            bidPriceHash: B256::random(),
-            amount: U256::from(rand::random::<u128>()),
-            collateralAmount: U256::from(rand::random::<u128>()),
-        })
-        .collect();
-    let offer_submissions: OfferSubmissions = (0..num_offers)
-        .map(|_| OfferSubmission {
-            offeror: Address::random(),
-            id: U96::from(rand::random::<u64>()),
-            offerPriceHash: B256::random(),
-            amount: U256::from(rand::random::<u128>()),
-        })
-        .collect();
-    let bid_reveals: BidReveals = (0..num_offers)
-        .map(|_| BidReveal {
-            orderId: U256::from(rand::random::<u64>()),
-            price: U256::from(rand::random::<u128>()),
-            nonce: U256::from(rand::random::<u128>()),
-        })
-        .collect();
-    let offer_reveals: OfferReveals = (0..num_offers)
-        .map(|_| OfferReveal {
-            orderId: U256::from(rand::random::<u64>()),
-            price: U256::from(rand::random::<u128>()),
-            nonce: U256::from(rand::random::<u128>()),
-        })
-        .collect();
+    let purchase_price: U256 = U256::from(rand::random::<u32>().max(1));
+    let collateral_price: U256 = U256::from(rand::random::<u32>().max(1));
     let tokens: AuctionParameters = AuctionParameters {
         purchaseToken: Address::random(),
-        purchasePrice: U256::from(rand::random::<u64>()),
+        purchasePrice: purchase_price,
         collateralToken: Address::random(),
-        collateralPrice: U256::from(rand::random::<u64>()),
-        dayCount: U256::from(rand::random::<u64>()),
+        collateralPrice: collateral_price,
+        dayCount: U256::from(rand::random::<u32>() % MAX_DAY_COUNT + 1),
+        dayCountConvention: DayCountConvention::Thirty360,
+        auctionMode: AuctionMode::SecondPriceMidpoint,
     };
 
+    let random_amount =
+        || U256::from(rand::random::<u64>() % 1_000_000 + u64::from(MIN_ORDER_AMOUNT));
+    let random_price = |min: u64, max: u64| {
+        bps_to_internal(&U256::from(rand::random::<u64>() % (max - min) + min))
+    };
+
+    let mut auction_builder: AuctionBuilder = AuctionBuilder::new(tokens);
+    for _ in 0..num_orders {
+        let amount: U256 = random_amount();
+        // Rounded up, so that the collateral value never falls short of twice the amount value
+        let collateral_amount: U256 =
+            (amount * purchase_price * U256::from(2)).div_ceil(collateral_price);
+        auction_builder = auction_builder
+            .add_bid(random_price(100, 2_000), amount, collateral_amount)
+            .add_offer(random_price(50, 1_500), random_amount());
+    }
+    let AuctionInputs {
+        bid_submissions,
+        offer_submissions,
+        bid_reveals,
+        offer_reveals,
+        auction_parameters: tokens,
+    } = auction_builder.build(&|x: &[u8]| keccak256(x));
+
     stdin.write(&prover_address);
     stdin.write(&bid_submissions);
     stdin.write(&offer_submissions);